@@ -0,0 +1,283 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::api_key::{ApiKey, ApiKeyScope, CreatedApiKey, NewApiKey};
+use crate::limits::RateLimit;
+use crate::sessions::Sessions;
+use crate::AppState;
+
+// Every key starts with this, which makes leaked keys easy to search for
+const TOKEN_PREFIX: &str = "cnz_";
+
+// Characters of the key kept in the clear to tell keys apart
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+// `last_used_at` is only rewritten when it is older than this
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+// Read from Rocket's configuration, e.g. `ROCKET_API_KEYS_REQUIRED=true`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    // Reject requests that don't carry a key or session token, even on read-only routes.
+    // Writes and admin routes always need one.
+    pub api_keys_required: bool,
+    // Users whose sessions get the `admin` scope, e.g. `ROCKET_ADMIN_USERS=["alice"]`.
+    // Everyone else signs in with `write`.
+    pub admin_users: Vec<String>,
+}
+
+// The user and scopes behind a valid key or session token
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub user: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+impl Principal {
+    pub fn allows(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|granted| *granted >= scope)
+    }
+}
+
+// What the `Authorization` header of a request turned out to hold
+enum Credentials {
+    Anonymous,
+    Valid(Principal),
+    Invalid,
+}
+
+// SHA-256 of a secret token, as stored in place of the token itself
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn new_token() -> String {
+    format!("{}{}{}", TOKEN_PREFIX, uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+async fn verify(state: &AppState, token: &str) -> Result<Option<Principal>, surrealdb::Error> {
+    let keys: Vec<ApiKey> = state.db.query("SELECT * FROM api_key WHERE key_hash = $hash LIMIT 1")
+        .bind(("hash", hash_token(token)))
+        .await?
+        .take(0)?;
+    let now = Utc::now();
+    let Some(key) = keys.into_iter().find(|key| key.is_active(now)) else {
+        return Ok(None);
+    };
+    let Some(id) = key.id.clone() else {
+        return Ok(None);
+    };
+
+    let recent = matches!(key.last_used_at, Some(at) if now - at < Duration::seconds(LAST_USED_RESOLUTION_SECONDS));
+    if !recent {
+        let _: Option<ApiKey> = state.db.update(("api_key", id.as_str()))
+            .merge(serde_json::json!({ "last_used_at": now }))
+            .await?;
+    }
+
+    Ok(Some(Principal { user: key.user, scopes: key.scopes }))
+}
+
+async fn credentials<'r>(request: &'r Request<'_>) -> &'r Credentials {
+    request.local_cache_async(async {
+        let Some(header) = request.headers().get_one("Authorization") else {
+            return Credentials::Anonymous;
+        };
+        let Some(token) = header.strip_prefix("Bearer ").map(str::trim) else {
+            return Credentials::Invalid;
+        };
+        // Anything that isn't an API key is a session token from signing in
+        if !token.starts_with(TOKEN_PREFIX) {
+            let user = request.rocket()
+                .state::<Arc<Sessions>>()
+                .and_then(|sessions| sessions.verify(token));
+            let Some(user) = user else {
+                return Credentials::Invalid;
+            };
+            let admin = request.rocket()
+                .state::<Arc<ApiKeyConfig>>()
+                .is_some_and(|config| config.admin_users.contains(&user));
+            let scope = if admin { ApiKeyScope::Admin } else { ApiKeyScope::Write };
+            return Credentials::Valid(Principal { user, scopes: vec![scope] });
+        }
+        let Some(state) = request.rocket().state::<Arc<AppState>>() else {
+            return Credentials::Invalid;
+        };
+
+        match verify(state, token).await {
+            Ok(Some(principal)) => Credentials::Valid(principal),
+            Ok(None) => Credentials::Invalid,
+            Err(e) => {
+                error!("Failed to look up API key: {}", e);
+                Credentials::Invalid
+            },
+        }
+    }).await
+}
+
+// The user behind the request's key, if it carries a valid one
+pub async fn principal<'r>(request: &'r Request<'_>) -> Option<&'r Principal> {
+    match credentials(request).await {
+        Credentials::Valid(principal) => Some(principal),
+        _ => None,
+    }
+}
+
+// The scope a route needs
+pub trait RequiredScope {
+    const SCOPE: ApiKeyScope;
+}
+
+pub struct CanRead;
+
+impl RequiredScope for CanRead {
+    const SCOPE: ApiKeyScope = ApiKeyScope::Read;
+}
+
+pub struct CanWrite;
+
+impl RequiredScope for CanWrite {
+    const SCOPE: ApiKeyScope = ApiKeyScope::Write;
+}
+
+pub struct CanAdmin;
+
+impl RequiredScope for CanAdmin {
+    const SCOPE: ApiKeyScope = ApiKeyScope::Admin;
+}
+
+// Request guard for routes that need scope `S`. A missing or unknown key is 401 and a
+// key without the scope is 403. Requests without a key only pass read-only routes, and
+// only while keys aren't required.
+pub struct Authorized<S> {
+    pub principal: Option<Principal>,
+    scope: PhantomData<S>,
+}
+
+#[rocket::async_trait]
+impl<'r, S: RequiredScope> FromRequest<'r> for Authorized<S> {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match credentials(request).await {
+            Credentials::Valid(principal) if principal.allows(S::SCOPE) => {
+                Outcome::Success(Authorized { principal: Some(principal.clone()), scope: PhantomData })
+            },
+            Credentials::Valid(_) => Outcome::Error((Status::Forbidden, ())),
+            Credentials::Invalid => Outcome::Error((Status::Unauthorized, ())),
+            Credentials::Anonymous => {
+                let required = S::SCOPE > ApiKeyScope::Read || request.rocket()
+                    .state::<Arc<ApiKeyConfig>>()
+                    .is_some_and(|config| config.api_keys_required);
+                if required {
+                    Outcome::Error((Status::Unauthorized, ()))
+                } else {
+                    Outcome::Success(Authorized { principal: None, scope: PhantomData })
+                }
+            },
+        }
+    }
+}
+
+// Keys belong to whoever is signed in or holds the key making the request; nobody
+// else can list, create or revoke them
+fn owner<S>(auth: &Authorized<S>) -> Result<&Principal, Status> {
+    auth.principal.as_ref().ok_or(Status::Unauthorized)
+}
+
+fn redacted(key: ApiKey) -> ApiKey {
+    ApiKey { key_hash: None, ..key }
+}
+
+// Newest first, including expired and revoked keys
+#[get("/keys")]
+pub async fn list_keys(auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Json<Vec<ApiKey>>, Status> {
+    let user = owner(&auth)?.user.clone();
+    let keys: Vec<ApiKey> = state.db.query("SELECT * FROM api_key WHERE user = $user ORDER BY created_at DESC")
+        .bind(("user", user))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(keys.into_iter().map(redacted).collect()))
+}
+
+// The response is the only time the key itself is shown. A key can't be given a
+// scope the credentials creating it don't have.
+#[post("/keys", data = "<key>")]
+pub async fn create_key(key: Json<NewApiKey>, auth: Authorized<CanWrite>, _rate: RateLimit, state: &State<Arc<AppState>>) -> Result<Json<CreatedApiKey>, Status> {
+    let key = key.into_inner();
+    let principal = owner(&auth)?;
+    let now = Utc::now();
+    key.validate(now).map_err(|_| Status::UnprocessableEntity)?;
+    if !key.scopes.iter().all(|scope| principal.allows(*scope)) {
+        return Err(Status::Forbidden);
+    }
+    let user = principal.user.clone();
+    let name = key.name.trim().to_string();
+
+    let mut scopes = key.scopes;
+    scopes.sort();
+    scopes.dedup();
+
+    let token = new_token();
+    let record = ApiKey {
+        id: None,
+        name,
+        user,
+        prefix: token[..DISPLAY_PREFIX_LEN].to_string(),
+        key_hash: Some(hash_token(&token)),
+        scopes,
+        created_at: now,
+        expires_at: key.expires_at,
+        last_used_at: None,
+        revoked_at: None,
+    };
+    let created: Vec<ApiKey> = state.db.create("api_key")
+        .content(&record)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let created = created.into_iter().next().ok_or(Status::InternalServerError)?;
+
+    Ok(Json(CreatedApiKey { key: redacted(created), token }))
+}
+
+// Revoked keys stop working at once but stay listed
+#[delete("/keys/<id>")]
+pub async fn revoke_key(id: &str, auth: Authorized<CanWrite>, _rate: RateLimit, state: &State<Arc<AppState>>) -> Result<Json<ApiKey>, Status> {
+    let user = owner(&auth)?.user.clone();
+    let key: Option<ApiKey> = state.db.select(("api_key", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let key = key.filter(|key| key.user == user).ok_or(Status::NotFound)?;
+    if key.revoked_at.is_some() {
+        return Ok(Json(redacted(key)));
+    }
+
+    let revoked: Option<ApiKey> = state.db.update(("api_key", id))
+        .merge(serde_json::json!({ "revoked_at": Utc::now() }))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let revoked = revoked.ok_or(Status::NotFound)?;
+
+    tracing::info!(key = %id, user = %revoked.user, "API key revoked");
+    Ok(Json(redacted(revoked)))
+}
+
+// Load the API key configuration at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("API Keys", |rocket| async {
+        let config: ApiKeyConfig = rocket.figment().extract().unwrap_or_default();
+        rocket.manage(Arc::new(config))
+    })
+}
@@ -0,0 +1,113 @@
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::Json;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+
+use crate::api_keys::{Authorized, CanRead, CanWrite};
+use crate::id::NoteId;
+use crate::limits::{AttachmentBody, LimitedJson, RateLimit};
+use crate::sessions::UserDb;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+
+// A file stored alongside a note; `data` holds the base64 encoded bytes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub note_id: NoteId,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    pub fn new(note_id: NoteId, file_name: String, content_type: String, bytes: &[u8]) -> Self {
+        Self {
+            id: None,
+            note_id,
+            file_name,
+            content_type,
+            size: bytes.len() as u64,
+            data: BASE64.encode(bytes),
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        BASE64.decode(&self.data).ok()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: String,
+}
+
+pub async fn attachments_for(db: &Surreal<Client>, note_ids: &[NoteId]) -> Result<Vec<Attachment>, surrealdb::Error> {
+    db.query("SELECT * FROM attachment WHERE note_id INSIDE $note_ids ORDER BY created_at")
+        .bind(("note_ids", note_ids))
+        .await?
+        .take(0)
+}
+
+pub async fn delete_attachments(db: &Surreal<Client>, note_id: &str) -> Result<(), surrealdb::Error> {
+    db.query("DELETE attachment WHERE note_id = $note_id")
+        .bind(("note_id", note_id))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+#[get("/notes/<id>/attachments")]
+pub async fn get_attachments(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>) -> Result<Json<Vec<Attachment>>, Status> {
+    workspace.find_note(&db, id).await?;
+    let attachments = attachments_for(&db, &[id.into()])
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(attachments))
+}
+
+#[post("/notes/<id>/attachments", data = "<attachment>")]
+pub async fn create_attachment(id: &str, attachment: LimitedJson<NewAttachment, AttachmentBody>, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>) -> Result<Json<Attachment>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    workspace.find_note(&db, id).await?;
+
+    let attachment = attachment.into_inner();
+    let bytes = BASE64.decode(&attachment.data)
+        .map_err(|_| Status::BadRequest)?;
+    let new_attachment = Attachment::new(id.into(), attachment.file_name, attachment.content_type, &bytes);
+
+    let created: Option<Attachment> = db.create("attachment")
+        .content(&new_attachment)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Visible but not writable, e.g. a workspace note the user has no share on
+    created.map(Json).ok_or(Status::Forbidden)
+}
+
+#[get("/attachments/<id>")]
+pub async fn download_attachment(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>) -> Result<(ContentType, Vec<u8>), Status> {
+    let attachment: Option<Attachment> = db.select(("attachment", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let attachment = attachment.ok_or(Status::NotFound)?;
+    workspace.find_note(&db, &attachment.note_id).await?;
+
+    let content_type = ContentType::parse_flexible(&attachment.content_type)
+        .unwrap_or(ContentType::Binary);
+    let bytes = attachment.bytes().ok_or(Status::InternalServerError)?;
+
+    Ok((content_type, bytes))
+}
@@ -0,0 +1,222 @@
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::Json;
+use rocket::Request;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+
+use crate::api_keys::{Authorized, CanAdmin};
+use crate::api_keys;
+use crate::export::ExportFile;
+use crate::note::Note;
+use crate::sessions::UserDb;
+use crate::AppState;
+
+// Recorded when a request names nobody
+const UNKNOWN_ACTOR: &str = "unknown";
+
+// Upper bound on events returned by one query
+const MAX_EVENTS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Share,
+    Import,
+}
+
+impl AuditAction {
+    fn parse(action: &str) -> Option<Self> {
+        match action {
+            "create" => Some(Self::Create),
+            "update" => Some(Self::Update),
+            "delete" => Some(Self::Delete),
+            "share" => Some(Self::Share),
+            "import" => Some(Self::Import),
+            _ => None,
+        }
+    }
+}
+
+// Who made a request and from where. `actor` is only ever the user behind a verified
+// session token or API key.
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    // The named actor, falling back to the given user (usually the note's owner)
+    pub fn actor_or(&self, fallback: &str) -> String {
+        self.actor.clone().unwrap_or_else(|| fallback.to_string())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            actor: api_keys::principal(request).await.map(|principal| principal.user.clone()),
+            ip: request.client_ip().map(|ip| ip.to_string()),
+            user_agent: request.headers().get_one("User-Agent").map(str::to_string),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub actor: String,
+    pub action: AuditAction,
+    pub note_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_version: Option<u64>,
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    // Lets members read, and listeners follow, only changes in their own workspaces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+}
+
+impl AuditEvent {
+    // Describe a change to a note from the versions before and after it
+    pub fn new(client: &ClientInfo, action: AuditAction, before: Option<&Note>, after: Option<&Note>) -> Self {
+        let note = after.or(before);
+        Self {
+            id: None,
+            actor: client.actor_or(note.map(|note| note.created_by.as_str()).unwrap_or(UNKNOWN_ACTOR)),
+            action,
+            note_id: note.and_then(|note| note.id.clone()).map(String::from).unwrap_or_default(),
+            before_version: before.map(|note| note.version),
+            after_version: after.map(|note| note.version),
+            at: Utc::now(),
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            workspace_id: note.and_then(|note| note.workspace_id.clone()).map(String::from),
+        }
+    }
+}
+
+// Changing who a note is shared with is recorded as a share rather than an edit
+pub fn update_action(before: &Note, after: &Note) -> AuditAction {
+    if before.is_shared != after.is_shared || before.shared_with != after.shared_with {
+        AuditAction::Share
+    } else {
+        AuditAction::Update
+    }
+}
+
+// Append an event and pass it on to anything following changes, such as webhooks.
+// The change it describes has already happened, so a failure here is logged rather
+// than turned into an error for the client.
+pub async fn record(state: &AppState, event: AuditEvent) {
+    let created: Result<Option<AuditEvent>, surrealdb::Error> = state.db.create("audit_event")
+        .content(&event)
+        .await;
+
+    if let Err(e) = created {
+        error!("Failed to record audit event {:?} on note {}: {}", event.action, event.note_id, e);
+    }
+
+    // Nobody listening is not an error
+    let _ = state.changes.send(event);
+}
+
+#[derive(Debug, FromForm)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub note: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn parse_time(value: Option<&str>) -> Result<Option<DateTime<Utc>>, Status> {
+    value
+        .map(|value| DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc)))
+        .transpose()
+        .map_err(|_| Status::BadRequest)
+}
+
+// Runs as the caller, so the schema only hands back events from their workspaces
+async fn query_events(db: &Surreal<Client>, filter: &AuditFilter) -> Result<Vec<AuditEvent>, Status> {
+    let action = filter.action.as_deref()
+        .map(|action| AuditAction::parse(action).ok_or(Status::BadRequest))
+        .transpose()?;
+    let since = parse_time(filter.since.as_deref())?;
+    let until = parse_time(filter.until.as_deref())?;
+
+    let mut conditions = Vec::new();
+    if filter.actor.is_some() {
+        conditions.push("actor = $actor");
+    }
+    if action.is_some() {
+        conditions.push("action = $action");
+    }
+    if filter.note.is_some() {
+        conditions.push("note_id = $note");
+    }
+    if since.is_some() {
+        conditions.push("at >= $since");
+    }
+    if until.is_some() {
+        conditions.push("at <= $until");
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    let sql = format!("SELECT * FROM audit_event{} ORDER BY at DESC LIMIT $limit", where_clause);
+    db.query(sql)
+        .bind(("actor", filter.actor.clone()))
+        .bind(("action", action))
+        .bind(("note", filter.note.clone()))
+        .bind(("since", since))
+        .bind(("until", until))
+        .bind(("limit", filter.limit.unwrap_or(MAX_EVENTS).min(MAX_EVENTS)))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)
+}
+
+// Newest first; all filters are optional and `since`/`until` take RFC 3339 timestamps
+#[get("/admin/audit?<filter..>")]
+pub async fn list_events(filter: AuditFilter, db: UserDb, _auth: Authorized<CanAdmin>) -> Result<Json<Vec<AuditEvent>>, Status> {
+    query_events(&db, &filter).await.map(Json)
+}
+
+// The same events as JSON Lines, one event per line
+#[get("/admin/audit/export?<filter..>")]
+pub async fn export_events(filter: AuditFilter, db: UserDb, _auth: Authorized<CanAdmin>) -> Result<ExportFile, Status> {
+    let events = query_events(&db, &filter).await?;
+
+    let mut body = String::new();
+    for event in &events {
+        body.push_str(&serde_json::to_string(event).map_err(|_| Status::InternalServerError)?);
+        body.push('\n');
+    }
+
+    Ok(ExportFile {
+        file_name: format!("audit-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S")),
+        content_type: ContentType::new("application", "x-ndjson"),
+        body: body.into_bytes(),
+    })
+}
@@ -0,0 +1,407 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::tokio::fs;
+use rocket::tokio::process::Command;
+use rocket::tokio::select;
+use rocket::tokio::sync::Mutex;
+use rocket::tokio::time::{interval, Duration};
+use rocket::State;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::engine::local::Mem;
+use surrealdb::Surreal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanAdmin};
+use crate::sessions::{define_token, Sessions};
+use crate::AppState;
+
+// Credentials used by the `surreal export` CLI, which connects over HTTP to the
+// server the API uses; the SDK can't export over WebSocket
+const SURREAL_USER: &str = "root";
+const SURREAL_PASS: &str = "root";
+const NAMESPACE: &str = "cosmiqnotz";
+const DATABASE: &str = "cosmiqnotz";
+
+const BACKUP_PREFIX: &str = "cosmiqnotz-";
+const BACKUP_EXTENSION: &str = "surql";
+const MANIFEST_EXTENSION: &str = "json";
+
+// Where the platform keeps application data: `%APPDATA%` on Windows,
+// `~/Library/Application Support` on macOS and `$XDG_DATA_HOME` or `~/.local/share` elsewhere
+fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local").join("share")))
+    }
+}
+
+// Read from Rocket's configuration, e.g. `ROCKET_BACKUP_DIR` or `backup_keep` in Rocket.toml.
+// Without a platform data directory `backup_dir` is left empty and has to be set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub backup_dir: PathBuf,
+    pub backup_keep: usize,
+    pub backup_max_age_days: i64,
+    pub backup_interval_hours: u64,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            backup_dir: data_dir().map(|dir| dir.join("cosmiqnotz").join("backups")).unwrap_or_default(),
+            backup_keep: 10,
+            backup_max_age_days: 30,
+            backup_interval_hours: 6,
+        }
+    }
+}
+
+pub struct Backups {
+    config: BackupConfig,
+    // Snapshots and restores must never run concurrently
+    lock: Mutex<()>,
+}
+
+// Stored next to every snapshot so it can be verified before restoring
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+    pub sha256: String,
+    pub note_count: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub name: String,
+    pub checksum_ok: bool,
+    pub loadable: bool,
+    pub note_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VerifyReport {
+    fn is_ok(&self) -> bool {
+        self.checksum_ok && self.loadable
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub restored: String,
+    pub safety_backup: BackupInfo,
+    pub verification: VerifyReport,
+}
+
+impl Backups {
+    fn snapshot_path(&self, name: &str) -> PathBuf {
+        self.config.backup_dir.join(format!("{}.{}", name, BACKUP_EXTENSION))
+    }
+
+    fn manifest_path(&self, name: &str) -> PathBuf {
+        self.config.backup_dir.join(format!("{}.{}", name, MANIFEST_EXTENSION))
+    }
+}
+
+// Backup names come from URLs; only accept the ones we generate
+fn valid_name(name: &str) -> bool {
+    name.starts_with(BACKUP_PREFIX)
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+async fn count_notes<C: surrealdb::Connection>(db: &Surreal<C>) -> Result<Option<u64>, surrealdb::Error> {
+    db.query("SELECT count() FROM note GROUP ALL")
+        .await?
+        .take((0, "count"))
+}
+
+async fn export_database(address: &str, path: &Path) -> Result<(), String> {
+    let output = Command::new("surreal")
+        .arg("export")
+        .arg("--conn")
+        .arg(format!("http://{}", address))
+        .arg("--user")
+        .arg(SURREAL_USER)
+        .arg("--pass")
+        .arg(SURREAL_PASS)
+        .arg("--ns")
+        .arg(NAMESPACE)
+        .arg("--db")
+        .arg(DATABASE)
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run surreal export: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("surreal export failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+// The export includes the scope's token definition, secret and all; snapshots must not
+fn without_token_secrets(sql: &str) -> String {
+    sql.lines()
+        .filter(|line| !line.trim_start().starts_with("DEFINE TOKEN"))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+async fn read_manifest(backups: &Backups, name: &str) -> Option<BackupInfo> {
+    let json = fs::read_to_string(backups.manifest_path(name)).await.ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+pub async fn list(backups: &Backups) -> Result<Vec<BackupInfo>, String> {
+    let mut entries = match fs::read_dir(&backups.config.backup_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read backup directory: {}", e)),
+    };
+
+    let mut infos = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(MANIFEST_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if let Some(info) = read_manifest(backups, name).await {
+            infos.push(info);
+        }
+    }
+
+    // Newest first
+    infos.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+    Ok(infos)
+}
+
+// Drop snapshots beyond the configured count or older than the configured age
+async fn rotate(backups: &Backups) -> Result<(), String> {
+    let cutoff = Utc::now() - chrono::Duration::days(backups.config.backup_max_age_days);
+
+    for (index, info) in list(backups).await?.iter().enumerate() {
+        if index >= backups.config.backup_keep || info.created_at < cutoff {
+            fs::remove_file(backups.snapshot_path(&info.name)).await.ok();
+            fs::remove_file(backups.manifest_path(&info.name)).await.ok();
+        }
+    }
+
+    Ok(())
+}
+
+async fn snapshot(state: &AppState, backups: &Backups, label: Option<&str>) -> Result<BackupInfo, String> {
+    fs::create_dir_all(&backups.config.backup_dir)
+        .await
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let created_at = Utc::now();
+    let mut name = format!("{}{}", BACKUP_PREFIX, created_at.format("%Y%m%dT%H%M%S%3fZ"));
+    if let Some(label) = label {
+        name.push('-');
+        name.push_str(label);
+    }
+
+    // Export to a temporary file first so a half-written snapshot is never listed
+    let path = backups.snapshot_path(&name);
+    let partial = path.with_extension("partial");
+    export_database(&state.db_address, &partial).await?;
+
+    let sql = fs::read_to_string(&partial)
+        .await
+        .map_err(|e| format!("Failed to read snapshot: {}", e))?;
+    let bytes = without_token_secrets(&sql).into_bytes();
+    fs::write(&partial, &bytes)
+        .await
+        .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+    let info = BackupInfo {
+        name: name.clone(),
+        created_at,
+        size: bytes.len() as u64,
+        sha256: sha256_hex(&bytes),
+        note_count: count_notes(&state.db).await.ok().flatten(),
+    };
+
+    fs::rename(&partial, &path)
+        .await
+        .map_err(|e| format!("Failed to store snapshot: {}", e))?;
+    let manifest = serde_json::to_string_pretty(&info)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(backups.manifest_path(&name), manifest)
+        .await
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    rotate(backups).await?;
+    Ok(info)
+}
+
+// Check the checksum recorded at backup time, then load the snapshot into a
+// throwaway in-memory database to prove it can actually be restored
+async fn verify(backups: &Backups, name: &str) -> Result<VerifyReport, Status> {
+    let info = read_manifest(backups, name).await.ok_or(Status::NotFound)?;
+    let bytes = fs::read(backups.snapshot_path(name)).await.map_err(|_| Status::NotFound)?;
+
+    let mut report = VerifyReport {
+        name: name.to_string(),
+        checksum_ok: bytes.len() as u64 == info.size && sha256_hex(&bytes) == info.sha256,
+        loadable: false,
+        note_count: None,
+        error: None,
+    };
+    if !report.checksum_ok {
+        report.error = Some("Checksum does not match the manifest".to_string());
+        return Ok(report);
+    }
+
+    let loaded = async {
+        let sql = String::from_utf8(bytes).map_err(|_| "Snapshot is not valid UTF-8".to_string())?;
+        let db = Surreal::new::<Mem>(()).await.map_err(|e| e.to_string())?;
+        db.use_ns(NAMESPACE).use_db(DATABASE).await.map_err(|e| e.to_string())?;
+        db.query(sql).await.and_then(|response| response.check()).map_err(|e| e.to_string())?;
+        count_notes(&db).await.map_err(|e| e.to_string())
+    }
+    .await;
+
+    match loaded {
+        Ok(note_count) => {
+            report.loadable = true;
+            report.note_count = note_count.or(Some(0));
+        },
+        Err(e) => report.error = Some(format!("Snapshot failed to load: {}", e)),
+    }
+
+    Ok(report)
+}
+
+// Load configuration at ignite and run scheduled snapshots once the server is live
+pub fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Backups", |rocket| async {
+        let config: BackupConfig = rocket.figment().extract().unwrap_or_default();
+        if config.backup_dir.as_os_str().is_empty() {
+            error!("No data directory to keep backups in; set ROCKET_BACKUP_DIR");
+            return Err(rocket);
+        }
+        let rocket = rocket.manage(Arc::new(Backups { config, lock: Mutex::new(()) }));
+
+        Ok(rocket.attach(AdHoc::on_liftoff("Backup Scheduler", |rocket| Box::pin(async move {
+            let (Some(state), Some(backups)) = (rocket.state::<Arc<AppState>>().cloned(), rocket.state::<Arc<Backups>>().cloned()) else {
+                error!("Backup scheduler could not find application state");
+                return;
+            };
+            let hours = backups.config.backup_interval_hours;
+            if hours == 0 {
+                return;
+            }
+
+            let mut shutdown = rocket.shutdown();
+            rocket::tokio::spawn(async move {
+                let mut ticker = interval(Duration::from_secs(hours * 60 * 60));
+                loop {
+                    select! {
+                        _ = ticker.tick() => {
+                            let _guard = backups.lock.lock().await;
+                            if let Err(e) = snapshot(&state, &backups, None).await {
+                                error!("Scheduled backup failed: {}", e);
+                            }
+                        },
+                        _ = &mut shutdown => break,
+                    }
+                }
+            });
+        }))))
+    })
+}
+
+#[get("/admin/backups")]
+pub async fn list_backups(_auth: Authorized<CanAdmin>, backups: &State<Arc<Backups>>) -> Result<Json<Vec<BackupInfo>>, Status> {
+    list(backups)
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[post("/admin/backups")]
+pub async fn create_backup(_auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>, backups: &State<Arc<Backups>>) -> Result<Json<BackupInfo>, Status> {
+    let _guard = backups.lock.lock().await;
+    snapshot(state, backups, None)
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[post("/admin/backups/<name>/verify")]
+pub async fn verify_backup(name: &str, _auth: Authorized<CanAdmin>, backups: &State<Arc<Backups>>) -> Result<Json<VerifyReport>, Status> {
+    if !valid_name(name) {
+        return Err(Status::BadRequest);
+    }
+
+    verify(backups, name).await.map(Json)
+}
+
+// Restore replaces the whole database. The snapshot is verified first and the
+// current state is saved as a `pre-restore` backup so a restore can be undone.
+#[post("/admin/backups/<name>/restore")]
+pub async fn restore_backup(name: &str, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>, backups: &State<Arc<Backups>>, sessions: &State<Arc<Sessions>>) -> Result<Json<RestoreReport>, (Status, Json<VerifyReport>)> {
+    let failed = |status: Status, error: &str| (status, Json(VerifyReport {
+        name: name.to_string(),
+        checksum_ok: false,
+        loadable: false,
+        note_count: None,
+        error: Some(error.to_string()),
+    }));
+
+    if !valid_name(name) {
+        return Err(failed(Status::BadRequest, "Invalid backup name"));
+    }
+
+    let _guard = backups.lock.lock().await;
+    let verification = verify(backups, name)
+        .await
+        .map_err(|status| failed(status, "Backup not found"))?;
+    if !verification.is_ok() {
+        return Err((Status::UnprocessableEntity, Json(verification)));
+    }
+
+    // Read before taking the safety backup, whose rotation may remove this snapshot
+    let sql = fs::read_to_string(backups.snapshot_path(name))
+        .await
+        .map_err(|e| failed(Status::InternalServerError, &e.to_string()))?;
+
+    let safety_backup = snapshot(state, backups, Some("pre-restore"))
+        .await
+        .map_err(|e| failed(Status::InternalServerError, &e))?;
+
+    let restored = async {
+        state.db.query(format!("REMOVE DATABASE {}", DATABASE)).await?.check()?;
+        state.db.use_ns(NAMESPACE).use_db(DATABASE).await?;
+        state.db.query(sql).await?.check()?;
+        define_token(&state.db, sessions).await?;
+        Ok::<_, surrealdb::Error>(())
+    }
+    .await;
+    restored.map_err(|e| failed(Status::InternalServerError, &format!("Restore failed: {}", e)))?;
+
+    Ok(Json(RestoreReport {
+        restored: name.to_string(),
+        safety_backup,
+        verification,
+    }))
+}
@@ -0,0 +1,160 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanWrite};
+use crate::audit::{self, AuditAction, AuditEvent, ClientInfo};
+use crate::batch::{BatchAction, BatchItemResult, BatchItemStatus, BatchReport, BatchRequest};
+use crate::id::NoteId;
+use crate::limits::RateLimit;
+use crate::sessions::UserDb;
+use crate::note::Note;
+use crate::tasks;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+// Upper bound on operations in one batch
+const MAX_OPERATIONS: usize = 500;
+
+fn clean(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+// Apply one operation to the working copy of a note
+fn apply(note: &mut Note, action: &BatchAction, now: DateTime<Utc>) -> Result<(), String> {
+    match action {
+        BatchAction::Delete => {
+            if !note.is_trashed() {
+                note.deleted_at = Some(now);
+            }
+        },
+        BatchAction::Restore => note.deleted_at = None,
+        BatchAction::AddTag { tag } => {
+            let tag = clean(tag).ok_or_else(|| "Tag is empty".to_string())?;
+            if !note.tags.contains(&tag) {
+                note.tags.push(tag);
+            }
+        },
+        BatchAction::RemoveTag { tag } => {
+            let tag = clean(tag).ok_or_else(|| "Tag is empty".to_string())?;
+            note.tags.retain(|existing| *existing != tag);
+        },
+        BatchAction::Move { notebook } => note.notebook = notebook.as_deref().and_then(clean),
+        BatchAction::Share { shared_with } => {
+            let mut users: Vec<String> = shared_with.iter().filter_map(|user| clean(user)).collect();
+            users.sort();
+            users.dedup();
+            note.is_shared = !users.is_empty();
+            note.shared_with = users;
+        },
+    }
+    Ok(())
+}
+
+fn audit_action(before: &Note, after: &Note) -> AuditAction {
+    match (before.is_trashed(), after.is_trashed()) {
+        (false, true) => AuditAction::Delete,
+        (true, false) => AuditAction::Update,
+        _ => audit::update_action(before, after),
+    }
+}
+
+// Apply a list of operations in one transaction. Every operation is checked first;
+// if any of them can't be applied nothing is written and the report says which.
+#[post("/notes/batch", data = "<request>")]
+pub async fn batch(request: Json<BatchRequest>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<BatchReport>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let operations = request.into_inner().operations;
+    if operations.is_empty() || operations.len() > MAX_OPERATIONS {
+        return Err(Status::BadRequest);
+    }
+
+    let mut originals: HashMap<NoteId, Note> = HashMap::new();
+    for operation in &operations {
+        if originals.contains_key(&operation.note_id) {
+            continue;
+        }
+        let note: Option<Note> = db.select(("note", operation.note_id.as_str()))
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        // Notes in other workspaces count as missing
+        if let Some(note) = note.filter(|note| workspace.contains(note)) {
+            originals.insert(operation.note_id.clone(), note);
+        }
+    }
+
+    let now = Utc::now();
+    let mut working = originals.clone();
+    let mut results = Vec::with_capacity(operations.len());
+    for (index, operation) in operations.iter().enumerate() {
+        let (status, message) = match working.get_mut(&operation.note_id) {
+            None => (BatchItemStatus::NotFound, None),
+            Some(note) if note.is_locked_for(&workspace.user, now) => {
+                (BatchItemStatus::Invalid, Some("Someone else is editing the note".to_string()))
+            }
+            Some(note) => match apply(note, &operation.action, now) {
+                Ok(()) => (BatchItemStatus::Ok, None),
+                Err(message) => (BatchItemStatus::Invalid, Some(message)),
+            },
+        };
+        results.push(BatchItemResult {
+            index,
+            note_id: operation.note_id.clone(),
+            status,
+            message,
+        });
+    }
+
+    if results.iter().any(|result| result.status != BatchItemStatus::Ok) {
+        for result in results.iter_mut().filter(|result| result.status == BatchItemStatus::Ok) {
+            result.status = BatchItemStatus::Skipped;
+        }
+        return Ok(Json(BatchReport { committed: false, results, notes: Vec::new() }));
+    }
+
+    // Only notes that actually changed are written, each with a new version
+    let mut changed: Vec<Note> = working.into_iter()
+        .filter(|(id, note)| originals.get(id) != Some(note))
+        .map(|(_, note)| note)
+        .collect();
+    for note in &mut changed {
+        note.version += 1;
+        note.updated_at = now;
+    }
+
+    if !changed.is_empty() {
+        let mut sql = String::from("BEGIN TRANSACTION;");
+        for index in 0..changed.len() {
+            sql.push_str(&format!(" UPDATE type::thing('note', $id{index}) CONTENT $note{index};"));
+        }
+        sql.push_str(" COMMIT TRANSACTION;");
+
+        let mut query = db.query(sql);
+        for (index, note) in changed.iter().enumerate() {
+            query = query
+                .bind((format!("id{}", index), note.id.clone()))
+                .bind((format!("note{}", index), note.clone()));
+        }
+        state.metrics.time_query("batch_update_notes", query)
+            .await
+            .and_then(|response| response.check())
+            .map_err(|_| Status::InternalServerError)?;
+    }
+
+    for note in &changed {
+        if let Err(e) = tasks::sync_tasks(&db, note).await {
+            error!("Failed to sync tasks after batch update: {}", e);
+        }
+        if let Some(before) = note.id.as_ref().and_then(|id| originals.get(id)) {
+            let action = audit_action(before, note);
+            audit::record(state, AuditEvent::new(&client, action, Some(before), Some(note))).await;
+        }
+    }
+
+    Ok(Json(BatchReport { committed: true, results, notes: changed }))
+}
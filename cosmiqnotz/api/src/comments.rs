@@ -0,0 +1,182 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+
+use crate::api_keys::{Authorized, CanRead, CanWrite};
+use crate::comment::{Comment, NewComment};
+use crate::id::CommentId;
+use crate::limits::RateLimit;
+use crate::sessions::UserDb;
+use crate::workspaces::InWorkspace;
+
+#[derive(Debug, Deserialize)]
+pub struct CommentEdit {
+    pub body: String,
+}
+
+// A comment on a note in the workspace; comments elsewhere are reported as missing
+async fn find_comment(db: &Surreal<Client>, workspace: &InWorkspace, id: &str) -> Result<Comment, Status> {
+    let comment: Option<Comment> = db.select(("comment", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let comment = comment.ok_or(Status::NotFound)?;
+
+    workspace.find_note(db, &comment.note_id).await?;
+    Ok(comment)
+}
+
+pub async fn delete_comments(db: &Surreal<Client>, note_id: &str) -> Result<(), surrealdb::Error> {
+    db.query("DELETE comment WHERE note_id = $note_id")
+        .bind(("note_id", note_id))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+// Encrypting a note drops the quotes its comments hold of the plaintext
+pub async fn drop_anchors(db: &Surreal<Client>, note_id: &str) -> Result<(), surrealdb::Error> {
+    db.query("UPDATE comment SET anchor = NONE WHERE note_id = $note_id AND anchor != NONE")
+        .bind(("note_id", note_id))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+// All comments on a note, oldest first; clients group replies under `parent_id`
+#[get("/notes/<id>/comments?<include_resolved>")]
+pub async fn get_comments(id: &str, include_resolved: Option<bool>, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>) -> Result<Json<Vec<Comment>>, Status> {
+    workspace.find_note(&db, id).await?;
+    let mut comments: Vec<Comment> = db
+        .query("SELECT * FROM comment WHERE note_id = $note_id ORDER BY created_at ASC")
+        .bind(("note_id", id))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    // Resolving a thread hides its replies too
+    if !include_resolved.unwrap_or(true) {
+        let resolved: Vec<CommentId> = comments.iter()
+            .filter(|comment| comment.resolved)
+            .filter_map(|comment| comment.id.clone())
+            .collect();
+        comments.retain(|comment| {
+            !comment.resolved
+                && !matches!(&comment.parent_id, Some(parent) if resolved.contains(parent))
+        });
+    }
+
+    Ok(Json(comments))
+}
+
+#[post("/notes/<id>/comments", data = "<comment>")]
+pub async fn create_comment(id: &str, comment: Json<NewComment>, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>) -> Result<Json<Comment>, Status> {
+    let comment = comment.into_inner();
+    comment.validate().map_err(|_| Status::BadRequest)?;
+
+    // Any member may comment, viewers included. Anchors quote the note, which would
+    // leak the text of an encrypted one.
+    let note = workspace.find_note(&db, id).await?;
+    if note.is_encrypted() && comment.anchor.is_some() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // Replies attach to a thread on the same note; only the thread itself is anchored
+    if let Some(parent_id) = &comment.parent_id {
+        let parent = find_comment(&db, &workspace, parent_id).await.map_err(|status| {
+            if status == Status::NotFound { Status::BadRequest } else { status }
+        })?;
+        if parent.note_id != id || parent.parent_id.is_some() || comment.anchor.is_some() {
+            return Err(Status::BadRequest);
+        }
+    }
+
+    let new_comment = Comment::new(id.into(), workspace.user.clone(), comment);
+    let created: Option<Comment> = db.create("comment")
+        .content(&new_comment)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    match created {
+        Some(comment) => Ok(Json(comment)),
+        None => Err(Status::InternalServerError)
+    }
+}
+
+#[put("/comments/<id>", data = "<edit>")]
+pub async fn update_comment(id: &str, edit: Json<CommentEdit>, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>) -> Result<Json<Comment>, Status> {
+    if edit.body.trim().is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    // Only the author rewords a comment
+    let mut comment = find_comment(&db, &workspace, id).await?;
+    if comment.author != workspace.user {
+        return Err(Status::Forbidden);
+    }
+    comment.body = edit.into_inner().body;
+    comment.updated_at = Utc::now();
+
+    let updated: Option<Comment> = db.update(("comment", id))
+        .content(&comment)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    updated.map(Json).ok_or(Status::NotFound)
+}
+
+async fn set_resolved(db: &Surreal<Client>, workspace: &InWorkspace, id: &str, resolved_by: Option<String>) -> Result<Json<Comment>, Status> {
+    let mut comment = find_comment(db, workspace, id).await?;
+
+    // Only whole threads are resolved
+    if comment.parent_id.is_some() {
+        return Err(Status::BadRequest);
+    }
+
+    comment.resolved = resolved_by.is_some();
+    comment.resolved_at = resolved_by.as_ref().map(|_| Utc::now());
+    comment.resolved_by = resolved_by;
+
+    let updated: Option<Comment> = db.update(("comment", id))
+        .content(&comment)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    updated.map(Json).ok_or(Status::NotFound)
+}
+
+#[post("/comments/<id>/resolve")]
+pub async fn resolve_comment(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanWrite>) -> Result<Json<Comment>, Status> {
+    set_resolved(&db, &workspace, id, Some(workspace.user.clone())).await
+}
+
+#[post("/comments/<id>/unresolve")]
+pub async fn unresolve_comment(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanWrite>) -> Result<Json<Comment>, Status> {
+    set_resolved(&db, &workspace, id, None).await
+}
+
+// Only the author deletes a comment; deleting a thread removes its replies with it
+#[delete("/comments/<id>")]
+pub async fn delete_comment(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanWrite>) -> Status {
+    match find_comment(&db, &workspace, id).await {
+        Ok(comment) if comment.author != workspace.user => return Status::Forbidden,
+        Ok(_) => {},
+        Err(status) => return status,
+    }
+
+    let result = db
+        .query("DELETE comment WHERE parent_id = $id; DELETE type::thing('comment', $id) RETURN BEFORE;")
+        .bind(("id", id))
+        .await
+        .and_then(|mut response| response.take::<Vec<Comment>>(1));
+
+    match result {
+        Ok(deleted) if !deleted.is_empty() => Status::NoContent,
+        Ok(_) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
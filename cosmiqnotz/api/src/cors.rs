@@ -0,0 +1,157 @@
+use rocket::fairing::{AdHoc, Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
+use rocket::{Request, Response};
+use serde::Deserialize;
+
+use crate::{telemetry, workspaces};
+
+// Request headers cross-origin clients may send
+const ALLOWED_HEADERS: [&str; 6] = [
+    "Authorization",
+    "Content-Type",
+    "If-Match",
+    "If-None-Match",
+    telemetry::REQUEST_ID_HEADER,
+    workspaces::WORKSPACE_HEADER,
+];
+
+// Response headers cross-origin clients may read
+const EXPOSED_HEADERS: [&str; 3] = ["ETag", "Retry-After", telemetry::REQUEST_ID_HEADER];
+
+// How long browsers may cache a preflight response, in seconds
+const PREFLIGHT_MAX_AGE: u32 = 600;
+
+// Read from Rocket's configuration, e.g. `ROCKET_CORS_ALLOWED_ORIGINS=[...]`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    pub cors_allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            cors_allowed_origins: vec![
+                // The desktop app's webview (Windows uses the https form)
+                "tauri://localhost".to_string(),
+                "https://tauri.localhost".to_string(),
+                // The trunk dev server
+                "http://localhost:8080".to_string(),
+                "http://127.0.0.1:8080".to_string(),
+            ],
+        }
+    }
+}
+
+// Only listed origins are allowed, and each gets its own origin echoed back,
+// since credentials can't be combined with a wildcard
+pub struct Cors {
+    config: CorsConfig,
+}
+
+impl Cors {
+    fn allows(&self, origin: &str) -> bool {
+        self.config.cors_allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+}
+
+// Whether a mounted route path such as `/api/notes/<id>` matches a request path
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/').filter(|segment| !segment.is_empty());
+    let mut path = path.split('/').filter(|segment| !segment.is_empty());
+
+    loop {
+        match (pattern.next(), path.next()) {
+            (Some(segment), _) if segment.starts_with('<') && segment.ends_with("..>") => return true,
+            (Some(segment), Some(_)) if segment.starts_with('<') => continue,
+            (Some(segment), Some(actual)) if segment == actual => continue,
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+// Methods of the routes mounted at the request's path, not counting OPTIONS itself
+fn route_methods(request: &Request<'_>) -> Vec<Method> {
+    let path = request.uri().path();
+    let mut methods: Vec<Method> = request.rocket()
+        .routes()
+        .filter(|route| route.method != Method::Options && path_matches(route.uri.path(), path.as_str()))
+        .map(|route| route.method)
+        .collect();
+    methods.sort_by_key(|method| method.as_str());
+    methods.dedup();
+    methods
+}
+
+#[rocket::async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        // Responses differ by origin, so caches must key on it
+        response.adjoin_header(Header::new("Vary", "Origin"));
+
+        let Some(origin) = request.headers().get_one("Origin") else {
+            return;
+        };
+        if !self.allows(origin) {
+            return;
+        }
+
+        response.set_header(Header::new("Access-Control-Allow-Origin", origin.to_string()));
+        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+        response.set_header(Header::new("Access-Control-Expose-Headers", EXPOSED_HEADERS.join(", ")));
+
+        let Some(requested_method) = request.headers().get_one("Access-Control-Request-Method") else {
+            return;
+        };
+        if request.method() != Method::Options {
+            return;
+        }
+
+        // Preflight: answer with what this particular route accepts
+        let methods = route_methods(request);
+        if !methods.iter().any(|method| method.as_str().eq_ignore_ascii_case(requested_method)) {
+            response.set_status(Status::MethodNotAllowed);
+            return;
+        }
+
+        let requested_headers = request.headers()
+            .get_one("Access-Control-Request-Headers")
+            .unwrap_or_default();
+        let headers: Vec<&str> = requested_headers
+            .split(',')
+            .map(str::trim)
+            .filter(|header| ALLOWED_HEADERS.iter().any(|allowed| allowed.eq_ignore_ascii_case(header)))
+            .collect();
+
+        let methods: Vec<&str> = methods.iter().map(|method| method.as_str()).collect();
+        response.set_header(Header::new("Access-Control-Allow-Methods", methods.join(", ")));
+        if !headers.is_empty() {
+            response.set_header(Header::new("Access-Control-Allow-Headers", headers.join(", ")));
+        }
+        response.set_header(Header::new("Access-Control-Max-Age", PREFLIGHT_MAX_AGE.to_string()));
+        response.adjoin_header(Header::new("Vary", "Access-Control-Request-Method"));
+        response.adjoin_header(Header::new("Vary", "Access-Control-Request-Headers"));
+    }
+}
+
+// Preflight requests for any mounted path; the fairing adds the CORS headers
+#[options("/<_..>")]
+pub fn preflight() -> Status {
+    Status::NoContent
+}
+
+// Load the allowed origins at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("CORS", |rocket| async {
+        let config: CorsConfig = rocket.figment().extract().unwrap_or_default();
+        rocket.attach(Cors { config })
+    })
+}
@@ -0,0 +1,70 @@
+use rocket::http::{Header, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::Request;
+use cosmiqnotz_core::error::{ErrorBody, ErrorCode, ErrorDetail};
+
+use crate::telemetry;
+
+// Seconds a client should wait before retrying, set by guards that reject a request
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetryAfter(pub Option<u64>);
+
+pub struct ApiError {
+    status: Status,
+    body: ErrorBody,
+    retry_after: Option<u64>,
+}
+
+// Machine readable name of a status, e.g. `not_found`. Statuses without a shared
+// code are named after their reason phrase.
+pub fn code(status: Status) -> String {
+    match ErrorCode::from_status(status.code) {
+        Some(code) => code.as_str().to_string(),
+        None => status.reason_lossy().to_lowercase().replace([' ', '-'], "_"),
+    }
+}
+
+fn message(status: Status, retry_after: Option<u64>) -> String {
+    match (status.code, retry_after) {
+        (401, _) => "A valid API key is required in the Authorization header".to_string(),
+        (403, _) => "The API key doesn't have the scope this endpoint needs".to_string(),
+        (413, _) => "The request body is larger than this endpoint accepts".to_string(),
+        (423, _) => "Someone else is editing the note and holds its edit lock".to_string(),
+        (429, Some(seconds)) => format!("Too many requests; retry in {} seconds", seconds),
+        _ => status.reason_lossy().to_string(),
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build_from(Json(self.body).respond_to(request)?);
+        response.status(self.status);
+        if let Some(seconds) = self.retry_after {
+            response.header(Header::new("Retry-After", seconds.to_string()));
+        }
+        if self.status == Status::Unauthorized {
+            response.header(Header::new("WWW-Authenticate", "Bearer"));
+        }
+        response.ok()
+    }
+}
+
+// Turns every error status returned by a route or guard into the structured format
+#[catch(default)]
+pub fn default_catcher(status: Status, request: &Request<'_>) -> ApiError {
+    let retry_after = request.local_cache(RetryAfter::default).0;
+
+    ApiError {
+        status,
+        body: ErrorBody {
+            error: ErrorDetail {
+                status: status.code,
+                code: code(status),
+                message: message(status, retry_after),
+                request_id: telemetry::request_id(request).0,
+            },
+        },
+        retry_after,
+    }
+}
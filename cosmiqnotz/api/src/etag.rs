@@ -0,0 +1,105 @@
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder, Response};
+use rocket::Request;
+use sha2::{Digest, Sha256};
+
+use crate::note::Note;
+
+// A note's entity tag changes whenever its version does
+pub fn note_etag(note: &Note) -> String {
+    format!("\"{}-{}\"", note.id.as_deref().unwrap_or_default(), note.version)
+}
+
+// A list's entity tag covers the id and version of every note in it, in order
+pub fn list_etag(notes: &[Note]) -> String {
+    let mut hasher = Sha256::new();
+    for note in notes {
+        hasher.update(note.id.as_deref().unwrap_or_default().as_bytes());
+        hasher.update(b":");
+        hasher.update(note.version.to_string().as_bytes());
+        hasher.update(b"\n");
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().take(16).map(|byte| format!("{:02x}", byte)).collect();
+    format!("\"list-{}\"", hex)
+}
+
+// Whether a comma separated If-Match / If-None-Match value names `etag`.
+// Weak validators compare equal to strong ones with the same value.
+fn header_matches(header: &str, etag: &str) -> bool {
+    header.split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+// The `If-None-Match` header, if the client sent one
+pub struct IfNoneMatch(Option<String>);
+
+impl IfNoneMatch {
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0.as_deref().is_some_and(|header| header_matches(header, etag))
+    }
+}
+
+// The `If-Match` header, if the client sent one
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+    // Fails with 412 when the client's copy is not the current one
+    pub fn check(&self, etag: &str) -> Result<(), Status> {
+        match self.0.as_deref() {
+            Some(header) if !header_matches(header, etag) => Err(Status::PreconditionFailed),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfNoneMatch(request.headers().get_one("If-None-Match").map(str::to_string)))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfMatch(request.headers().get_one("If-Match").map(str::to_string)))
+    }
+}
+
+// A response carrying an ETag, or an empty 304 when the client's copy is current.
+// `no-cache` makes browsers revalidate with If-None-Match instead of refetching.
+pub enum Tagged<R> {
+    Modified(String, R),
+    NotModified(String),
+}
+
+impl<R> Tagged<R> {
+    pub fn new(etag: String, if_none_match: &IfNoneMatch, body: R) -> Self {
+        if if_none_match.matches(&etag) {
+            Tagged::NotModified(etag)
+        } else {
+            Tagged::Modified(etag, body)
+        }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let (etag, response) = match self {
+            Tagged::Modified(etag, body) => (etag, Response::build_from(body.respond_to(request)?).finalize()),
+            Tagged::NotModified(etag) => (etag, Response::build().status(Status::NotModified).finalize()),
+        };
+
+        Response::build_from(response)
+            .header(Header::new("ETag", etag))
+            .header(Header::new("Cache-Control", "no-cache"))
+            .ok()
+    }
+}
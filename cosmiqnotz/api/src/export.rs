@@ -0,0 +1,291 @@
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::{Request, State};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::api_keys::{Authorized, CanRead};
+use crate::attachments::{self, Attachment};
+use crate::id::NoteId;
+use crate::note::Note;
+use crate::render::{self, escape_html, Renderer};
+use crate::sessions::UserDb;
+use crate::workspaces::InWorkspace;
+
+// Folder used in archives for notes that don't belong to a notebook
+const UNFILED_NOTEBOOK: &str = "Unfiled";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Markdown,
+    Html,
+    Zip,
+}
+
+impl ExportFormat {
+    fn parse(format: Option<&str>) -> Option<Self> {
+        match format.unwrap_or("zip") {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown | Self::Zip => "md",
+        }
+    }
+}
+
+// A downloadable file produced by an export
+pub struct ExportFile {
+    pub file_name: String,
+    pub content_type: ContentType,
+    pub body: Vec<u8>,
+}
+
+impl<'r> Responder<'r, 'static> for ExportFile {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(self.content_type)
+            .raw_header("Content-Disposition", format!("attachment; filename=\"{}\"", self.file_name))
+            .sized_body(self.body.len(), Cursor::new(self.body))
+            .ok()
+    }
+}
+
+// Quote a string so it is a valid YAML scalar; JSON strings are valid YAML
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn yaml_list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|v| yaml_string(v)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn slugify(title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug
+    }
+}
+
+// Notebook and attachment names come from users; keep each to one path segment so no
+// entry can land outside the archive's folders when it is extracted
+fn archive_segment(name: &str, fallback: &str) -> String {
+    let segment: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':') || c.is_control() { '-' } else { c })
+        .collect();
+    let segment = segment.trim();
+
+    if segment.trim_matches('.').is_empty() {
+        fallback.to_string()
+    } else {
+        segment.to_string()
+    }
+}
+
+fn file_stem(note: &Note) -> String {
+    match &note.id {
+        Some(id) => format!("{}-{}", slugify(&note.title), id),
+        None => slugify(&note.title),
+    }
+}
+
+pub fn render_markdown(note: &Note) -> String {
+    let mut front_matter = vec![
+        "---".to_string(),
+        format!("id: {}", yaml_string(note.id.as_deref().unwrap_or_default())),
+        format!("title: {}", yaml_string(&note.title)),
+        format!("created_at: {}", note.created_at.to_rfc3339()),
+        format!("updated_at: {}", note.updated_at.to_rfc3339()),
+        format!("created_by: {}", yaml_string(&note.created_by)),
+        format!("version: {}", note.version),
+    ];
+    if let Some(notebook) = &note.notebook {
+        front_matter.push(format!("notebook: {}", yaml_string(notebook)));
+    }
+    front_matter.push(format!("tags: {}", yaml_list(&note.tags)));
+    front_matter.push(format!("is_shared: {}", note.is_shared));
+    front_matter.push(format!("shared_with: {}", yaml_list(&note.shared_with)));
+    if let Some(due_at) = note.due_at {
+        front_matter.push(format!("due_at: {}", due_at.to_rfc3339()));
+    }
+    if let Some(remind_at) = note.remind_at {
+        front_matter.push(format!("remind_at: {}", remind_at.to_rfc3339()));
+    }
+    // Title and body stay encrypted; keep what's needed to decrypt them on a client
+    if let Some(encryption) = &note.encryption {
+        front_matter.push("encrypted: true".to_string());
+        front_matter.push(format!("encryption: {}", serde_json::to_string(encryption).unwrap_or_default()));
+    }
+    front_matter.push("---".to_string());
+
+    format!("{}\n\n{}\n", front_matter.join("\n"), note.content)
+}
+
+// `body` is the note's content already rendered by `render::Renderer`
+pub fn render_html(note: &Note, body: &str) -> String {
+    if note.is_encrypted() {
+        return "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Encrypted note</title>\n</head>\n<body>\n<p>This note is end-to-end encrypted and can only be read in CosmiqNotz.</p>\n</body>\n</html>\n".to_string();
+    }
+
+    let title = escape_html(&note.title);
+    let tags = note.tags.iter()
+        .map(|tag| format!("<span class=\"tag\">{}</span>", escape_html(tag)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<article>\n<h1>{title}</h1>\n<p class=\"meta\">Updated {updated} &middot; version {version} {tags}</p>\n{body}</article>\n</body>\n</html>\n",
+        title = title,
+        updated = note.updated_at.format("%Y-%m-%d %H:%M"),
+        version = note.version,
+        tags = tags,
+        body = body,
+    )
+}
+
+fn render(note: &Note, format: ExportFormat, bodies: &HashMap<NoteId, String>) -> String {
+    match format {
+        ExportFormat::Html => {
+            let body = note.id.as_ref().and_then(|id| bodies.get(id)).map(String::as_str).unwrap_or_default();
+            render_html(note, body)
+        },
+        ExportFormat::Markdown | ExportFormat::Zip => render_markdown(note),
+    }
+}
+
+// Bodies of the notes as HTML, keyed by note id, through the same renderer and cache
+// as the preview endpoint
+async fn render_bodies(db: &Surreal<Client>, renderer: &Renderer, workspace: &InWorkspace, notes: &[Note]) -> Result<HashMap<NoteId, String>, Status> {
+    let mut bodies = HashMap::new();
+    for note in notes.iter().filter(|note| !note.is_encrypted()) {
+        let Some(id) = note.id.clone() else {
+            continue;
+        };
+        let links = render::resolve_links(db, &workspace.id, &note.content)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        bodies.insert(id, renderer.render_note(note, &links));
+    }
+    Ok(bodies)
+}
+
+fn build_archive(notes: &[Note], bodies: &HashMap<NoteId, String>, attachments: &[Attachment], format: ExportFormat) -> zip::result::ZipResult<Vec<u8>> {
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+
+    for note in notes {
+        let notebook = archive_segment(note.notebook.as_deref().unwrap_or(UNFILED_NOTEBOOK), UNFILED_NOTEBOOK);
+        let path = format!("{}/{}.{}", notebook, file_stem(note), format.extension());
+        archive.start_file(path, options)?;
+        archive.write_all(render(note, format, bodies).as_bytes())?;
+    }
+
+    for attachment in attachments {
+        // Skip anything that was stored with invalid base64 rather than failing the whole export
+        let Some(bytes) = attachment.bytes() else {
+            continue;
+        };
+        let path = format!(
+            "attachments/{}/{}",
+            archive_segment(&attachment.note_id, "unknown"),
+            archive_segment(&attachment.file_name, "attachment"),
+        );
+        archive.start_file(path, options)?;
+        archive.write_all(&bytes)?;
+    }
+
+    Ok(archive.finish()?.into_inner())
+}
+
+async fn select_notes(db: &Surreal<Client>, workspace: &InWorkspace, note: Option<&str>, notebook: Option<&str>) -> Result<Vec<Note>, Status> {
+    match (note, notebook) {
+        (Some(id), _) => workspace.find_note(db, id).await.map(|note| vec![note]),
+        (None, Some(notebook)) => {
+            db.query("SELECT * FROM note WHERE deleted_at = NONE AND workspace_id = $workspace AND notebook = $notebook ORDER BY updated_at DESC")
+                .bind(("workspace", workspace.id.as_str()))
+                .bind(("notebook", notebook))
+                .await
+                .and_then(|mut response| response.take(0))
+                .map_err(|_| Status::InternalServerError)
+        },
+        (None, None) => {
+            db.query("SELECT * FROM note WHERE deleted_at = NONE AND workspace_id = $workspace ORDER BY updated_at DESC")
+                .bind(("workspace", workspace.id.as_str()))
+                .await
+                .and_then(|mut response| response.take(0))
+                .map_err(|_| Status::InternalServerError)
+        },
+    }
+}
+
+// Export a single note, a notebook or everything. Markdown and HTML exports of a
+// single note come back as one document; anything else is bundled into a zip archive
+// together with the notes' attachments.
+#[get("/export?<format>&<note>&<notebook>")]
+pub async fn export(format: Option<&str>, note: Option<&str>, notebook: Option<&str>, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, renderer: &State<Arc<Renderer>>) -> Result<ExportFile, Status> {
+    let format = ExportFormat::parse(format).ok_or(Status::BadRequest)?;
+    let notes = select_notes(&db, &workspace, note, notebook).await?;
+    let bodies = if format == ExportFormat::Html {
+        render_bodies(&db, renderer, &workspace, &notes).await?
+    } else {
+        HashMap::new()
+    };
+
+    if format != ExportFormat::Zip && note.is_some() {
+        let note = &notes[0];
+        let content_type = match format {
+            ExportFormat::Html => ContentType::HTML,
+            _ => ContentType::new("text", "markdown"),
+        };
+
+        return Ok(ExportFile {
+            file_name: format!("{}.{}", file_stem(note), format.extension()),
+            content_type,
+            body: render(note, format, &bodies).into_bytes(),
+        });
+    }
+
+    let note_ids: Vec<NoteId> = notes.iter().filter_map(|note| note.id.clone()).collect();
+    let attachments = attachments::attachments_for(&db, &note_ids)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let body = build_archive(&notes, &bodies, &attachments, format)
+        .map_err(|_| Status::InternalServerError)?;
+    let file_name = match (note, notebook) {
+        (Some(_), _) => format!("{}.zip", file_stem(&notes[0])),
+        (None, Some(notebook)) => format!("{}.zip", slugify(notebook)),
+        (None, None) => "cosmiqnotz-export.zip".to_string(),
+    };
+
+    Ok(ExportFile {
+        file_name,
+        content_type: ContentType::ZIP,
+        body,
+    })
+}
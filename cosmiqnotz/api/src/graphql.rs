@@ -0,0 +1,551 @@
+use rocket::fairing::AdHoc;
+use rocket::futures::stream::{self, BoxStream, StreamExt};
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Shutdown, State};
+use async_graphql::{Context, Enum, ErrorExtensions, InputObject, Object, Schema, SimpleObject, Subscription, Variables, ID};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::sync::Arc;
+
+use crate::api_key::ApiKeyScope;
+use crate::api_keys::{Authorized, CanRead};
+use crate::audit::{self, AuditAction, AuditEvent, ClientInfo};
+use crate::errors;
+use crate::limits::{LimitedJson, NoteBody, RateLimit};
+use crate::note::{Note, NoteFlags};
+use crate::render;
+use crate::sessions::UserDb;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::{delete_note_data, tasks, view_query, AppState};
+
+// Deepest nesting a query may use, e.g. note → backlinks → author
+const MAX_DEPTH: usize = 8;
+
+// Upper bound on the number of fields one query may resolve
+const MAX_COMPLEXITY: usize = 500;
+
+// Most notes one `notes` query returns
+const MAX_NOTES: usize = 500;
+
+pub type NotesSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+type GraphQLResult<T> = async_graphql::Result<T>;
+
+// A status the REST routes would have answered with, as a GraphQL error with the same
+// `code` the REST error bodies use
+fn status_error(status: Status) -> async_graphql::Error {
+    async_graphql::Error::new(status.reason_lossy()).extend_with(|_, extensions| extensions.set("code", errors::code(status)))
+}
+
+fn database_error(e: surrealdb::Error) -> async_graphql::Error {
+    error!("GraphQL query failed: {}", e);
+    status_error(Status::InternalServerError)
+}
+
+// Who is asking, checked by the same request guards as the REST routes. Note queries
+// run on the caller's own connection, so the table permissions apply here too.
+pub struct Caller {
+    workspace: InWorkspace,
+    db: Surreal<Client>,
+    client: ClientInfo,
+    can_write: bool,
+    state: Arc<AppState>,
+}
+
+impl Caller {
+    fn new(workspace: InWorkspace, db: UserDb, client: ClientInfo, auth: &Authorized<CanRead>, state: &Arc<AppState>) -> Arc<Self> {
+        Arc::new(Self {
+            workspace,
+            db: (*db).clone(),
+            client,
+            // Like REST writes: they need a key or session with the write scope
+            can_write: auth.principal.as_ref().is_some_and(|principal| principal.allows(ApiKeyScope::Write)),
+            state: state.clone(),
+        })
+    }
+
+    fn require_editor(&self) -> GraphQLResult<()> {
+        if !self.can_write {
+            return Err(status_error(Status::Forbidden));
+        }
+        self.workspace.require(WorkspaceRole::Editor).map_err(status_error)
+    }
+
+    async fn find_note(&self, id: &str) -> GraphQLResult<Option<Note>> {
+        let note: Option<Note> = self.db.select(("note", id)).await.map_err(database_error)?;
+        Ok(note.filter(|note| self.workspace.contains(note)))
+    }
+
+    async fn notes(&self, sql: &str, bindings: serde_json::Value) -> GraphQLResult<Vec<Note>> {
+        self.db.query(sql)
+            .bind(("workspace", self.workspace.id.as_str()))
+            .bind(bindings)
+            .await
+            .and_then(|mut response| response.take(0))
+            .map_err(database_error)
+    }
+
+    // A recorded change as seen by this caller; changes in other workspaces and to
+    // notes the caller can't read are left out
+    async fn change(&self, event: AuditEvent) -> Option<NoteChange> {
+        if event.workspace_id.as_deref() != Some(self.workspace.id.as_str()) {
+            return None;
+        }
+        let note = match event.action {
+            AuditAction::Delete => None,
+            _ => Some(self.find_note(&event.note_id).await.ok().flatten()?),
+        };
+
+        Some(NoteChange {
+            kind: event.action.into(),
+            note_id: ID(event.note_id),
+            version: event.after_version.or(event.before_version),
+            actor: event.actor,
+            at: event.at,
+            note: note.map(NoteNode),
+        })
+    }
+}
+
+fn caller<'a>(ctx: &'a Context<'_>) -> GraphQLResult<&'a Arc<Caller>> {
+    ctx.data::<Arc<Caller>>()
+}
+
+#[derive(Deserialize)]
+struct UserRecord {
+    username: String,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+// A user as other members of a workspace see them; email addresses stay private
+pub struct User {
+    username: String,
+    display_name: String,
+}
+
+#[Object]
+impl User {
+    async fn username(&self) -> &str {
+        &self.username
+    }
+
+    async fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    // Their notes in the current workspace that the caller can see, newest first
+    async fn notes(&self, ctx: &Context<'_>) -> GraphQLResult<Vec<NoteNode>> {
+        let notes = caller(ctx)?
+            .notes(
+                "SELECT * FROM note WHERE workspace_id = $workspace AND created_by = $user AND deleted_at = NONE ORDER BY updated_at DESC",
+                serde_json::json!({ "user": self.username }),
+            )
+            .await?;
+        Ok(notes.into_iter().map(NoteNode).collect())
+    }
+}
+
+async fn find_user(state: &AppState, username: &str) -> GraphQLResult<User> {
+    let record: Option<UserRecord> = state.db.select(("user", username)).await.map_err(database_error)?;
+    // Users named by API keys may have acted before they had a record
+    Ok(match record {
+        Some(record) => User {
+            display_name: record.display_name.unwrap_or_else(|| record.username.clone()),
+            username: record.username,
+        },
+        None => User { username: username.to_string(), display_name: username.to_string() },
+    })
+}
+
+pub struct NoteNode(Note);
+
+#[Object(name = "Note")]
+impl NoteNode {
+    async fn id(&self) -> ID {
+        ID(self.0.id.clone().map(String::from).unwrap_or_default())
+    }
+
+    // Ciphertext for encrypted notes, which only clients holding the passphrase can read
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn encrypted(&self) -> bool {
+        self.0.is_encrypted()
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn version(&self) -> u64 {
+        self.0.version
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn notebook(&self) -> Option<&str> {
+        self.0.notebook.as_deref()
+    }
+
+    async fn shared_with(&self) -> &[String] {
+        &self.0.shared_with
+    }
+
+    async fn pinned(&self) -> bool {
+        self.0.pinned
+    }
+
+    async fn favorite(&self) -> bool {
+        self.0.favorite
+    }
+
+    async fn archived(&self) -> bool {
+        self.0.archived
+    }
+
+    async fn due_at(&self) -> Option<DateTime<Utc>> {
+        self.0.due_at
+    }
+
+    async fn remind_at(&self) -> Option<DateTime<Utc>> {
+        self.0.remind_at
+    }
+
+    async fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        self.0.deleted_at
+    }
+
+    // Who holds the note's edit lock, while it lasts
+    async fn locked_by(&self) -> Option<&str> {
+        self.0.lock_holder(Utc::now())
+    }
+
+    async fn author(&self, ctx: &Context<'_>) -> GraphQLResult<User> {
+        find_user(&caller(ctx)?.state, &self.0.created_by).await
+    }
+
+    // Notes this one links to with `[[Title]]`
+    async fn links(&self, ctx: &Context<'_>) -> GraphQLResult<Vec<NoteNode>> {
+        let targets = render::link_targets(&self.0.content);
+        if self.0.is_encrypted() || targets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let notes = caller(ctx)?
+            .notes(
+                "SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND string::lowercase(title) INSIDE $titles ORDER BY updated_at DESC",
+                serde_json::json!({ "titles": targets }),
+            )
+            .await?;
+        Ok(notes.into_iter().map(NoteNode).collect())
+    }
+
+    // Notes that link to this one with `[[Title]]`
+    async fn backlinks(&self, ctx: &Context<'_>) -> GraphQLResult<Vec<NoteNode>> {
+        let key = render::link_key(&self.0.title);
+        if self.0.is_encrypted() || key.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Narrowed down in the database, then checked against the parsed links
+        let notes = caller(ctx)?
+            .notes(
+                "SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND encryption = NONE AND string::lowercase(content) CONTAINS $needle ORDER BY updated_at DESC",
+                serde_json::json!({ "needle": format!("[[{}", key) }),
+            )
+            .await?;
+        Ok(notes.into_iter()
+            .filter(|note| note.id != self.0.id && render::link_targets(&note.content).contains(&key))
+            .map(NoteNode)
+            .collect())
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    // Notes in one of the views `GET /api/notes?view=` offers, optionally narrowed down
+    // to a tag or a search; encrypted notes never match a search
+    async fn notes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "String::from(\"all\")")] view: String,
+        tag: Option<String>,
+        search: Option<String>,
+        #[graphql(default = 100)] first: usize,
+    ) -> GraphQLResult<Vec<NoteNode>> {
+        let sql = view_query(&view).ok_or_else(|| status_error(Status::BadRequest))?;
+        let notes = caller(ctx)?.notes(sql, serde_json::json!({})).await?;
+        let search = search.map(|search| search.to_lowercase());
+
+        Ok(notes.into_iter()
+            .filter(|note| tag.as_ref().is_none_or(|tag| note.tags.contains(tag)))
+            .filter(|note| match &search {
+                Some(search) => !note.is_encrypted()
+                    && (note.title.to_lowercase().contains(search) || note.content.to_lowercase().contains(search)),
+                None => true,
+            })
+            .take(first.min(MAX_NOTES))
+            .map(NoteNode)
+            .collect())
+    }
+
+    async fn note(&self, ctx: &Context<'_>, id: ID) -> GraphQLResult<Option<NoteNode>> {
+        Ok(caller(ctx)?.find_note(&id).await?.map(NoteNode))
+    }
+
+    // The user making the request
+    async fn me(&self, ctx: &Context<'_>) -> GraphQLResult<User> {
+        let caller = caller(ctx)?;
+        find_user(&caller.state, &caller.workspace.user).await
+    }
+}
+
+// Fields of a note to set; anything left out stays as it is
+#[derive(InputObject)]
+pub struct NoteInput {
+    title: Option<String>,
+    content: Option<String>,
+    notebook: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(InputObject)]
+pub struct NoteFlagsInput {
+    pinned: Option<bool>,
+    favorite: Option<bool>,
+    archived: Option<bool>,
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_note(&self, ctx: &Context<'_>, input: NoteInput) -> GraphQLResult<NoteNode> {
+        let caller = caller(ctx)?;
+        caller.require_editor()?;
+
+        let mut note = Note::new(input.title.unwrap_or_default(), input.content.unwrap_or_default(), caller.workspace.user.clone());
+        note.notebook = input.notebook;
+        note.tags = input.tags.unwrap_or_default();
+        note.workspace_id = Some(caller.workspace.id.clone());
+        note.validate().map_err(|_| status_error(Status::UnprocessableEntity))?;
+
+        let created: Option<Note> = caller.db.create("note").content(&note).await.map_err(database_error)?;
+        let created = created.ok_or_else(|| status_error(Status::InternalServerError))?;
+        tasks::sync_tasks(&caller.db, &created).await.map_err(database_error)?;
+        audit::record(&caller.state, AuditEvent::new(&caller.client, AuditAction::Create, None, Some(&created))).await;
+        Ok(NoteNode(created))
+    }
+
+    // Fails with `precondition_failed` when `expected_version` is given and the note
+    // has moved on since, like `If-Match` on the REST route
+    async fn update_note(&self, ctx: &Context<'_>, id: ID, input: NoteInput, expected_version: Option<u64>) -> GraphQLResult<NoteNode> {
+        let caller = caller(ctx)?;
+        caller.require_editor()?;
+        let previous = caller.find_note(&id).await?.ok_or_else(|| status_error(Status::NotFound))?;
+        if expected_version.is_some_and(|version| version != previous.version) {
+            return Err(status_error(Status::PreconditionFailed));
+        }
+        // Encrypted titles and bodies can only be written by a client holding the passphrase
+        if previous.is_encrypted() && (input.title.is_some() || input.content.is_some()) {
+            return Err(status_error(Status::UnprocessableEntity));
+        }
+        if previous.is_locked_for(&caller.workspace.user, Utc::now()) {
+            return Err(status_error(Status::Locked));
+        }
+
+        let mut note = previous.clone();
+        note.update(
+            input.title.unwrap_or_else(|| previous.title.clone()),
+            input.content.unwrap_or_else(|| previous.content.clone()),
+        );
+        if let Some(notebook) = input.notebook {
+            note.notebook = Some(notebook).filter(|notebook| !notebook.trim().is_empty());
+        }
+        if let Some(tags) = input.tags {
+            note.tags = tags;
+        }
+        note.validate().map_err(|_| status_error(Status::UnprocessableEntity))?;
+
+        let updated: Option<Note> = caller.db.update(("note", id.as_str())).content(&note).await.map_err(database_error)?;
+        let updated = updated.ok_or_else(|| status_error(Status::Forbidden))?;
+        tasks::sync_tasks(&caller.db, &updated).await.map_err(database_error)?;
+        let action = audit::update_action(&previous, &updated);
+        audit::record(&caller.state, AuditEvent::new(&caller.client, action, Some(&previous), Some(&updated))).await;
+        Ok(NoteNode(updated))
+    }
+
+    async fn set_note_flags(&self, ctx: &Context<'_>, id: ID, flags: NoteFlagsInput) -> GraphQLResult<NoteNode> {
+        let caller = caller(ctx)?;
+        caller.require_editor()?;
+        let previous = caller.find_note(&id).await?.ok_or_else(|| status_error(Status::NotFound))?;
+
+        let mut note = previous.clone();
+        note.set_flags(&NoteFlags { pinned: flags.pinned, favorite: flags.favorite, archived: flags.archived });
+        if note == previous {
+            return Ok(NoteNode(note));
+        }
+
+        // Flags don't count as an edit, so `updated_at` is left alone
+        note.version += 1;
+        let updated: Option<Note> = caller.db.update(("note", id.as_str())).content(&note).await.map_err(database_error)?;
+        let updated = updated.ok_or_else(|| status_error(Status::Forbidden))?;
+        audit::record(&caller.state, AuditEvent::new(&caller.client, AuditAction::Update, Some(&previous), Some(&updated))).await;
+        Ok(NoteNode(updated))
+    }
+
+    // Deletes the note for good, as `DELETE /api/notes/<id>` does
+    async fn delete_note(&self, ctx: &Context<'_>, id: ID) -> GraphQLResult<bool> {
+        let caller = caller(ctx)?;
+        caller.require_editor()?;
+        let current = caller.find_note(&id).await?.ok_or_else(|| status_error(Status::NotFound))?;
+        if current.is_locked_for(&caller.workspace.user, Utc::now()) {
+            return Err(status_error(Status::Locked));
+        }
+
+        let deleted: Option<Note> = caller.db.delete(("note", id.as_str())).await.map_err(database_error)?;
+        let deleted = deleted.ok_or_else(|| status_error(Status::Forbidden))?;
+        audit::record(&caller.state, AuditEvent::new(&caller.client, AuditAction::Delete, Some(&deleted), None)).await;
+        delete_note_data(&caller.state, &id).await.map_err(database_error)?;
+        Ok(true)
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum NoteChangeKind {
+    Created,
+    Updated,
+    Deleted,
+    Shared,
+    Imported,
+}
+
+impl From<AuditAction> for NoteChangeKind {
+    fn from(action: AuditAction) -> Self {
+        match action {
+            AuditAction::Create => Self::Created,
+            AuditAction::Update => Self::Updated,
+            AuditAction::Delete => Self::Deleted,
+            AuditAction::Share => Self::Shared,
+            AuditAction::Import => Self::Imported,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct NoteChange {
+    kind: NoteChangeKind,
+    note_id: ID,
+    version: Option<u64>,
+    actor: String,
+    at: DateTime<Utc>,
+    // The note as it is now; absent once it has been deleted
+    note: Option<NoteNode>,
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    // Every change to a note in the workspace, as recorded in the audit log. Without
+    // a caller the stream holds just the error.
+    async fn note_changes(&self, ctx: &Context<'_>) -> BoxStream<'static, GraphQLResult<NoteChange>> {
+        let caller = match caller(ctx) {
+            Ok(caller) => caller.clone(),
+            Err(e) => return stream::once(async move { Err(e) }).boxed(),
+        };
+        let changes = caller.state.changes.subscribe();
+
+        stream::unfold((changes, caller), |(mut changes, caller)| async move {
+            loop {
+                match changes.recv().await {
+                    Ok(event) => if let Some(change) = caller.change(event).await {
+                        return Some((Ok(change), (changes, caller)));
+                    },
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }).boxed()
+    }
+}
+
+// Queries and mutations. Errors come back in the response's `errors`, each with the
+// `code` the REST routes would have answered with.
+#[post("/graphql", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn graphql(request: LimitedJson<async_graphql::Request, NoteBody>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, auth: Authorized<CanRead>, state: &State<Arc<AppState>>, schema: &State<NotesSchema>) -> Json<async_graphql::Response> {
+    let caller = Caller::new(workspace, db, client, &auth, state);
+    Json(schema.execute(request.into_inner().data(caller)).await)
+}
+
+// Subscriptions as server-sent events, one response per event. EventSource can't send
+// a body or headers, so the operation goes in the query string, and the workspace
+// usually as `?workspace=`.
+#[get("/graphql/stream?<query>&<variables>&<operation_name>")]
+#[allow(clippy::too_many_arguments)]
+pub fn graphql_stream(query: String, variables: Option<String>, operation_name: Option<String>, client: ClientInfo, workspace: InWorkspace, db: UserDb, auth: Authorized<CanRead>, state: &State<Arc<AppState>>, schema: &State<NotesSchema>, mut end: Shutdown) -> Result<EventStream![], Status> {
+    let variables: Variables = match variables {
+        Some(variables) => serde_json::from_str(&variables).map_err(|_| Status::BadRequest)?,
+        None => Variables::default(),
+    };
+    let mut request = async_graphql::Request::new(query).variables(variables);
+    if let Some(operation_name) = operation_name {
+        request = request.operation_name(operation_name);
+    }
+    let request = request.data(Caller::new(workspace, db, client, &auth, state));
+    let schema = schema.inner().clone();
+
+    Ok(EventStream! {
+        let mut responses = schema.execute_stream(request);
+        loop {
+            let response = select! {
+                response = responses.next() => match response {
+                    Some(response) => response,
+                    None => break,
+                },
+                _ = &mut end => break,
+            };
+
+            yield Event::json(&response);
+        }
+    })
+}
+
+// The schema in SDL, for code generators and editors
+#[get("/graphql/schema")]
+pub fn graphql_schema(_auth: Authorized<CanRead>, schema: &State<NotesSchema>) -> (ContentType, String) {
+    (ContentType::Plain, schema.sdl())
+}
+
+pub fn schema() -> NotesSchema {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("GraphQL", |rocket| async {
+        rocket.manage(schema())
+    })
+}
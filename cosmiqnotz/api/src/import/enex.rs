@@ -0,0 +1,408 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{content_type_for, ImportedAttachment, ImportedNote, ParsedFile};
+use crate::note::Note;
+
+// Timestamp format used by Evernote exports, e.g. 20230115T093000Z
+const ENEX_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Default)]
+struct EnexResource {
+    data: String,
+    mime: Option<String>,
+    file_name: Option<String>,
+}
+
+#[derive(Default)]
+struct EnexNote {
+    title: String,
+    content: String,
+    created: Option<String>,
+    updated: Option<String>,
+    tags: Vec<String>,
+    resources: Vec<EnexResource>,
+}
+
+fn element_name(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.name().as_ref()).to_lowercase()
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    e.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attr| attr.unescape_value().ok().map(|value| value.to_string()))
+}
+
+// Entities ENML inherits from XHTML that plain XML unescaping doesn't know
+fn resolve_entity(entity: &str) -> Option<&'static str> {
+    match entity {
+        "nbsp" => Some(" "),
+        "ndash" => Some("–"),
+        "mdash" => Some("—"),
+        "hellip" => Some("…"),
+        "lsquo" => Some("‘"),
+        "rsquo" => Some("’"),
+        "ldquo" => Some("“"),
+        "rdquo" => Some("”"),
+        "copy" => Some("©"),
+        _ => None,
+    }
+}
+
+fn read_notes(xml: &str) -> Result<Vec<EnexNote>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut notes = Vec::new();
+    let mut note: Option<EnexNote> = None;
+    let mut resource: Option<EnexResource> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                match element_name(&e).as_str() {
+                    "note" => note = Some(EnexNote::default()),
+                    "resource" => resource = Some(EnexResource::default()),
+                    _ => {},
+                }
+                text.clear();
+            },
+            Event::Text(e) => text.push_str(&e.unescape_with(resolve_entity)?),
+            Event::CData(e) => text.push_str(&String::from_utf8_lossy(&e.into_inner())),
+            Event::End(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_lowercase();
+                let value = std::mem::take(&mut text);
+
+                if name == "note" {
+                    notes.extend(note.take());
+                    continue;
+                }
+                if name == "resource" {
+                    if let (Some(note), Some(resource)) = (note.as_mut(), resource.take()) {
+                        note.resources.push(resource);
+                    }
+                    continue;
+                }
+
+                match (name.as_str(), note.as_mut(), resource.as_mut()) {
+                    ("data", _, Some(resource)) => resource.data = value,
+                    ("mime", _, Some(resource)) => resource.mime = Some(value.trim().to_string()),
+                    ("file-name", _, Some(resource)) => resource.file_name = Some(value.trim().to_string()),
+                    ("title", Some(note), None) => note.title = value.trim().to_string(),
+                    ("content", Some(note), None) => note.content = value,
+                    ("created", Some(note), None) => note.created = Some(value.trim().to_string()),
+                    ("updated", Some(note), None) => note.updated = Some(value.trim().to_string()),
+                    ("tag", Some(note), None) => note.tags.push(value.trim().to_string()),
+                    _ => {},
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    Ok(notes)
+}
+
+// Converts the XHTML subset used by ENML into Markdown
+struct MarkdownWriter<'a> {
+    out: String,
+    lists: Vec<Option<usize>>,
+    links: Vec<Option<String>>,
+    in_pre: bool,
+    // Attachment file names by the MD5 hash `<en-media>` refers to them with
+    media: &'a HashMap<String, String>,
+}
+
+impl<'a> MarkdownWriter<'a> {
+    fn new(media: &'a HashMap<String, String>) -> Self {
+        Self {
+            out: String::new(),
+            lists: Vec::new(),
+            links: Vec::new(),
+            in_pre: false,
+            media,
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn ensure_line(&mut self) {
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn ensure_blank_line(&mut self) {
+        self.ensure_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn start(&mut self, e: &BytesStart) {
+        let name = element_name(e);
+        match name.as_str() {
+            "div" => self.ensure_line(),
+            "p" | "blockquote" | "table" => self.ensure_blank_line(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.ensure_blank_line();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            },
+            "ul" => {
+                self.ensure_line();
+                self.lists.push(None);
+            },
+            "ol" => {
+                self.ensure_line();
+                self.lists.push(Some(0));
+            },
+            "li" => {
+                self.ensure_line();
+                let depth = self.lists.len().max(1);
+                self.out.push_str(&"  ".repeat(depth - 1));
+                match self.lists.last_mut() {
+                    Some(Some(index)) => {
+                        *index += 1;
+                        self.out.push_str(&format!("{}. ", index));
+                    },
+                    _ => self.out.push_str("- "),
+                }
+            },
+            "tr" => self.ensure_line(),
+            "td" | "th" => self.out.push_str("| "),
+            "pre" => {
+                self.ensure_blank_line();
+                self.out.push_str("```\n");
+                self.in_pre = true;
+            },
+            "code" if !self.in_pre => self.out.push('`'),
+            "b" | "strong" => self.out.push_str("**"),
+            "i" | "em" => self.out.push('*'),
+            "s" | "strike" | "del" => self.out.push_str("~~"),
+            "a" => {
+                let href = attribute(e, "href");
+                if href.is_some() {
+                    self.out.push('[');
+                }
+                self.links.push(href);
+            },
+            _ => self.empty(e),
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "div" | "tr" | "li" => self.ensure_line(),
+            "p" | "blockquote" | "table" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.ensure_blank_line(),
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.ensure_blank_line();
+                }
+            },
+            "td" | "th" => self.out.push(' '),
+            "pre" => {
+                self.ensure_line();
+                self.out.push_str("```\n\n");
+                self.in_pre = false;
+            },
+            "code" if !self.in_pre => self.out.push('`'),
+            "b" | "strong" => self.out.push_str("**"),
+            "i" | "em" => self.out.push('*'),
+            "s" | "strike" | "del" => self.out.push_str("~~"),
+            "a" => {
+                if let Some(Some(href)) = self.links.pop() {
+                    self.out.push_str(&format!("]({})", href));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn empty(&mut self, e: &BytesStart) {
+        match element_name(e).as_str() {
+            "br" => self.out.push('\n'),
+            "hr" => {
+                self.ensure_blank_line();
+                self.out.push_str("---\n\n");
+            },
+            "en-todo" => {
+                let mark = match attribute(e, "checked").as_deref() {
+                    Some("true") => "[x] ",
+                    _ => "[ ] ",
+                };
+                // Outside a list, write a full checklist item so task extraction picks it up
+                if self.at_line_start() {
+                    self.out.push_str("- ");
+                }
+                self.out.push_str(mark);
+            },
+            "en-media" => {
+                let file_name = attribute(e, "hash").and_then(|hash| self.media.get(&hash.to_lowercase()));
+                if let Some(file_name) = file_name {
+                    self.out.push_str(&format!("![[{}]]", file_name));
+                }
+            },
+            "img" => {
+                if let Some(src) = attribute(e, "src") {
+                    self.out.push_str(&format!("![]({})", src));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_pre {
+            self.out.push_str(text);
+            return;
+        }
+
+        let text = text.replace(['\r', '\n'], " ");
+        let text = if self.at_line_start() { text.trim_start() } else { text.as_str() };
+        self.out.push_str(text);
+    }
+
+    fn finish(self) -> String {
+        let mut markdown = String::with_capacity(self.out.len());
+        let mut newlines = 0;
+        for c in self.out.chars() {
+            if c == '\n' {
+                newlines += 1;
+                if newlines > 2 {
+                    continue;
+                }
+            } else {
+                newlines = 0;
+            }
+            markdown.push(c);
+        }
+        markdown.trim().to_string()
+    }
+}
+
+fn enml_to_markdown(enml: &str, media: &HashMap<String, String>) -> Result<String, quick_xml::Error> {
+    let mut reader = Reader::from_str(enml);
+    let mut writer = MarkdownWriter::new(media);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => writer.start(&e),
+            Event::Empty(e) => writer.empty(&e),
+            Event::End(e) => writer.end(&String::from_utf8_lossy(e.name().as_ref()).to_lowercase()),
+            Event::Text(e) => writer.text(&e.unescape_with(resolve_entity)?),
+            Event::CData(e) => writer.text(&String::from_utf8_lossy(&e.into_inner())),
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    Ok(writer.finish())
+}
+
+fn parse_enex_date(value: Option<&String>) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value?, ENEX_DATE_FORMAT)
+        .ok()
+        .map(|datetime| datetime.and_utc())
+}
+
+fn convert_note(enex: EnexNote, notebook: &str, owner: &str) -> Result<(Note, Vec<ImportedAttachment>), String> {
+    let mut media = HashMap::new();
+    let mut attachments = Vec::new();
+
+    for (index, resource) in enex.resources.into_iter().enumerate() {
+        let data: String = resource.data.chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = BASE64.decode(data)
+            .map_err(|e| format!("Invalid attachment data: {}", e))?;
+
+        let file_name = resource.file_name
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| {
+                let extension = resource.mime.as_deref()
+                    .and_then(|mime| mime.rsplit('/').next())
+                    .unwrap_or("bin");
+                format!("attachment-{}.{}", index + 1, extension)
+            });
+
+        media.insert(format!("{:x}", md5::compute(&bytes)), file_name.clone());
+        attachments.push(ImportedAttachment {
+            content_type: resource.mime.unwrap_or_else(|| content_type_for(&file_name)),
+            file_name,
+            bytes,
+        });
+    }
+
+    let content = enml_to_markdown(&enex.content, &media)
+        .map_err(|e| format!("Invalid note content: {}", e))?;
+
+    let title = if enex.title.is_empty() { "Untitled Note".to_string() } else { enex.title };
+    let mut note = Note::new(title, content, owner.to_string());
+    note.notebook = Some(notebook.to_string());
+    note.tags = enex.tags.into_iter().filter(|tag| !tag.is_empty()).collect();
+    if let Some(created_at) = parse_enex_date(enex.created.as_ref()) {
+        note.created_at = created_at;
+    }
+    note.updated_at = parse_enex_date(enex.updated.as_ref()).unwrap_or(note.created_at);
+
+    Ok((note, attachments))
+}
+
+fn parse_enex(path: &Path, owner: &str, parsed: &mut Vec<ParsedFile>) {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    // Evernote exports one notebook per file
+    let notebook = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+
+    let notes = match fs::read_to_string(path) {
+        Ok(xml) => read_notes(&xml).map_err(|e| format!("Invalid ENEX file: {}", e)),
+        Err(e) => Err(format!("Failed to read file: {}", e)),
+    };
+    let notes = match notes {
+        Ok(notes) => notes,
+        Err(e) => {
+            parsed.push(ParsedFile::Failed(file_name, e));
+            return;
+        },
+    };
+
+    for (index, enex) in notes.into_iter().enumerate() {
+        let entry = format!("{}#{}", file_name, index + 1);
+        parsed.push(match convert_note(enex, &notebook, owner) {
+            Ok((note, attachments)) => ParsedFile::Note(Box::new(ImportedNote { path: entry, note, attachments })),
+            Err(e) => ParsedFile::Failed(entry, e),
+        });
+    }
+}
+
+// Parse a single `.enex` file, or every `.enex` file directly inside a folder
+pub fn parse_file(path: &Path, owner: &str) -> Result<Vec<ParsedFile>, String> {
+    let files: Vec<PathBuf> = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("enex")))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut parsed = Vec::new();
+    for file in &files {
+        parse_enex(file, owner, &mut parsed);
+    }
+
+    Ok(parsed)
+}
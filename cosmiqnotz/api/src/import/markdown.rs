@@ -0,0 +1,350 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{content_type_for, ImportedAttachment, ImportedNote, ParsedFile};
+use crate::note::Note;
+
+// Recursively collect files below `dir`, skipping hidden entries such as `.obsidian` and `.trash`.
+// Symlinks are skipped too: they can lead out of the folder, or back into it forever.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref(),
+        Some("md") | Some("markdown")
+    )
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// Split a leading `---` delimited YAML block from the body
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (None, text);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let body = &rest[offset + line.len()..];
+            return (Some(&rest[..offset]), body.trim_start_matches(['\r', '\n']));
+        }
+        offset += line.len();
+    }
+
+    (None, text)
+}
+
+fn field<'a>(front_matter: &'a Mapping, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| front_matter.get(*key))
+}
+
+fn parse_datetime(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.as_str()?.trim();
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(datetime.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    let items: Vec<String> = match value {
+        Value::String(text) => text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(str::to_string)
+            .collect(),
+        Value::Sequence(items) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    items
+        .into_iter()
+        .map(|item| item.trim().trim_start_matches('#').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// Obsidian style `#tags` written inline in the body, ignoring fenced code
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_code = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+
+        for token in line.split_whitespace() {
+            let Some(rest) = token.strip_prefix('#') else {
+                continue;
+            };
+            let tag: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                .collect();
+            if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    tags
+}
+
+fn first_heading(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+// Targets of Obsidian `![[embed]]` links, without size or heading suffixes
+fn wiki_embeds(body: &str) -> Vec<String> {
+    let mut embeds = Vec::new();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("![[") {
+        let after = &rest[start + 3..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        let target = after[..end].split(['|', '#']).next().unwrap_or_default().trim();
+        if !target.is_empty() {
+            embeds.push(target.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+
+    embeds
+}
+
+// Local targets of Markdown `![alt](path)` images
+fn image_links(body: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("![") {
+        let after = &rest[start + 2..];
+        let Some(open) = after.find("](") else {
+            break;
+        };
+        let target_start = &after[open + 2..];
+        let Some(close) = target_start.find(')') else {
+            break;
+        };
+        let target = target_start[..close].split_whitespace().next().unwrap_or_default();
+        if !target.is_empty() && !target.contains("://") && !target.starts_with(['/', '#']) {
+            links.push(target.replace("%20", " "));
+        }
+        rest = &target_start[close + 1..];
+    }
+
+    links
+}
+
+fn read_attachment(path: &Path) -> Option<ImportedAttachment> {
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let bytes = fs::read(path).ok()?;
+    Some(ImportedAttachment {
+        content_type: content_type_for(&file_name),
+        file_name,
+        bytes,
+    })
+}
+
+struct Vault<'a> {
+    root: &'a Path,
+    owner: &'a str,
+    obsidian: bool,
+    // Non-Markdown files by lowercase file name, for resolving Obsidian embeds
+    files_by_name: HashMap<String, PathBuf>,
+}
+
+impl Vault<'_> {
+    fn resolve_attachments(&self, path: &Path, body: &str) -> Vec<PathBuf> {
+        let mut resolved = Vec::new();
+
+        if self.obsidian {
+            for target in wiki_embeds(body) {
+                let name = target.rsplit('/').next().unwrap_or(&target).to_lowercase();
+                if let Some(file) = self.files_by_name.get(&name) {
+                    resolved.push(file.clone());
+                }
+            }
+        } else {
+            let dir = path.parent().unwrap_or(self.root);
+            for target in image_links(body) {
+                // Never read files outside the folder being imported
+                let Ok(file) = dir.join(&target).canonicalize() else {
+                    continue;
+                };
+                if file.starts_with(self.root) && file.is_file() {
+                    resolved.push(file);
+                }
+            }
+        }
+
+        resolved.sort();
+        resolved.dedup();
+        resolved
+    }
+
+    fn parse_note(&self, path: &Path, referenced: &mut HashSet<PathBuf>) -> ParsedFile {
+        let display = relative_path(self.root, path);
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => return ParsedFile::Failed(display, format!("Failed to read file: {}", e)),
+        };
+
+        let (raw_front_matter, body) = split_front_matter(&text);
+        let front_matter = match raw_front_matter {
+            Some(raw) if !raw.trim().is_empty() => match serde_yaml::from_str::<Mapping>(raw) {
+                Ok(front_matter) => front_matter,
+                Err(e) => return ParsedFile::Failed(display, format!("Invalid front matter: {}", e)),
+            },
+            _ => Mapping::new(),
+        };
+
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let title = field(&front_matter, &["title"])
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| if self.obsidian { None } else { first_heading(body) })
+            .unwrap_or(stem);
+
+        let mut note = Note::new(title, body.to_string(), self.owner.to_string());
+
+        // Folder structure becomes the notebook unless the front matter names one
+        let folder = path.parent()
+            .map(|parent| relative_path(self.root, parent))
+            .filter(|folder| !folder.is_empty());
+        note.notebook = field(&front_matter, &["notebook"])
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or(folder);
+
+        let mut tags = field(&front_matter, &["tags", "tag"]).map(string_list).unwrap_or_default();
+        if self.obsidian {
+            tags.extend(inline_tags(body));
+        }
+        let mut seen = HashSet::new();
+        tags.retain(|tag| seen.insert(tag.to_lowercase()));
+        note.tags = tags;
+
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok().map(DateTime::<Utc>::from);
+        if let Some(created_at) = field(&front_matter, &["created_at", "created", "date"]).and_then(parse_datetime) {
+            note.created_at = created_at;
+        } else if let Some(modified) = modified {
+            note.created_at = modified;
+        }
+        note.updated_at = field(&front_matter, &["updated_at", "updated", "modified"])
+            .and_then(parse_datetime)
+            .or(modified)
+            .unwrap_or(note.created_at);
+
+        if let Some(shared_with) = field(&front_matter, &["shared_with"]) {
+            note.shared_with = string_list(shared_with);
+        }
+        note.is_shared = field(&front_matter, &["is_shared"])
+            .and_then(Value::as_bool)
+            .unwrap_or(!note.shared_with.is_empty());
+        note.due_at = field(&front_matter, &["due_at", "due"]).and_then(parse_datetime);
+        note.remind_at = field(&front_matter, &["remind_at", "reminder"]).and_then(parse_datetime);
+
+        let attachments = self.resolve_attachments(path, body)
+            .into_iter()
+            .filter_map(|file| {
+                let attachment = read_attachment(&file)?;
+                referenced.insert(file);
+                Some(attachment)
+            })
+            .collect();
+
+        ParsedFile::Note(Box::new(ImportedNote {
+            path: display,
+            note,
+            attachments,
+        }))
+    }
+}
+
+// Parse a folder of Markdown files. With `obsidian` set, file names are titles,
+// inline `#tags` are collected and `![[embeds]]` are resolved anywhere in the vault.
+pub fn parse_folder(root: &Path, owner: &str, obsidian: bool) -> Result<Vec<ParsedFile>, String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+    let root = root.canonicalize().map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+    let root = root.as_path();
+
+    let mut files = Vec::new();
+    collect_files(root, &mut files).map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+
+    let (notes, others): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|path| is_markdown(path));
+    let files_by_name = others
+        .iter()
+        .filter_map(|path| Some((path.file_name()?.to_string_lossy().to_lowercase(), path.clone())))
+        .collect();
+
+    let vault = Vault { root, owner, obsidian, files_by_name };
+    let mut referenced = HashSet::new();
+    let mut parsed: Vec<ParsedFile> = notes
+        .iter()
+        .map(|path| vault.parse_note(path, &mut referenced))
+        .collect();
+
+    parsed.extend(
+        others
+            .iter()
+            .filter(|path| !referenced.contains(*path))
+            .map(|path| ParsedFile::Skipped(relative_path(root, path), "Not a Markdown file or referenced attachment")),
+    );
+
+    Ok(parsed)
+}
@@ -0,0 +1,321 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanWrite};
+use crate::attachments::Attachment;
+use crate::audit::{self, AuditAction, AuditEvent, ClientInfo};
+use crate::id::NoteId;
+use crate::note::Note;
+use crate::limits::{ImportBody, LimitedJson, RateLimit};
+use crate::sessions::UserDb;
+use crate::tasks;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+mod enex;
+mod markdown;
+
+// Read from Rocket's configuration, e.g. `ROCKET_IMPORT_ROOT=/home/alice`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportConfig {
+    // The only folder, with everything below it, that `/api/import` reads from. Without
+    // one, imports from the server's file system are turned off.
+    pub import_root: Option<PathBuf>,
+}
+
+impl ImportConfig {
+    // `path` with links resolved, if it exists below the import root. Paths elsewhere
+    // look missing, so the answer gives nothing away about the rest of the machine.
+    fn resolve(&self, path: &str) -> Result<PathBuf, Status> {
+        let root = self.import_root.as_ref().ok_or(Status::Forbidden)?;
+        let root = root.canonicalize().map_err(|_| Status::Forbidden)?;
+        let path = Path::new(path).canonicalize().map_err(|_| Status::NotFound)?;
+        if path.starts_with(&root) {
+            Ok(path)
+        } else {
+            Err(Status::NotFound)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Markdown,
+    Obsidian,
+    Enex,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    pub source: ImportSource,
+    pub path: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Imported,
+    WouldImport,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileResult {
+    pub path: String,
+    pub status: FileStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_id: Option<NoteId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notebook: Option<String>,
+    pub attachments: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl FileResult {
+    fn skipped(path: String, message: &str) -> Self {
+        Self {
+            path,
+            status: FileStatus::Skipped,
+            note_id: None,
+            title: None,
+            notebook: None,
+            attachments: 0,
+            message: Some(message.to_string()),
+        }
+    }
+
+    fn failed(path: String, message: String) -> Self {
+        Self {
+            path,
+            status: FileStatus::Failed,
+            note_id: None,
+            title: None,
+            notebook: None,
+            attachments: 0,
+            message: Some(message),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub files: Vec<FileResult>,
+}
+
+impl ImportReport {
+    fn push(&mut self, result: FileResult) {
+        match result.status {
+            FileStatus::Imported | FileStatus::WouldImport => self.imported += 1,
+            FileStatus::Skipped => self.skipped += 1,
+            FileStatus::Failed => self.failed += 1,
+        }
+        self.files.push(result);
+    }
+}
+
+// A file attached to an imported note, before it is stored
+pub struct ImportedAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+// A note parsed from a source file, ready to be stored
+pub struct ImportedNote {
+    pub path: String,
+    pub note: Note,
+    pub attachments: Vec<ImportedAttachment>,
+}
+
+// Outcome of parsing a single source file or ENEX entry
+pub enum ParsedFile {
+    Note(Box<ImportedNote>),
+    Skipped(String, &'static str),
+    Failed(String, String),
+}
+
+pub fn content_type_for(file_name: &str) -> String {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+fn parse_source(source: ImportSource, root: &Path, owner: &str) -> Result<Vec<ParsedFile>, String> {
+    match source {
+        ImportSource::Markdown => markdown::parse_folder(root, owner, false),
+        ImportSource::Obsidian => markdown::parse_folder(root, owner, true),
+        ImportSource::Enex => enex::parse_file(root, owner),
+    }
+}
+
+// Stored as the importing user, so the table permissions apply as to any other note
+async fn store_note(state: &AppState, db: &Surreal<Client>, client: &ClientInfo, imported: ImportedNote) -> Result<FileResult, String> {
+    let created: Option<Note> = db.create("note")
+        .content(&imported.note)
+        .await
+        .map_err(|e| format!("Failed to save note: {}", e))?;
+    let created = created.ok_or_else(|| "Failed to save note".to_string())?;
+    let note_id = created.id.clone().unwrap_or_default();
+
+    tasks::sync_tasks(db, &created)
+        .await
+        .map_err(|e| format!("Failed to extract tasks: {}", e))?;
+
+    for attachment in &imported.attachments {
+        let attachment = Attachment::new(
+            note_id.clone(),
+            attachment.file_name.clone(),
+            attachment.content_type.clone(),
+            &attachment.bytes,
+        );
+        let _: Option<Attachment> = db.create("attachment")
+            .content(&attachment)
+            .await
+            .map_err(|e| format!("Failed to save attachment {}: {}", attachment.file_name, e))?;
+    }
+
+    audit::record(state, AuditEvent::new(client, AuditAction::Import, None, Some(&created))).await;
+
+    Ok(FileResult {
+        path: imported.path,
+        status: FileStatus::Imported,
+        note_id: Some(note_id),
+        title: Some(created.title),
+        notebook: created.notebook,
+        attachments: imported.attachments.len(),
+        message: None,
+    })
+}
+
+// Imported notes land in the request's workspace and belong to the importing user
+async fn apply(state: &AppState, db: &Surreal<Client>, client: &ClientInfo, workspace: &InWorkspace, parsed: Vec<ParsedFile>, dry_run: bool) -> ImportReport {
+    let mut report = ImportReport { dry_run, ..ImportReport::default() };
+
+    for file in parsed {
+        // Notes are held to the same rules as those saved through the API
+        let file = match file {
+            ParsedFile::Note(imported) => match imported.note.validate() {
+                Ok(()) => ParsedFile::Note(imported),
+                Err(e) => ParsedFile::Failed(imported.path, e.to_string()),
+            },
+            file => file,
+        };
+        let result = match file {
+            ParsedFile::Note(imported) if dry_run => FileResult {
+                path: imported.path,
+                status: FileStatus::WouldImport,
+                note_id: None,
+                title: Some(imported.note.title),
+                notebook: imported.note.notebook,
+                attachments: imported.attachments.len(),
+                message: None,
+            },
+            ParsedFile::Note(mut imported) => {
+                imported.note.workspace_id = Some(workspace.id.clone());
+                imported.note.created_by = workspace.user.clone();
+                let path = imported.path.clone();
+                store_note(state, db, client, *imported)
+                    .await
+                    .unwrap_or_else(|e| FileResult::failed(path, e))
+            },
+            ParsedFile::Skipped(path, reason) => FileResult::skipped(path, reason),
+            ParsedFile::Failed(path, message) => FileResult::failed(path, message),
+        };
+        report.push(result);
+    }
+
+    report
+}
+
+// Import a Markdown folder, Obsidian vault or Evernote export from a path below the
+// configured import root on the server's machine. With `dry_run` set nothing is
+// written and the report shows what would be imported.
+#[post("/import", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import(request: Json<ImportRequest>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, config: &State<Arc<ImportConfig>>, state: &State<Arc<AppState>>) -> Result<Json<ImportReport>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let request = request.into_inner();
+    let root = config.resolve(&request.path)?;
+
+    let owner = workspace.user.clone();
+    let source = request.source;
+    let parsed = rocket::tokio::task::spawn_blocking(move || parse_source(source, &root, &owner))
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .map_err(|_| Status::UnprocessableEntity)?;
+
+    Ok(Json(apply(state, &db, &client, &workspace, parsed, request.dry_run).await))
+}
+
+// Import notes previously exported as CosmiqNotz JSON
+#[post("/notes/import?<dry_run>", data = "<notes>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_notes(dry_run: Option<bool>, notes: LimitedJson<Vec<Note>, ImportBody>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<ImportReport>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let now = Utc::now();
+    let parsed = notes.into_inner()
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut note)| {
+            // Imported notes always get fresh ids so they can't overwrite existing ones
+            note.id = None;
+            note.reminder_sent_at = None;
+            note.due_sent_at = None;
+            if note.created_at.timestamp() == 0 {
+                note.created_at = now;
+            }
+            ParsedFile::Note(Box::new(ImportedNote {
+                path: format!("#{}", index),
+                note,
+                attachments: Vec::new(),
+            }))
+        })
+        .collect();
+
+    Ok(Json(apply(state, &db, &client, &workspace, parsed, dry_run.unwrap_or(false)).await))
+}
+
+// Load the import configuration at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Imports", |rocket| async {
+        let config: ImportConfig = rocket.figment().extract().unwrap_or_default();
+        rocket.manage(Arc::new(config))
+    })
+}
@@ -0,0 +1,198 @@
+use rocket::data::{self, ByteUnit, Data, FromData, ToByteUnit};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::errors::RetryAfter;
+
+// Buckets untouched for this long are full again and can be forgotten
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(600);
+
+// Prune idle buckets once this many clients are being tracked
+const PRUNE_THRESHOLD: usize = 1024;
+
+// Read from Rocket's configuration, e.g. `ROCKET_RATE_LIMIT_PER_MINUTE` or
+// `rate_limit_burst` in Rocket.toml
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    // Sustained requests per minute per client; 0 disables rate limiting
+    pub rate_limit_per_minute: u32,
+    // Requests a client may make in a burst before being limited
+    pub rate_limit_burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_per_minute: 120,
+            rate_limit_burst: 30,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    // Take a token for `client`, or return how many seconds until one is available
+    fn acquire(&self, client: &str) -> Result<(), u64> {
+        if self.config.rate_limit_per_minute == 0 {
+            return Ok(());
+        }
+
+        let per_second = f64::from(self.config.rate_limit_per_minute) / 60.0;
+        let capacity = f64::from(self.config.rate_limit_burst.max(1));
+        let now = Instant::now();
+
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < IDLE_BUCKET_TTL);
+        }
+
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_second).ceil() as u64)
+        }
+    }
+}
+
+// Request guard for routes that write; each client (by address) gets its own bucket
+pub struct RateLimit;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RateLimit {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(limiter) = request.rocket().state::<Arc<RateLimiter>>() else {
+            return Outcome::Success(RateLimit);
+        };
+        let client = request.client_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        match limiter.acquire(&client) {
+            Ok(()) => Outcome::Success(RateLimit),
+            Err(seconds) => {
+                request.local_cache(|| RetryAfter(Some(seconds.max(1))));
+                Outcome::Error((Status::TooManyRequests, ()))
+            },
+        }
+    }
+}
+
+// A named body size limit. The size can be overridden in Rocket's `limits` table,
+// e.g. `ROCKET_LIMITS={import="128 MiB"}`.
+pub trait BodyLimit {
+    const NAME: &'static str;
+    fn default_size() -> ByteUnit;
+}
+
+// Notes, including their content
+pub struct NoteBody;
+
+impl BodyLimit for NoteBody {
+    const NAME: &'static str = "note";
+    fn default_size() -> ByteUnit {
+        2.mebibytes()
+    }
+}
+
+// A base64 encoded attachment
+pub struct AttachmentBody;
+
+impl BodyLimit for AttachmentBody {
+    const NAME: &'static str = "attachment";
+    fn default_size() -> ByteUnit {
+        25.mebibytes()
+    }
+}
+
+// A bulk JSON import of notes
+pub struct ImportBody;
+
+impl BodyLimit for ImportBody {
+    const NAME: &'static str = "import";
+    fn default_size() -> ByteUnit {
+        64.mebibytes()
+    }
+}
+
+// JSON body capped at the size limit `L` rather than Rocket's shared `json` limit.
+// Bodies over the limit are rejected with 413 without being read in full.
+pub struct LimitedJson<T, L> {
+    value: T,
+    limit: PhantomData<L>,
+}
+
+impl<T, L> LimitedJson<T, L> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, L> Deref for LimitedJson<T, L> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned, L: BodyLimit> FromData<'r> for LimitedJson<T, L> {
+    type Error = ();
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = request.limits().get(L::NAME).unwrap_or_else(L::default_size);
+
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => return Outcome::Error((Status::PayloadTooLarge, ())),
+            Err(_) => return Outcome::Error((Status::BadRequest, ())),
+        };
+
+        match serde_json::from_str(&body) {
+            Ok(value) => Outcome::Success(LimitedJson { value, limit: PhantomData }),
+            Err(_) => Outcome::Error((Status::UnprocessableEntity, ())),
+        }
+    }
+}
+
+// Load the rate limit configuration at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Rate Limiting", |rocket| async {
+        let config: RateLimitConfig = rocket.figment().extract().unwrap_or_default();
+        rocket.manage(Arc::new(RateLimiter::new(config)))
+    })
+}
@@ -0,0 +1,121 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::serde::json::Json;
+use rocket::State;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanWrite};
+use crate::limits::RateLimit;
+use crate::note::{Note, NoteLock};
+use crate::sessions::UserDb;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+
+// Read from Rocket's configuration, e.g. `ROCKET_NOTE_LOCK_SECONDS=300`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+    // How long a lock lasts without a heartbeat. Editors renew it well before then,
+    // so a closed tab frees the note within this long.
+    pub note_lock_seconds: i64,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        Self { note_lock_seconds: 120 }
+    }
+}
+
+async fn find_note(db: &UserDb, workspace: &InWorkspace, id: &str) -> Result<Note, Status> {
+    let note: Option<Note> = db.select(("note", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    note.filter(|note| workspace.contains(note)).ok_or(Status::NotFound)
+}
+
+// Take the note's edit lock, or renew it when the caller already holds it; editors
+// call this again as a heartbeat. While someone else holds the lock the answer is
+// `409` with their lock.
+#[post("/notes/<id>/lock")]
+pub async fn acquire_lock(id: &str, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, config: &State<Arc<LockConfig>>) -> Result<Custom<Json<NoteLock>>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let note = find_note(&db, &workspace, id).await?;
+    if note.is_trashed() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // One statement, so two editors can't both find the note free and take it
+    let now = Utc::now();
+    let expires_at = now + Duration::seconds(config.note_lock_seconds.max(1));
+    let locked: Vec<Note> = db
+        .query("UPDATE type::thing('note', $id) SET locked_by = $user, lock_expires_at = $expires_at WHERE locked_by = NONE OR locked_by = $user OR lock_expires_at = NONE OR lock_expires_at <= $now")
+        .bind(("id", id))
+        .bind(("user", workspace.user.as_str()))
+        .bind(("expires_at", expires_at))
+        .bind(("now", now))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    if !locked.is_empty() {
+        return Ok(Custom(Status::Ok, Json(NoteLock { note_id: id.into(), locked_by: workspace.user, expires_at })));
+    }
+
+    // Either someone else holds the lock, or the user may read the note but not edit it
+    let current = find_note(&db, &workspace, id).await?;
+    match (current.lock_holder(now), current.lock_expires_at) {
+        (Some(holder), Some(expires_at)) if holder != workspace.user => Ok(Custom(Status::Conflict, Json(NoteLock {
+            note_id: id.into(),
+            locked_by: holder.to_string(),
+            expires_at,
+        }))),
+        _ => Err(Status::Forbidden),
+    }
+}
+
+// Give up the caller's lock. Workspace owners may `force` a lock someone else holds
+// open, e.g. one left behind by a crashed client.
+#[delete("/notes/<id>/lock?<force>")]
+pub async fn release_lock(id: &str, force: Option<bool>, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>) -> Status {
+    let force = force.unwrap_or(false);
+    let required = if force { WorkspaceRole::Owner } else { WorkspaceRole::Editor };
+    if let Err(status) = workspace.require(required) {
+        return status;
+    }
+    let note = match find_note(&db, &workspace, id).await {
+        Ok(note) => note,
+        Err(status) => return status,
+    };
+    let now = Utc::now();
+    let Some(holder) = note.lock_holder(now).map(str::to_string) else {
+        return Status::NoContent;
+    };
+    if holder != workspace.user && !force {
+        return Status::Conflict;
+    }
+
+    let released = db
+        .query("UPDATE type::thing('note', $id) SET locked_by = NONE, lock_expires_at = NONE")
+        .bind(("id", id))
+        .await
+        .and_then(|response| response.check());
+    if released.is_err() {
+        return Status::InternalServerError;
+    }
+
+    if holder != workspace.user {
+        tracing::info!(note = %id, holder = %holder, by = %workspace.user, "Edit lock released by force");
+    }
+    Status::NoContent
+}
+
+// Load the lock configuration at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Note Locks", |rocket| async {
+        let config: LockConfig = rocket.figment().extract().unwrap_or_default();
+        rocket.manage(Arc::new(config))
+    })
+}
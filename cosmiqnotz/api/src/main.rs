@@ -1,70 +1,145 @@
 #[macro_use] extern crate rocket;
+use rocket::figment::Figment;
 use rocket::serde::json::Json;
-use rocket::State;
+use rocket::{Build, Rocket, State};
 use rocket::http::Status;
-use rocket::fairing::{Fairing, Info, Kind};
-use rocket::http::Header;
-use rocket::{Request, Response};
 use surrealdb::Surreal;
 use surrealdb::engine::remote::ws::{Client, Ws};
 use surrealdb::opt::auth::Root;
 use std::sync::Arc;
+use rocket::tokio::sync::broadcast;
 
-// CORS Fairing
-pub struct CORS;
+mod api_keys;
+mod attachments;
+mod audit;
+mod backup;
+mod batches;
+mod comments;
+mod cors;
+mod errors;
+mod etag;
+mod export;
+mod graphql;
+mod import;
+mod limits;
+mod locks;
+mod metrics;
+mod reminders;
+mod render;
+mod sessions;
+mod shares;
+mod tasks;
+mod telemetry;
+mod webhooks;
+mod workspaces;
+#[cfg(test)]
+mod tests;
+use api_keys::{Authorized, CanRead, CanWrite};
+use audit::{AuditAction, AuditEvent, ClientInfo};
+use etag::{IfMatch, IfNoneMatch, Tagged};
+use limits::{LimitedJson, NoteBody, RateLimit};
+use metrics::Metrics;
+use reminder::ReminderEvent;
+use sessions::UserDb;
+use workspaces::InWorkspace;
 
-#[rocket::async_trait]
-impl Fairing for CORS {
-    fn info(&self) -> Info {
-        Info {
-            name: "CORS Fairing",
-            kind: Kind::Response,
-        }
-    }
-
-    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
-        response.set_header(Header::new("Access-Control-Allow-Methods", "GET, POST, PUT, DELETE, OPTIONS"));
-        response.set_header(Header::new("Access-Control-Allow-Headers", "Content-Type"));
-        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
-    }
-}
+// Data models shared with the frontend and the desktop shell
+use cosmiqnotz_core::{api_key, batch, comment, id, note, reminder, share_link, task, workspace};
+use note::{Note, NoteFlags};
+use workspace::WorkspaceRole;
 
-// Import data models
-#[path = "../../src/models/note.rs"]
-mod note;
-use note::Note;
+// Where the API finds SurrealDB unless `db_address` is configured, e.g.
+// `ROCKET_DB_ADDRESS=127.0.0.1:8001`. Per-user connections in `sessions` use the same.
+const DB_ADDRESS: &str = "127.0.0.1:8000";
+const DB_NAMESPACE: &str = "cosmiqnotz";
+const DB_DATABASE: &str = "cosmiqnotz";
 
 // Application state with database connection
 struct AppState {
     db: Surreal<Client>,
+    db_address: String,
+    reminders: broadcast::Sender<ReminderEvent>,
+    // Every recorded change to a note; webhooks are delivered from here
+    changes: broadcast::Sender<AuditEvent>,
+    metrics: Metrics,
 }
 
 // API Endpoints
-#[get("/notes")]
-async fn get_notes(state: &State<Arc<AppState>>) -> Json<Vec<Note>> {
-    let notes: Vec<Note> = state.db.select("note")
+// Every note route acts within the request's workspace; notes in other workspaces
+// are never listed and are reported as missing when named directly. Note queries run
+// as the request's user, so the table permissions in init.surql apply to them too.
+
+// The query listing the notes of a view in `$workspace`. The default view leaves out
+// archived notes and lists pinned notes first. Flags are compared with `!= true` so
+// notes saved before they existed still match.
+fn view_query(view: &str) -> Option<&'static str> {
+    match view {
+        "all" => Some("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND archived != true ORDER BY pinned DESC, updated_at DESC"),
+        "pinned" => Some("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND pinned = true ORDER BY updated_at DESC"),
+        "favorites" => Some("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND favorite = true ORDER BY pinned DESC, updated_at DESC"),
+        "archived" => Some("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND archived = true ORDER BY updated_at DESC"),
+        "trash" => Some("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at != NONE ORDER BY deleted_at DESC"),
+        _ => None,
+    }
+}
+
+// Everything kept alongside a note, removed once the user's own delete of the note has
+// gone through. The table permissions follow the note, which is gone by then, and other
+// members' comments were never the user's to delete, so this runs as root.
+async fn delete_note_data(state: &AppState, id: &str) -> Result<(), surrealdb::Error> {
+    tasks::delete_tasks(&state.db, id).await?;
+    attachments::delete_attachments(&state.db, id).await?;
+    comments::delete_comments(&state.db, id).await?;
+    shares::delete_share_links(&state.db, id).await
+}
+
+#[get("/notes?<view>")]
+async fn get_notes(view: Option<&str>, if_none_match: IfNoneMatch, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Tagged<Json<Vec<Note>>>, Status> {
+    let sql = view_query(view.unwrap_or("all")).ok_or(Status::BadRequest)?;
+    let query = db.query(sql).bind(("workspace", workspace.id.as_str()));
+    let notes: Vec<Note> = state.metrics.time_query("select_notes", query)
         .await
+        .and_then(|mut response| response.take(0))
         .unwrap_or_default();
     
-    Json(notes)
+    Ok(Tagged::new(etag::list_etag(&notes), &if_none_match, Json(notes)))
+}
+
+// Encrypted notes are never matched; clients search those locally after decrypting
+#[get("/notes/search?<q>")]
+async fn search_notes(q: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Json<Vec<Note>>, Status> {
+    let query = db
+        .query("SELECT * FROM note WHERE workspace_id = $workspace AND encryption = NONE AND deleted_at = NONE AND (string::lowercase(title) CONTAINS $q OR string::lowercase(content) CONTAINS $q) ORDER BY updated_at DESC")
+        .bind(("workspace", workspace.id.as_str()))
+        .bind(("q", q.to_lowercase()));
+    let notes: Vec<Note> = state.metrics.time_query("search_notes", query)
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+    
+    Ok(Json(notes))
 }
 
 #[get("/notes/<id>")]
-async fn get_note(id: &str, state: &State<Arc<AppState>>) -> Result<Json<Note>, Status> {
-    let note: Option<Note> = state.db.select(("note", id))
+async fn get_note(id: &str, if_none_match: IfNoneMatch, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Tagged<Json<Note>>, Status> {
+    let note: Option<Note> = state.metrics.time_query("select_note", db.select(("note", id)))
         .await
         .unwrap_or(None);
     
-    match note {
-        Some(note) => Ok(Json(note)),
+    match note.filter(|note| workspace.contains(note)) {
+        Some(note) => Ok(Tagged::new(etag::note_etag(&note), &if_none_match, Json(note))),
         None => Err(Status::NotFound)
     }
 }
 
 #[post("/notes", data = "<note>")]
-async fn create_note(note: Json<Note>, state: &State<Arc<AppState>>) -> Result<Json<Note>, Status> {
+async fn create_note(note: LimitedJson<Note, NoteBody>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<Note>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
     let mut new_note = note.into_inner();
+    new_note.validate().map_err(|_| Status::UnprocessableEntity)?;
+    new_note.workspace_id = Some(workspace.id.clone());
+    // The database only lets users create notes as themselves
+    new_note.created_by = workspace.user.clone();
     
     // Ensure created_at and updated_at are set
     if new_note.created_at.timestamp() == 0 {
@@ -72,20 +147,28 @@ async fn create_note(note: Json<Note>, state: &State<Arc<AppState>>) -> Result<J
     }
     new_note.updated_at = chrono::Utc::now();
     
-    let created: Option<Note> = state.db.create("note")
-        .content(&new_note)
+    let created: Option<Note> = state.metrics.time_query("create_note", db.create("note").content(&new_note))
         .await
         .map_err(|_| Status::InternalServerError)?;
     
     match created {
-        Some(created_note) => Ok(Json(created_note)),
+        Some(created_note) => {
+            tasks::sync_tasks(&db, &created_note)
+                .await
+                .map_err(|_| Status::InternalServerError)?;
+            audit::record(state, AuditEvent::new(&client, AuditAction::Create, None, Some(&created_note))).await;
+            Ok(Json(created_note))
+        },
         None => Err(Status::InternalServerError)
     }
 }
 
 #[put("/notes/<id>", data = "<note>")]
-async fn update_note(id: &str, note: Json<Note>, state: &State<Arc<AppState>>) -> Result<Json<Note>, Status> {
+#[allow(clippy::too_many_arguments)]
+async fn update_note(id: &str, note: LimitedJson<Note, NoteBody>, if_match: IfMatch, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Tagged<Json<Note>>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
     let mut updated_note = note.into_inner();
+    updated_note.validate().map_err(|_| Status::UnprocessableEntity)?;
     
     // Ensure ID matches
     match &updated_note.id {
@@ -93,7 +176,7 @@ async fn update_note(id: &str, note: Json<Note>, state: &State<Arc<AppState>>) -
             return Err(Status::BadRequest);
         },
         None => {
-            updated_note.id = Some(id.to_string());
+            updated_note.id = Some(id.into());
         },
         _ => {}
     }
@@ -101,43 +184,130 @@ async fn update_note(id: &str, note: Json<Note>, state: &State<Arc<AppState>>) -
     // Update timestamp
     updated_note.updated_at = chrono::Utc::now();
     
-    let result: Option<Note> = state.db.update(("note", id))
-        .content(&updated_note)
+    let previous: Option<Note> = state.metrics.time_query("select_note", db.select(("note", id)))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let previous = previous.filter(|note| workspace.contains(note)).ok_or(Status::NotFound)?;
+    
+    // The client's copy must still be current, and every saved change gets a new version
+    if_match.check(&etag::note_etag(&previous))?;
+    // Only the holder of an edit lock may save while it lasts
+    if previous.is_locked_for(&workspace.user, chrono::Utc::now()) {
+        return Err(Status::Locked);
+    }
+    updated_note.version = updated_note.version.max(previous.version + 1);
+    updated_note.workspace_id = previous.workspace_id.clone();
+    // The lock is only changed through its own endpoint
+    updated_note.locked_by = previous.locked_by.clone();
+    updated_note.lock_expires_at = previous.lock_expires_at;
+    // Delivery is tracked by the scheduler; a reminder rescheduled later fires again
+    updated_note.reminder_sent_at = previous.reminder_sent_at;
+    updated_note.due_sent_at = previous.due_sent_at;
+    
+    let result: Option<Note> = state.metrics.time_query("update_note", db.update(("note", id)).content(&updated_note))
         .await
         .map_err(|_| Status::InternalServerError)?;
     
     match result {
-        Some(note) => Ok(Json(note)),
-        None => Err(Status::NotFound)
+        Some(note) => {
+            tasks::sync_tasks(&db, &note)
+                .await
+                .map_err(|_| Status::InternalServerError)?;
+            if note.is_encrypted() && !previous.is_encrypted() {
+                comments::drop_anchors(&db, id)
+                    .await
+                    .map_err(|_| Status::InternalServerError)?;
+            }
+            let action = audit::update_action(&previous, &note);
+            audit::record(state, AuditEvent::new(&client, action, Some(&previous), Some(&note))).await;
+            Ok(Tagged::Modified(etag::note_etag(&note), Json(note)))
+        },
+        // Visible but not writable, e.g. a workspace note the user has no share on
+        None => Err(Status::Forbidden)
     }
 }
 
+#[put("/notes/<id>/flags", data = "<flags>")]
+#[allow(clippy::too_many_arguments)]
+async fn set_note_flags(id: &str, flags: Json<NoteFlags>, if_match: IfMatch, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Tagged<Json<Note>>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let previous: Option<Note> = state.metrics.time_query("select_note", db.select(("note", id)))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let previous = previous.filter(|note| workspace.contains(note)).ok_or(Status::NotFound)?;
+    if_match.check(&etag::note_etag(&previous))?;
+    
+    let mut note = previous.clone();
+    note.set_flags(&flags);
+    if note == previous {
+        return Ok(Tagged::Modified(etag::note_etag(&note), Json(note)));
+    }
+    
+    // Flags don't count as an edit, so `updated_at` is left alone
+    note.version += 1;
+    let result: Option<Note> = state.metrics.time_query("update_note", db.update(("note", id)).content(&note))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let note = result.ok_or(Status::Forbidden)?;
+    
+    audit::record(state, AuditEvent::new(&client, AuditAction::Update, Some(&previous), Some(&note))).await;
+    Ok(Tagged::Modified(etag::note_etag(&note), Json(note)))
+}
+
 #[delete("/notes/<id>")]
-async fn delete_note(id: &str, state: &State<Arc<AppState>>) -> Status {
-    let result: bool = state.db.delete(("note", id))
+#[allow(clippy::too_many_arguments)]
+async fn delete_note(id: &str, if_match: IfMatch, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Status {
+    if let Err(status) = workspace.require(WorkspaceRole::Editor) {
+        return status;
+    }
+    let current: Option<Note> = state.metrics.time_query("select_note", db.select(("note", id)))
         .await
-        .unwrap_or(false);
+        .unwrap_or(None);
+    match current.filter(|note| workspace.contains(note)) {
+        Some(current) => {
+            if let Err(status) = if_match.check(&etag::note_etag(&current)) {
+                return status;
+            }
+            if current.is_locked_for(&workspace.user, chrono::Utc::now()) {
+                return Status::Locked;
+            }
+        },
+        None => return Status::NotFound,
+    }
+    
+    let deleted: Option<Note> = state.metrics.time_query("delete_note", db.delete(("note", id)))
+        .await
+        .unwrap_or(None);
     
-    if result {
+    if let Some(deleted) = deleted {
+        audit::record(state, AuditEvent::new(&client, AuditAction::Delete, Some(&deleted), None)).await;
+        if delete_note_data(state, id).await.is_err() {
+            return Status::InternalServerError;
+        }
         Status::NoContent
     } else {
-        Status::NotFound
+        Status::Forbidden
     }
 }
 
-// Options route for CORS preflight requests
-#[options("/<_..>")]
-fn options() -> Status {
-    Status::NoContent
-}
-
 #[launch]
 async fn rocket() -> _ {
+    let log_guard = telemetry::init();
+    app(rocket::Config::figment()).await.manage(log_guard)
+}
+
+// The API with all its routes and fairings, configured from `figment`. Tests build
+// it with their own configuration and database.
+async fn app(figment: Figment) -> Rocket<Build> {
+    let db_address: String = figment.extract_inner("db_address").unwrap_or_else(|_| DB_ADDRESS.to_string());
+    
     // Connect to SurrealDB
-    let db = Surreal::new::<Ws>("127.0.0.1:8000")
+    let db = Surreal::new::<Ws>(db_address.as_str())
         .await
         .expect("Failed to connect to SurrealDB");
     
+    // The shared connection stays root for background work and for tables without
+    // per-user permissions; note routes go through `sessions::UserDb` instead
     db.signin(Root {
         username: "root",
         password: "root",
@@ -145,21 +315,87 @@ async fn rocket() -> _ {
     .await
     .expect("Failed to sign in to SurrealDB");
     
-    db.use_ns("cosmiqnotz").use_db("cosmiqnotz")
+    db.use_ns(DB_NAMESPACE).use_db(DB_DATABASE)
         .await
         .expect("Failed to use namespace and database");
     
-    let state = Arc::new(AppState { db });
+    let (reminder_tx, _) = broadcast::channel(64);
+    let (change_tx, _) = broadcast::channel(256);
+    let state = Arc::new(AppState { db, db_address, reminders: reminder_tx, changes: change_tx, metrics: Metrics::default() });
     
-    rocket::build()
+    rocket::custom(figment)
         .manage(state)
-        .attach(CORS)
+        .attach(telemetry::RequestTracing)
+        .attach(cors::fairing())
+        .attach(metrics::RequestMetrics)
+        .attach(limits::fairing())
+        .attach(locks::fairing())
+        .attach(api_keys::fairing())
+        .attach(sessions::fairing())
+        .attach(render::fairing())
+        .attach(graphql::fairing())
+        .attach(reminders::scheduler())
+        .attach(backup::fairing())
+        .attach(import::fairing())
+        .attach(webhooks::fairing())
+        .attach(workspaces::fairing())
         .mount("/api", routes![
             get_notes,
+            search_notes,
             get_note,
             create_note,
             update_note,
             delete_note,
-            options,
+            set_note_flags,
+            locks::acquire_lock,
+            locks::release_lock,
+            render::note_html,
+            graphql::graphql,
+            graphql::graphql_stream,
+            graphql::graphql_schema,
+            batches::batch,
+            reminders::upcoming_reminders,
+            reminders::reminder_events,
+            tasks::get_tasks,
+            tasks::update_task,
+            attachments::get_attachments,
+            attachments::create_attachment,
+            attachments::download_attachment,
+            export::export,
+            import::import,
+            import::import_notes,
+            backup::list_backups,
+            backup::create_backup,
+            backup::verify_backup,
+            backup::restore_backup,
+            audit::list_events,
+            audit::export_events,
+            comments::get_comments,
+            comments::create_comment,
+            comments::update_comment,
+            comments::resolve_comment,
+            comments::unresolve_comment,
+            comments::delete_comment,
+            shares::list_share_links,
+            shares::create_share_link,
+            shares::revoke_share_link,
+            api_keys::list_keys,
+            api_keys::create_key,
+            api_keys::revoke_key,
+            sessions::sign_up,
+            sessions::sign_in,
+            workspaces::list_workspaces,
+            workspaces::create_workspace,
+            workspaces::list_members,
+            workspaces::set_member_role,
+            workspaces::remove_member,
+            webhooks::list_webhooks,
+            webhooks::create_webhook,
+            webhooks::delete_webhook,
+            webhooks::list_deliveries,
+            webhooks::test_webhook,
         ])
+        .mount("/", routes![metrics::health, metrics::ready, metrics::metrics, cors::preflight])
+        .mount("/", routes![shares::view_shared_note, shares::unlock_shared_note])
+        .register("/", catchers![errors::default_catcher])
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::Json;
+use rocket::{Data, Request, Response, State};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::IntoFuture;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::AppState;
+
+// Histogram bucket upper bounds, in seconds
+const BUCKETS: [f64; 11] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+// Tables init.surql defines; the server isn't ready until all of them exist
+const REQUIRED_TABLES: [&str; 11] = ["note", "task", "attachment", "comment", "audit_event", "webhook", "webhook_delivery", "api_key", "share_link", "workspace", "workspace_member"];
+
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bucket, count) in BUCKETS.iter().zip(self.counts.iter_mut()) {
+            if seconds <= *bucket {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (bucket, count) in BUCKETS.iter().zip(self.counts.iter()) {
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bucket, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, self.count);
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    // Keyed by (method, route, status)
+    requests: BTreeMap<(String, String, u16), u64>,
+    // Keyed by (method, route)
+    latencies: BTreeMap<(String, String), Histogram>,
+    // Keyed by query name
+    queries: BTreeMap<&'static str, Histogram>,
+}
+
+// Counters and timings exported at /metrics in the Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    fn observe_request(&self, method: String, route: String, status: u16, elapsed: Duration) {
+        let Ok(mut registry) = self.registry.lock() else {
+            return;
+        };
+        *registry.requests.entry((method.clone(), route.clone(), status)).or_default() += 1;
+        registry.latencies.entry((method, route)).or_default().observe(elapsed);
+    }
+
+    // Run a database call inside a `surrealdb` span and record how long it took under `name`
+    pub async fn time_query<F: IntoFuture>(&self, name: &'static str, query: F) -> F::Output {
+        let started = Instant::now();
+        let output = query.into_future().instrument(tracing::info_span!("surrealdb", query = name)).await;
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.queries.entry(name).or_default().observe(started.elapsed());
+        }
+        output
+    }
+
+    fn render(&self, out: &mut String) {
+        let Ok(registry) = self.registry.lock() else {
+            return;
+        };
+
+        out.push_str("# HELP cosmiqnotz_http_requests_total HTTP requests handled, by route and status.\n");
+        out.push_str("# TYPE cosmiqnotz_http_requests_total counter\n");
+        for ((method, route, status), count) in &registry.requests {
+            let _ = writeln!(
+                out,
+                "cosmiqnotz_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method, escape_label(route), status, count
+            );
+        }
+
+        out.push_str("# HELP cosmiqnotz_http_request_duration_seconds Time taken to handle HTTP requests, by route.\n");
+        out.push_str("# TYPE cosmiqnotz_http_request_duration_seconds histogram\n");
+        for ((method, route), histogram) in &registry.latencies {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape_label(route));
+            histogram.render(out, "cosmiqnotz_http_request_duration_seconds", &labels);
+        }
+
+        out.push_str("# HELP cosmiqnotz_db_query_duration_seconds Time taken by database queries, by query.\n");
+        out.push_str("# TYPE cosmiqnotz_db_query_duration_seconds histogram\n");
+        for (name, histogram) in &registry.queries {
+            let labels = format!("query=\"{}\"", name);
+            histogram.render(out, "cosmiqnotz_db_query_duration_seconds", &labels);
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// When the current request started, cached on the request by the fairing
+#[derive(Clone, Copy)]
+struct RequestStart(Option<Instant>);
+
+// Times every request and counts it against the route that handled it
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(started) = request.local_cache(|| RequestStart(None)).0 else {
+            return;
+        };
+        let Some(state) = request.rocket().state::<Arc<AppState>>() else {
+            return;
+        };
+
+        // Label by route pattern rather than path so ids don't create new series
+        let route = request.route()
+            .map(|route| route.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        state.metrics.observe_request(
+            request.method().to_string(),
+            route,
+            response.status().code,
+            started.elapsed(),
+        );
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthStatus {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatabaseInfo {
+    #[serde(default)]
+    tables: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteCounts {
+    total: u64,
+    encrypted: u64,
+}
+
+// Liveness: the process is up and serving requests
+#[get("/health")]
+pub fn health() -> Json<HealthStatus> {
+    Json(HealthStatus { status: "ok", problems: Vec::new() })
+}
+
+// Readiness: the database answers and the schema from init.surql has been applied
+#[get("/ready")]
+pub async fn ready(state: &State<Arc<AppState>>) -> (Status, Json<HealthStatus>) {
+    let info: Result<Option<DatabaseInfo>, surrealdb::Error> = state.metrics
+        .time_query("info_for_db", state.db.query("INFO FOR DB"))
+        .await
+        .and_then(|mut response| response.take(0));
+
+    let problems = match info {
+        Ok(Some(info)) => REQUIRED_TABLES.iter()
+            .filter(|table| !info.tables.contains_key(**table))
+            .map(|table| format!("missing table {}", table))
+            .collect(),
+        Ok(None) => vec!["database returned no schema information".to_string()],
+        Err(e) => vec![format!("database unavailable: {}", e)],
+    };
+
+    if problems.is_empty() {
+        (Status::Ok, Json(HealthStatus { status: "ready", problems }))
+    } else {
+        (Status::ServiceUnavailable, Json(HealthStatus { status: "unavailable", problems }))
+    }
+}
+
+#[get("/metrics")]
+pub async fn metrics(state: &State<Arc<AppState>>) -> (ContentType, String) {
+    let mut out = String::new();
+    state.metrics.render(&mut out);
+
+    // Note counts are read at scrape time; leave them out if the database is down
+    let counts: Result<Option<NoteCounts>, surrealdb::Error> = state.metrics
+        .time_query(
+            "count_notes",
+            state.db.query("SELECT count() AS total, count(encryption != NONE) AS encrypted FROM note GROUP ALL"),
+        )
+        .await
+        .and_then(|mut response| response.take(0));
+
+    match counts {
+        Ok(counts) => {
+            let counts = counts.unwrap_or(NoteCounts { total: 0, encrypted: 0 });
+            out.push_str("# HELP cosmiqnotz_notes Notes stored, by encryption.\n");
+            out.push_str("# TYPE cosmiqnotz_notes gauge\n");
+            let _ = writeln!(out, "cosmiqnotz_notes{{encrypted=\"false\"}} {}", counts.total.saturating_sub(counts.encrypted));
+            let _ = writeln!(out, "cosmiqnotz_notes{{encrypted=\"true\"}} {}", counts.encrypted);
+        },
+        Err(e) => error!("Failed to count notes for metrics: {}", e),
+    }
+
+    (ContentType::new("text", "plain").with_params(("version", "0.0.4")), out)
+}
@@ -0,0 +1,146 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::time::{interval, Duration};
+use rocket::{Shutdown, State};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanRead};
+use crate::note::Note;
+use crate::reminder::{ReminderEvent, ReminderKind};
+use crate::sessions::UserDb;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+// How often the scheduler looks for reminders that have come due
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Default look-ahead window for the upcoming reminders endpoint
+const DEFAULT_WINDOW_HOURS: i64 = 24;
+
+// Mark every pending reminder and passed due date as sent and broadcast an event for each
+pub(crate) async fn fire_due_reminders(state: &AppState) -> Result<usize, surrealdb::Error> {
+    let now = Utc::now();
+
+    let notes: Vec<Note> = state.db
+        .query("SELECT * FROM note WHERE deleted_at = NONE AND ((remind_at != NONE AND remind_at <= $now) OR (due_at != NONE AND due_at <= $now))")
+        .bind(("now", now))
+        .await?
+        .take(0)?;
+
+    let mut fired = 0;
+    for note in &notes {
+        let Some(id) = &note.id else {
+            continue;
+        };
+        let mut events = Vec::new();
+        let mut sent = serde_json::Map::new();
+        if let Some(remind_at) = note.remind_at.filter(|_| note.reminder_pending(now)) {
+            events.push(ReminderEvent::new(note, ReminderKind::Reminder, remind_at));
+            sent.insert("reminder_sent_at".to_string(), serde_json::json!(now));
+        }
+        if let Some(due_at) = note.due_at.filter(|_| note.due_pending(now)) {
+            events.push(ReminderEvent::new(note, ReminderKind::Due, due_at));
+            sent.insert("due_sent_at".to_string(), serde_json::json!(now));
+        }
+        if events.is_empty() {
+            continue;
+        }
+
+        let _: Option<Note> = state.db.update(("note", id.as_str()))
+            .merge(sent)
+            .await?;
+
+        // Nobody listening is not an error; the reminder still counts as sent
+        for event in events {
+            let _ = state.reminders.send(event);
+            fired += 1;
+        }
+    }
+
+    Ok(fired)
+}
+
+// Background task started once the server is live
+pub fn scheduler() -> AdHoc {
+    AdHoc::on_liftoff("Reminder Scheduler", |rocket| Box::pin(async move {
+        let Some(state) = rocket.state::<Arc<AppState>>().cloned() else {
+            error!("Reminder scheduler could not find application state");
+            return;
+        };
+        let mut shutdown = rocket.shutdown();
+
+        rocket::tokio::spawn(async move {
+            let mut ticker = interval(POLL_INTERVAL);
+            loop {
+                select! {
+                    _ = ticker.tick() => {
+                        if let Err(e) = fire_due_reminders(&state).await {
+                            error!("Failed to fire reminders: {}", e);
+                        }
+                    },
+                    _ = &mut shutdown => break,
+                }
+            }
+        });
+    }))
+}
+
+// Runs as the caller, so only notes they can read are listed
+#[get("/reminders/upcoming?<hours>")]
+pub async fn upcoming_reminders(hours: Option<i64>, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>) -> Result<Json<Vec<ReminderEvent>>, Status> {
+    let now = Utc::now();
+    let until = now + chrono::Duration::hours(hours.unwrap_or(DEFAULT_WINDOW_HOURS).max(0));
+
+    let notes: Vec<Note> = db
+        .query("SELECT * FROM note WHERE deleted_at = NONE AND workspace_id = $workspace AND ((remind_at != NONE AND remind_at >= $now AND remind_at <= $until) OR (due_at != NONE AND due_at >= $now AND due_at <= $until))")
+        .bind(("workspace", workspace.id.as_str()))
+        .bind(("now", now))
+        .bind(("until", until))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    let in_window = |at: &DateTime<Utc>| *at >= now && *at <= until;
+    let mut upcoming: Vec<ReminderEvent> = Vec::new();
+    for note in &notes {
+        if let Some(at) = note.remind_at.filter(in_window) {
+            upcoming.push(ReminderEvent::new(note, ReminderKind::Reminder, at));
+        }
+        if let Some(at) = note.due_at.filter(in_window) {
+            upcoming.push(ReminderEvent::new(note, ReminderKind::Due, at));
+        }
+    }
+    upcoming.sort_by_key(|event| event.at);
+
+    Ok(Json(upcoming))
+}
+
+// Server-sent stream of reminders as the scheduler fires them. EventSource can't set
+// headers, so the workspace is usually given as `?workspace=`.
+#[get("/reminders/events")]
+pub fn reminder_events(workspace: InWorkspace, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>, mut end: Shutdown) -> EventStream![] {
+    let mut rx = state.reminders.subscribe();
+
+    EventStream! {
+        loop {
+            let event = select! {
+                msg = rx.recv() => match msg {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut end => break,
+            };
+            if event.workspace_id.as_deref() != Some(workspace.id.as_str()) {
+                continue;
+            }
+
+            yield Event::json(&event);
+        }
+    }
+}
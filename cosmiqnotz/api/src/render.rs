@@ -0,0 +1,309 @@
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Status};
+use rocket::State;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::api_keys::{Authorized, CanRead};
+use crate::etag::{IfNoneMatch, Tagged};
+use crate::note::Note;
+use crate::sessions::UserDb;
+use crate::workspaces::InWorkspace;
+
+// Rendered notes kept in memory; the least recently used one goes first
+const CACHE_CAPACITY: usize = 512;
+
+// Prefix of the classes put on highlighted code, e.g. `hl-keyword`
+const HIGHLIGHT_PREFIX: &str = "hl-";
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// A piece of text split around `[[Target]]` and `[[Target|label]]` links.
+// `![[embeds]]` are attachments, not links, and stay text.
+enum Segment<'a> {
+    Text(&'a str),
+    Link { target: &'a str, label: &'a str },
+}
+
+fn split_wiki_links(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(length) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + length];
+        let end = start + 2 + length + 2;
+        if rest[..start].ends_with('!') || inner.trim().is_empty() || inner.contains(['[', '\n']) {
+            segments.push(Segment::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target.trim(), label.trim()),
+            None => (inner.trim(), inner.trim()),
+        };
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Link { target, label });
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+// Links are resolved by title, ignoring case and any `#heading` suffix
+pub fn link_key(target: &str) -> String {
+    target.split('#').next().unwrap_or_default().trim().to_lowercase()
+}
+
+// Every distinct title a note's `[[links]]` point at
+pub fn link_targets(content: &str) -> Vec<String> {
+    let mut targets: Vec<String> = split_wiki_links(content)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Link { target, .. } => Some(link_key(target)),
+            Segment::Text(_) => None,
+        })
+        .filter(|key| !key.is_empty())
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+// Titles a note links to, mapped to the ids of the notes in the workspace they name.
+// Links to missing notes are left out and render as plain text.
+pub async fn resolve_links(db: &Surreal<Client>, workspace_id: &str, content: &str) -> Result<BTreeMap<String, String>, surrealdb::Error> {
+    let targets = link_targets(content);
+    if targets.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let notes: Vec<Note> = db
+        .query("SELECT * FROM note WHERE workspace_id = $workspace AND deleted_at = NONE AND string::lowercase(title) INSIDE $titles ORDER BY updated_at DESC")
+        .bind(("workspace", workspace_id))
+        .bind(("titles", &targets))
+        .await?
+        .take(0)?;
+
+    // With several notes of the same title, the most recently updated one wins
+    let mut links = BTreeMap::new();
+    for note in notes {
+        if let Some(id) = note.id {
+            links.entry(note.title.to_lowercase()).or_insert(id.into_string());
+        }
+    }
+    Ok(links)
+}
+
+fn wiki_link_html(target: &str, label: &str, links: &BTreeMap<String, String>) -> String {
+    match links.get(&link_key(target)) {
+        Some(id) => format!(
+            "<a class=\"wiki-link\" href=\"#note-{id}\" data-note-id=\"{id}\">{label}</a>",
+            id = escape_html(id),
+            label = escape_html(label),
+        ),
+        None => format!("<span class=\"wiki-link missing\">{}</span>", escape_html(label)),
+    }
+}
+
+struct CachedHtml {
+    version: u64,
+    links: u64,
+    html: String,
+    used_at: Instant,
+}
+
+// Renders Markdown to sanitized HTML and remembers the result for each note version
+pub struct Renderer {
+    syntaxes: SyntaxSet,
+    cache: Mutex<HashMap<String, CachedHtml>>,
+}
+
+impl Renderer {
+    fn new() -> Self {
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn highlight(&self, code: &str, language: &str) -> String {
+        let syntax = self.syntaxes.find_syntax_by_token(language)
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            &self.syntaxes,
+            ClassStyle::SpacedPrefixed { prefix: HIGHLIGHT_PREFIX },
+        );
+        for line in LinesWithEndings::from(code) {
+            if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+                return escape_html(code);
+            }
+        }
+        generator.finalize()
+    }
+
+    // CommonMark with GFM tables, task lists, strikethrough and footnotes. Code blocks
+    // are highlighted and `[[links]]` point at the notes in `links`. Raw HTML in the
+    // note is sanitized along with everything else.
+    pub fn markdown_to_html(&self, markdown: &str, links: &BTreeMap<String, String>) -> String {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_TASKLISTS);
+        options.insert(Options::ENABLE_FOOTNOTES);
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+
+        let mut events: Vec<Event> = Vec::new();
+        // The parser splits text at brackets, so runs of text are joined before
+        // looking for links
+        let mut text = String::new();
+        let mut code: Option<(String, String)> = None;
+
+        let flush_text = |text: &mut String, events: &mut Vec<Event>| {
+            if text.is_empty() {
+                return;
+            }
+            for segment in split_wiki_links(text) {
+                events.push(match segment {
+                    Segment::Text(part) => Event::Text(CowStr::from(part.to_string())),
+                    Segment::Link { target, label } => Event::Html(CowStr::from(wiki_link_html(target, label, links))),
+                });
+            }
+            text.clear();
+        };
+
+        for event in Parser::new_ext(markdown, options) {
+            match event {
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    flush_text(&mut text, &mut events);
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    code = Some((language, String::new()));
+                },
+                Event::End(Tag::CodeBlock(_)) => {
+                    if let Some((language, body)) = code.take() {
+                        let class = if language.is_empty() { String::new() } else { format!(" class=\"language-{}\"", escape_html(&language)) };
+                        events.push(Event::Html(CowStr::from(format!(
+                            "<pre class=\"highlight\"><code{}>{}</code></pre>\n",
+                            class,
+                            self.highlight(&body, &language),
+                        ))));
+                    }
+                },
+                Event::Text(part) if code.is_some() => {
+                    if let Some((_, body)) = code.as_mut() {
+                        body.push_str(&part);
+                    }
+                },
+                Event::Text(part) => text.push_str(&part),
+                other => {
+                    flush_text(&mut text, &mut events);
+                    events.push(other);
+                },
+            }
+        }
+        flush_text(&mut text, &mut events);
+
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        sanitize(&output)
+    }
+
+    // The body of a note as HTML, from the cache when neither the note nor the notes
+    // its links resolve to have changed
+    pub fn render_note(&self, note: &Note, links: &BTreeMap<String, String>) -> String {
+        let id = note.id.clone().map(String::from).unwrap_or_default();
+        let mut hasher = DefaultHasher::new();
+        links.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        if let Ok(mut cache) = self.cache.lock() {
+            if let Some(cached) = cache.get_mut(&id).filter(|cached| cached.version == note.version && cached.links == fingerprint) {
+                cached.used_at = Instant::now();
+                return cached.html.clone();
+            }
+        }
+
+        let html = self.markdown_to_html(&note.content, links);
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= CACHE_CAPACITY && !cache.contains_key(&id) {
+                let oldest = cache.iter().min_by_key(|(_, cached)| cached.used_at).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    cache.remove(&oldest);
+                }
+            }
+            cache.insert(id, CachedHtml { version: note.version, links: fingerprint, html: html.clone(), used_at: Instant::now() });
+        }
+        html
+    }
+}
+
+// Removes scripts, event handlers and anything else that could run in the page, keeping
+// the classes and attributes the renderer itself adds
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("a", ["class", "data-note-id"])
+        .add_tag_attributes("span", ["class"])
+        .add_tag_attributes("pre", ["class"])
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("sup", ["class"])
+        .add_tag_attributes("div", ["class", "id"]);
+    builder.clean(html).to_string()
+}
+
+// The note's body as an HTML fragment, for previews. Encrypted notes can only be
+// rendered on a client that holds the passphrase.
+#[get("/notes/<id>/html")]
+pub async fn note_html(id: &str, if_none_match: IfNoneMatch, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, renderer: &State<Arc<Renderer>>) -> Result<Tagged<(ContentType, String)>, Status> {
+    let note: Option<Note> = db.select(("note", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let note = note.filter(|note| workspace.contains(note)).ok_or(Status::NotFound)?;
+    if note.is_encrypted() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let links = resolve_links(&db, &workspace.id, &note.content)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let html = renderer.render_note(&note, &links);
+
+    let mut hasher = DefaultHasher::new();
+    html.hash(&mut hasher);
+    let etag = format!("\"html-{}-{}-{:x}\"", id, note.version, hasher.finish());
+    Ok(Tagged::new(etag, &if_none_match, (ContentType::HTML, html)))
+}
+
+// Loads the syntax definitions once at ignite
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Markdown Rendering", |rocket| async {
+        rocket.manage(Arc::new(Renderer::new()))
+    })
+}
@@ -0,0 +1,318 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::remote::ws::{Client, Ws};
+use surrealdb::opt::auth::Scope;
+use surrealdb::Surreal;
+use cosmiqnotz_core::session::{SessionToken, SignIn, SignUp};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use crate::audit::ClientInfo;
+use crate::limits::RateLimit;
+use crate::{AppState, DB_DATABASE, DB_NAMESPACE};
+
+// The record access scope users sign in to, defined in init.surql
+const SCOPE: &str = "user";
+
+// Name of the token the API signs its own session tokens with. It is defined on the
+// scope at ignite, so the secret never has to be written into the schema.
+const TOKEN_NAME: &str = "cosmiqnotz_api";
+
+const ISSUER: &str = "cosmiqnotz-api";
+
+// Cached connections are replaced this long before their token runs out
+const RENEW_MARGIN_SECONDS: i64 = 60;
+
+// Read from Rocket's configuration, e.g. `ROCKET_SESSION_MINUTES=30`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    // Key for the tokens the API issues. Without one a random key is used, and
+    // tokens stop working when the server restarts.
+    pub session_secret: Option<String>,
+    pub session_minutes: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            session_secret: None,
+            session_minutes: 60,
+        }
+    }
+}
+
+// Claims SurrealDB reads from a scope token
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    iat: i64,
+    nbf: i64,
+    exp: i64,
+    iss: String,
+    #[serde(rename = "NS")]
+    ns: String,
+    #[serde(rename = "DB")]
+    db: String,
+    #[serde(rename = "SC")]
+    sc: String,
+    #[serde(rename = "TK")]
+    tk: String,
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+// A database connection authenticated as one user
+struct Session {
+    db: Surreal<Client>,
+    expires_at: DateTime<Utc>,
+}
+
+pub struct Sessions {
+    config: SessionConfig,
+    secret: String,
+    open: Mutex<HashMap<String, Session>>,
+}
+
+impl Sessions {
+    fn new(config: SessionConfig) -> Self {
+        let secret = config.session_secret.clone()
+            .filter(|secret| !secret.is_empty())
+            .unwrap_or_else(|| format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()));
+        Self { config, secret, open: Mutex::new(HashMap::new()) }
+    }
+
+    fn lifetime(&self) -> Duration {
+        Duration::minutes(self.config.session_minutes.max(1))
+    }
+
+    // A token SurrealDB accepts for the user's record in the `user` scope
+    pub fn issue(&self, user: &str) -> Result<(String, DateTime<Utc>), jsonwebtoken::errors::Error> {
+        let now = Utc::now();
+        let expires_at = now + self.lifetime();
+        let claims = Claims {
+            iat: now.timestamp(),
+            nbf: now.timestamp(),
+            exp: expires_at.timestamp(),
+            iss: ISSUER.to_string(),
+            ns: DB_NAMESPACE.to_string(),
+            db: DB_DATABASE.to_string(),
+            sc: SCOPE.to_string(),
+            tk: TOKEN_NAME.to_string(),
+            id: format!("user:{}", record_key(user)),
+        };
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::HS512), &claims, &EncodingKey::from_secret(self.secret.as_bytes()))?;
+        Ok((token, expires_at))
+    }
+
+    // The user a token issued by `issue` belongs to, if it is genuine and unexpired
+    pub fn verify(&self, token: &str) -> Option<String> {
+        let mut validation = Validation::new(Algorithm::HS512);
+        validation.set_issuer(&[ISSUER]);
+        let claims = jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(self.secret.as_bytes()), &validation)
+            .ok()?
+            .claims;
+        let key = claims.id.strip_prefix("user:")?;
+        Some(key.trim_start_matches('⟨').trim_end_matches('⟩').replace("\\⟩", "⟩"))
+    }
+
+    fn cached(&self, user: &str) -> Option<Surreal<Client>> {
+        let open = self.open.lock().ok()?;
+        let session = open.get(user)?;
+        (session.expires_at - Duration::seconds(RENEW_MARGIN_SECONDS) > Utc::now()).then(|| session.db.clone())
+    }
+
+    // A connection that runs queries as `user`, so the table permissions in init.surql
+    // decide what they can see and change
+    pub async fn connect(&self, state: &AppState, user: &str) -> Result<Surreal<Client>, Status> {
+        if let Some(db) = self.cached(user) {
+            return Ok(db);
+        }
+
+        ensure_user(state, user).await.map_err(|e| {
+            error!("Failed to create user record for {}: {}", user, e);
+            Status::InternalServerError
+        })?;
+        let (token, expires_at) = self.issue(user).map_err(|e| {
+            error!("Failed to issue session token: {}", e);
+            Status::InternalServerError
+        })?;
+        let db = open_connection(&state.db_address).await.map_err(|e| {
+            error!("Failed to connect to SurrealDB: {}", e);
+            Status::ServiceUnavailable
+        })?;
+        db.authenticate(token).await.map_err(|e| {
+            error!("SurrealDB rejected the session for {}: {}", user, e);
+            Status::Unauthorized
+        })?;
+
+        if let Ok(mut open) = self.open.lock() {
+            let now = Utc::now();
+            open.retain(|_, session| session.expires_at > now);
+            open.insert(user.to_string(), Session { db: db.clone(), expires_at });
+        }
+        Ok(db)
+    }
+}
+
+// Record ids of users are their usernames, quoted so any name is a valid id
+fn record_key(user: &str) -> String {
+    format!("⟨{}⟩", user.replace('⟩', "\\⟩"))
+}
+
+async fn open_connection(address: &str) -> Result<Surreal<Client>, surrealdb::Error> {
+    let db = Surreal::new::<Ws>(address).await?;
+    db.use_ns(DB_NAMESPACE).use_db(DB_DATABASE).await?;
+    Ok(db)
+}
+
+#[derive(Serialize)]
+struct UserRecord<'a> {
+    username: &'a str,
+    display_name: &'a str,
+}
+
+// Users acting through an API key get a record the first time they act, if they
+// never signed up, since permissions are checked against `$auth`
+async fn ensure_user(state: &AppState, user: &str) -> Result<(), surrealdb::Error> {
+    let existing: Option<serde_json::Value> = state.db.select(("user", user)).await?;
+    if existing.is_none() {
+        // Two requests may race to create it; whichever loses finds it there already
+        let _: Result<Option<serde_json::Value>, surrealdb::Error> = state.db.create(("user", user))
+            .content(UserRecord { username: user, display_name: user })
+            .await;
+    }
+    Ok(())
+}
+
+// The database as the request's user. Routes that take this instead of `state.db`
+// can only reach records the schema's permissions grant that user.
+pub struct UserDb(Surreal<Client>);
+
+impl Deref for UserDb {
+    type Target = Surreal<Client>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for UserDb {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let actor = match request.guard::<ClientInfo>().await {
+            Outcome::Success(client) => client.actor,
+            _ => None,
+        };
+        let Some(user) = actor else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+        let (Some(state), Some(sessions)) = (request.rocket().state::<Arc<AppState>>(), request.rocket().state::<Arc<Sessions>>()) else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        match sessions.connect(state, &user).await {
+            Ok(db) => Outcome::Success(UserDb(db)),
+            Err(status) => Outcome::Error((status, ())),
+        }
+    }
+}
+
+fn session_token(sessions: &Sessions, user: String) -> Result<Json<SessionToken>, Status> {
+    let (token, expires_at) = sessions.issue(&user).map_err(|_| Status::InternalServerError)?;
+    Ok(Json(SessionToken { user, token, expires_at }))
+}
+
+// Create an account through the scope's SIGNUP clause, which stores an Argon2 hash
+// of the password
+#[post("/auth/signup", data = "<account>")]
+pub async fn sign_up(account: Json<SignUp>, _rate: RateLimit, state: &State<Arc<AppState>>, sessions: &State<Arc<Sessions>>) -> Result<Json<SessionToken>, Status> {
+    #[derive(Serialize)]
+    struct Params<'a> {
+        username: &'a str,
+        password: &'a str,
+        email: Option<&'a str>,
+        display_name: Option<&'a str>,
+    }
+
+    let account = account.into_inner();
+    let username = account.username.trim();
+    if username.is_empty() || account.password.is_empty() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let db = open_connection(&state.db_address).await.map_err(|_| Status::ServiceUnavailable)?;
+    db.signup(Scope {
+        namespace: DB_NAMESPACE,
+        database: DB_DATABASE,
+        scope: SCOPE,
+        params: Params {
+            username,
+            password: &account.password,
+            email: account.email.as_deref(),
+            display_name: account.display_name.as_deref(),
+        },
+    })
+    .await
+    .map_err(|_| Status::Conflict)?;
+
+    tracing::info!(user = %username, "User signed up");
+    session_token(sessions, username.to_string())
+}
+
+// Check the password through the scope's SIGNIN clause and hand out a session token
+#[post("/auth/signin", data = "<credentials>")]
+pub async fn sign_in(credentials: Json<SignIn>, _rate: RateLimit, state: &State<Arc<AppState>>, sessions: &State<Arc<Sessions>>) -> Result<Json<SessionToken>, Status> {
+    let credentials = credentials.into_inner();
+    let db = open_connection(&state.db_address).await.map_err(|_| Status::ServiceUnavailable)?;
+    db.signin(Scope {
+        namespace: DB_NAMESPACE,
+        database: DB_DATABASE,
+        scope: SCOPE,
+        params: &credentials,
+    })
+    .await
+    .map_err(|_| Status::Unauthorized)?;
+
+    session_token(sessions, credentials.username)
+}
+
+// Let the `user` scope accept the API's session tokens. Backups leave the token out,
+// so restoring one defines it again.
+pub async fn define_token(db: &Surreal<Client>, sessions: &Sessions) -> Result<(), surrealdb::Error> {
+    // A JSON string is also a valid SurrealQL string
+    let secret = serde_json::to_string(&sessions.secret).unwrap_or_default();
+    db.query(format!("DEFINE TOKEN {} ON SCOPE {} TYPE HS512 VALUE {};", TOKEN_NAME, SCOPE, secret))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+// Load the session configuration and let the `user` scope accept the API's tokens
+pub fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Database Sessions", |rocket| async {
+        let config: SessionConfig = rocket.figment().extract().unwrap_or_default();
+        let sessions = Sessions::new(config);
+
+        let Some(state) = rocket.state::<Arc<AppState>>().cloned() else {
+            error!("Database sessions could not find application state");
+            return Err(rocket);
+        };
+        if let Err(e) = define_token(&state.db, &sessions).await {
+            error!("Failed to define the session token in SurrealDB: {}", e);
+            return Err(rocket);
+        }
+
+        Ok(rocket.manage(Arc::new(sessions)))
+    })
+}
@@ -0,0 +1,284 @@
+use rocket::form::Form;
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use chrono::Utc;
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use crate::api_keys::{hash_token, Authorized, CanRead, CanWrite};
+use crate::audit::ClientInfo;
+use crate::limits::RateLimit;
+use crate::note::Note;
+use crate::render::{escape_html, Renderer};
+use crate::sessions::UserDb;
+use crate::share_link::{CreatedShareLink, NewShareLink, ShareLink};
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+// Characters of the token kept in the clear to tell links apart
+const DISPLAY_PREFIX_LEN: usize = 8;
+
+// Public pages are served from here, outside `/api`
+const SHARE_PATH: &str = "/s";
+
+// Shared pages only need their own inline styles; nothing else may load or run
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; img-src https: data:";
+
+const PAGE_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,sans-serif;max-width:760px;margin:0 auto;padding:2rem 1rem;color:#1f2933;line-height:1.6}\
+h1{margin-top:0}pre{background:#f5f7fa;padding:.75rem;overflow-x:auto}table{border-collapse:collapse}td,th{border:1px solid #d9e2ec;padding:.25rem .5rem}\
+.meta{color:#7b8794;font-size:.9rem}.wiki-link{color:inherit}form{display:flex;gap:.5rem}input{flex:1;padding:.5rem}button{padding:.5rem 1rem}.error{color:#c81e1e}";
+
+fn new_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+fn redacted(link: ShareLink) -> ShareLink {
+    ShareLink { token_hash: None, password_hash: None, ..link }
+}
+
+async fn find_link(state: &AppState, workspace: &InWorkspace, id: &str) -> Result<ShareLink, Status> {
+    let link: Option<ShareLink> = state.db.select(("share_link", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    link.filter(|link| link.workspace_id == workspace.id).ok_or(Status::NotFound)
+}
+
+pub async fn delete_share_links(db: &Surreal<Client>, note_id: &str) -> Result<(), surrealdb::Error> {
+    db.query("DELETE share_link WHERE note_id = $note_id")
+        .bind(("note_id", note_id))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+// Every link to a note, newest first, including expired and revoked ones
+#[get("/notes/<id>/shares")]
+pub async fn list_share_links(id: &str, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Json<Vec<ShareLink>>, Status> {
+    workspace.find_note(&db, id).await?;
+    let links: Vec<ShareLink> = state.db.query("SELECT * FROM share_link WHERE note_id = $note_id ORDER BY created_at DESC")
+        .bind(("note_id", id))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(links.into_iter().map(redacted).collect()))
+}
+
+// The response is the only time the link's token is shown. Encrypted notes can't
+// be shared, since the server can't read them.
+#[post("/notes/<id>/shares", data = "<link>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_share_link(id: &str, link: Json<NewShareLink>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<CreatedShareLink>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let note = workspace.find_note(&db, id).await?;
+    let link = link.into_inner();
+    let now = Utc::now();
+    if note.is_encrypted() || note.is_trashed() || link.validate(now).is_err() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // Argon2, the same as account passwords
+    let password = link.password.filter(|password| !password.is_empty());
+    let password_hash = match &password {
+        Some(password) => {
+            let hash: Option<String> = state.db.query("RETURN crypto::argon2::generate($password)")
+                .bind(("password", password))
+                .await
+                .and_then(|mut response| response.take(0))
+                .map_err(|_| Status::InternalServerError)?;
+            Some(hash.ok_or(Status::InternalServerError)?)
+        },
+        None => None,
+    };
+
+    let token = new_token();
+    let record = ShareLink {
+        id: None,
+        note_id: id.into(),
+        workspace_id: workspace.id.clone(),
+        created_by: workspace.user.clone(),
+        prefix: token[..DISPLAY_PREFIX_LEN].to_string(),
+        token_hash: Some(hash_token(&token)),
+        password_protected: password_hash.is_some(),
+        password_hash,
+        created_at: now,
+        expires_at: link.expires_at,
+        revoked_at: None,
+        views: 0,
+        last_viewed_at: None,
+    };
+    let created: Vec<ShareLink> = state.db.create("share_link")
+        .content(&record)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let created = created.into_iter().next().ok_or(Status::InternalServerError)?;
+
+    tracing::info!(note = %id, by = %client.actor_or(&workspace.user), "Share link created");
+    Ok(Json(CreatedShareLink {
+        link: redacted(created),
+        path: format!("{}/{}", SHARE_PATH, token),
+        token,
+    }))
+}
+
+// Revoked links stop working at once but stay listed with their view counts
+#[delete("/shares/<id>")]
+pub async fn revoke_share_link(id: &str, client: ClientInfo, workspace: InWorkspace, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<ShareLink>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let link = find_link(state, &workspace, id).await?;
+    if link.revoked_at.is_some() {
+        return Ok(Json(redacted(link)));
+    }
+
+    let revoked: Option<ShareLink> = state.db.update(("share_link", id))
+        .merge(serde_json::json!({ "revoked_at": Utc::now() }))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let revoked = revoked.ok_or(Status::NotFound)?;
+
+    tracing::info!(link = %id, note = %revoked.note_id, by = %client.actor_or(&workspace.user), "Share link revoked");
+    Ok(Json(redacted(revoked)))
+}
+
+// A standalone HTML page for visitors of a share link. Pages are never cached or
+// indexed, and don't send the link on as a referrer.
+pub struct SharePage {
+    status: Status,
+    html: String,
+}
+
+impl SharePage {
+    fn new(status: Status, title: &str, body: &str) -> Self {
+        let html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<meta name=\"robots\" content=\"noindex\">\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            title = escape_html(title),
+            style = PAGE_STYLE,
+            body = body,
+        );
+        Self { status, html }
+    }
+
+    fn unavailable() -> Self {
+        Self::new(Status::NotFound, "Link unavailable", "<h1>This link is no longer available</h1>\n<p>It may have expired, been revoked, or the note may have been deleted.</p>\n")
+    }
+
+    fn password_form(token: &str, wrong: bool) -> Self {
+        let error = if wrong { "<p class=\"error\">That password is not correct.</p>\n" } else { "" };
+        let body = format!(
+            "<h1>This note is password protected</h1>\n{error}<form method=\"post\" action=\"{path}/{token}\">\n<input type=\"password\" name=\"password\" placeholder=\"Password\" autofocus required>\n<button type=\"submit\">View note</button>\n</form>\n",
+            error = error,
+            path = SHARE_PATH,
+            token = escape_html(token),
+        );
+        let status = if wrong { Status::Unauthorized } else { Status::Ok };
+        Self::new(status, "Password required", &body)
+    }
+
+    fn note(note: &Note, body: &str) -> Self {
+        let content = format!(
+            "<article>\n<h1>{title}</h1>\n<p class=\"meta\">Updated {updated}</p>\n{body}</article>\n",
+            title = escape_html(&note.title),
+            updated = note.updated_at.format("%Y-%m-%d %H:%M"),
+            body = body,
+        );
+        Self::new(Status::Ok, &note.title, &content)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for SharePage {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .status(self.status)
+            .header(ContentType::HTML)
+            .raw_header("Content-Security-Policy", CONTENT_SECURITY_POLICY)
+            .raw_header("Cache-Control", "no-store")
+            .raw_header("Referrer-Policy", "no-referrer")
+            .raw_header("X-Robots-Tag", "noindex")
+            .sized_body(self.html.len(), Cursor::new(self.html))
+            .ok()
+    }
+}
+
+// The link behind a token and the note it shares, while both are still available.
+// Links to notes in the trash stop working until the note is restored.
+async fn shared_note(state: &AppState, token: &str) -> Result<Option<(ShareLink, Note)>, surrealdb::Error> {
+    let links: Vec<ShareLink> = state.db.query("SELECT * FROM share_link WHERE token_hash = $hash LIMIT 1")
+        .bind(("hash", hash_token(token)))
+        .await?
+        .take(0)?;
+    let Some(link) = links.into_iter().find(|link| link.is_active(Utc::now())) else {
+        return Ok(None);
+    };
+
+    let note: Option<Note> = state.db.select(("note", link.note_id.as_str())).await?;
+    Ok(note
+        .filter(|note| note.workspace_id.as_deref() == Some(link.workspace_id.as_str()))
+        .filter(|note| !note.is_trashed() && !note.is_encrypted())
+        .map(|note| (link, note)))
+}
+
+async fn render_shared(state: &AppState, renderer: &Renderer, link: &ShareLink, note: &Note) -> SharePage {
+    if let Some(id) = &link.id {
+        let counted = state.db.query("UPDATE type::thing('share_link', $id) SET views += 1, last_viewed_at = $now")
+            .bind(("id", id))
+            .bind(("now", Utc::now()))
+            .await
+            .and_then(|response| response.check());
+        if let Err(e) = counted {
+            error!("Failed to count view of share link {}: {}", id, e);
+        }
+    }
+
+    // `[[links]]` are left unresolved; visitors can't open the other notes, and their
+    // titles and ids stay private
+    SharePage::note(note, &renderer.render_note(note, &BTreeMap::new()))
+}
+
+#[derive(FromForm)]
+pub struct Unlock {
+    password: String,
+}
+
+// The shared note as a read-only page, or a password prompt for protected links
+#[get("/s/<token>")]
+pub async fn view_shared_note(token: &str, state: &State<Arc<AppState>>, renderer: &State<Arc<Renderer>>) -> Result<SharePage, Status> {
+    let shared = shared_note(state, token).await.map_err(|_| Status::InternalServerError)?;
+    let Some((link, note)) = shared else {
+        return Ok(SharePage::unavailable());
+    };
+    if link.password_protected {
+        return Ok(SharePage::password_form(token, false));
+    }
+
+    Ok(render_shared(state, renderer, &link, &note).await)
+}
+
+// Rate limited, so passwords can't be guessed quickly
+#[post("/s/<token>", data = "<unlock>")]
+pub async fn unlock_shared_note(token: &str, unlock: Form<Unlock>, _rate: RateLimit, state: &State<Arc<AppState>>, renderer: &State<Arc<Renderer>>) -> Result<SharePage, Status> {
+    let shared = shared_note(state, token).await.map_err(|_| Status::InternalServerError)?;
+    let Some((link, note)) = shared else {
+        return Ok(SharePage::unavailable());
+    };
+
+    if let Some(hash) = &link.password_hash {
+        let matches: Option<bool> = state.db.query("RETURN crypto::argon2::compare($hash, $password)")
+            .bind(("hash", hash))
+            .bind(("password", &unlock.password))
+            .await
+            .and_then(|mut response| response.take(0))
+            .map_err(|_| Status::InternalServerError)?;
+        if matches != Some(true) {
+            return Ok(SharePage::password_form(token, true));
+        }
+    }
+
+    Ok(render_shared(state, renderer, &link, &note).await)
+}
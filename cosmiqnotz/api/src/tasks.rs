@@ -0,0 +1,223 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Deserialize;
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanRead, CanWrite};
+use crate::audit::{self, AuditAction, AuditEvent, ClientInfo};
+use crate::limits::RateLimit;
+use crate::sessions::UserDb;
+use crate::note::Note;
+use crate::task::Task;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct TaskUpdate {
+    pub done: bool,
+}
+
+// A checklist line split into its parts: the prefix up to and including `[`,
+// the check mark, and the item text
+struct ChecklistLine<'a> {
+    prefix: &'a str,
+    done: bool,
+    text: &'a str,
+}
+
+fn parse_checklist_line(line: &str) -> Option<ChecklistLine<'_>> {
+    let body = line.trim_start();
+    let indent = line.len() - body.len();
+
+    let mut chars = body.chars();
+    if !matches!(chars.next(), Some('-' | '*' | '+')) {
+        return None;
+    }
+    let rest = chars.as_str();
+    let after_marker = rest.trim_start();
+    if after_marker.len() == rest.len() || !after_marker.starts_with('[') {
+        return None;
+    }
+
+    let done = match after_marker.get(1..3) {
+        Some(" ]") => false,
+        Some("x]") | Some("X]") => true,
+        _ => return None,
+    };
+
+    let text = &after_marker[3..];
+    if !text.is_empty() && !text.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let prefix_len = indent + (body.len() - after_marker.len()) + 1;
+    Some(ChecklistLine {
+        prefix: &line[..prefix_len],
+        done,
+        text: text.trim(),
+    })
+}
+
+// Numbered lines of `content` outside fenced code blocks, where a `- [ ]` is just code
+fn prose_lines(content: &str) -> Vec<(usize, &str)> {
+    let mut fence: Option<&str> = None;
+    let mut lines = Vec::new();
+
+    for (number, raw) in content.split('\n').enumerate() {
+        let line = raw.trim_end_matches('\r');
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(*marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            (None, None) => lines.push((number, line)),
+            _ => {},
+        }
+    }
+    lines
+}
+
+fn task_id(note_id: &str, line: usize) -> String {
+    format!("{}_{}", note_id, line)
+}
+
+// Parse every `- [ ]` / `- [x]` item out of a note's content
+pub fn extract_tasks(note: &Note) -> Vec<Task> {
+    // Encrypted content is opaque to the server, and trashed notes have no open tasks
+    if note.is_encrypted() || note.is_trashed() {
+        return Vec::new();
+    }
+
+    let note_id = note.id.clone().unwrap_or_default();
+
+    prose_lines(&note.content)
+        .into_iter()
+        .filter_map(|(line, raw)| {
+            let item = parse_checklist_line(raw)?;
+            Some(Task {
+                id: Some(task_id(&note_id, line).into()),
+                note_id: note_id.clone(),
+                note_title: note.title.clone(),
+                line,
+                text: item.text.to_string(),
+                done: item.done,
+                created_by: note.created_by.clone(),
+                updated_at: note.updated_at,
+                workspace_id: note.workspace_id.clone(),
+            })
+        })
+        .collect()
+}
+
+// Rewrite the check mark on a single line, provided it still holds the expected item
+fn set_task_state(content: &str, line: usize, text: &str, done: bool) -> Option<String> {
+    if !prose_lines(content).iter().any(|(number, _)| *number == line) {
+        return None;
+    }
+
+    let mut lines: Vec<String> = content.split('\n').map(str::to_string).collect();
+    let target = lines.get(line)?;
+
+    let item = parse_checklist_line(target.trim_end_matches('\r'))?;
+    if item.text != text {
+        return None;
+    }
+
+    let mark = if done { "x" } else { " " };
+    let rewritten = format!("{}{}{}", item.prefix, mark, &target[item.prefix.len() + 1..]);
+    lines[line] = rewritten;
+
+    Some(lines.join("\n"))
+}
+
+// Replace the stored tasks for a note with those currently in its content
+pub async fn sync_tasks(db: &Surreal<Client>, note: &Note) -> Result<(), surrealdb::Error> {
+    let Some(note_id) = &note.id else {
+        return Ok(());
+    };
+
+    db.query("BEGIN TRANSACTION; DELETE task WHERE note_id = $note_id; INSERT INTO task $tasks; COMMIT TRANSACTION;")
+        .bind(("note_id", note_id))
+        .bind(("tasks", extract_tasks(note)))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn delete_tasks(db: &Surreal<Client>, note_id: &str) -> Result<(), surrealdb::Error> {
+    db.query("DELETE task WHERE note_id = $note_id")
+        .bind(("note_id", note_id))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+#[get("/tasks?<status>&<note>")]
+pub async fn get_tasks(status: Option<&str>, note: Option<&str>, workspace: InWorkspace, db: UserDb, _auth: Authorized<CanRead>) -> Result<Json<Vec<Task>>, Status> {
+    let mut conditions = vec!["workspace_id = $workspace"];
+    match status {
+        None | Some("all") => {},
+        Some("open") => conditions.push("done = false"),
+        Some("done") => conditions.push("done = true"),
+        Some(_) => return Err(Status::BadRequest),
+    }
+    if note.is_some() {
+        conditions.push("note_id = $note_id");
+    }
+
+    let query = format!("SELECT * FROM task WHERE {} ORDER BY updated_at DESC, line ASC", conditions.join(" AND "));
+
+    let tasks: Vec<Task> = db.query(query)
+        .bind(("workspace", workspace.id.as_str()))
+        .bind(("note_id", note))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(tasks))
+}
+
+#[put("/tasks/<id>", data = "<update>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_task(id: &str, update: Json<TaskUpdate>, client: ClientInfo, workspace: InWorkspace, db: UserDb, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<Task>, Status> {
+    workspace.require(WorkspaceRole::Editor)?;
+    let task: Option<Task> = db.select(("task", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let task = task.ok_or(Status::NotFound)?;
+
+    let mut note = workspace.find_note(&db, &task.note_id).await?;
+    if note.is_locked_for(&workspace.user, chrono::Utc::now()) {
+        return Err(Status::Locked);
+    }
+    let previous = note.clone();
+
+    // The note changed since the task was extracted
+    let content = set_task_state(&note.content, task.line, &task.text, update.done)
+        .ok_or(Status::Conflict)?;
+
+    note.update(note.title.clone(), content);
+
+    let saved: Option<Note> = db.update(("note", task.note_id.as_str()))
+        .content(&note)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let saved = saved.ok_or(Status::Forbidden)?;
+
+    sync_tasks(&db, &saved)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    audit::record(state, AuditEvent::new(&client, AuditAction::Update, Some(&previous), Some(&saved))).await;
+
+    extract_tasks(&saved)
+        .into_iter()
+        .find(|t| t.line == task.line)
+        .map(Json)
+        .ok_or(Status::InternalServerError)
+}
@@ -0,0 +1,161 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Data, Request, Response};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::Span;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{InitError, RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+// Header carrying the request id; clients may send their own and it is echoed back
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// Longest client-supplied request id that is accepted as-is
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+const LOG_FILE_PREFIX: &str = "cosmiqnotz-api";
+
+// Number of daily log files kept in the log directory
+const LOG_FILES_KEPT: usize = 7;
+
+// Keeps the background log writer alive; dropping it flushes and stops file logging
+pub struct LogGuard {
+    _writer: Option<WorkerGuard>,
+}
+
+fn file_appender(dir: &Path) -> Result<RollingFileAppender, InitError> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(LOG_FILES_KEPT)
+        .build(dir)
+}
+
+// Install the global subscriber. With `log_dir` configured (the desktop app sets
+// ROCKET_LOG_DIR) logs are written as JSON to a daily rotating file; otherwise they
+// are printed to the terminal, as is the error if the log directory can't be used.
+// Rocket's own `log` output is forwarded either way.
+pub fn init() -> LogGuard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let log_dir: Option<PathBuf> = rocket::Config::figment().extract_inner("log_dir").ok();
+
+    match log_dir.as_deref().map(|dir| (dir, file_appender(dir))) {
+        Some((_, Ok(appender))) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = tracing_subscriber::fmt()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false)
+                .with_span_events(FmtSpan::CLOSE)
+                .with_env_filter(filter)
+                .with_writer(writer)
+                .try_init();
+            LogGuard { _writer: Some(guard) }
+        },
+        unavailable => {
+            let _ = tracing_subscriber::fmt()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_env_filter(filter)
+                .try_init();
+            if let Some((dir, Err(e))) = unavailable {
+                tracing::error!("Failed to open log directory {}: {}", dir.display(), e);
+            }
+            LogGuard { _writer: None }
+        },
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    fn generate() -> Self {
+        RequestId(uuid::Uuid::new_v4().to_string())
+    }
+
+    // Accept ids from clients only when they are short and plainly formatted,
+    // since they end up in log files
+    fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| RequestId(value.to_string()))
+    }
+}
+
+// Per-request tracing state, cached on the request by the fairing
+struct RequestTrace {
+    id: RequestId,
+    span: Span,
+    started: Instant,
+}
+
+impl RequestTrace {
+    fn new(request: &Request<'_>) -> Self {
+        let id = request.headers()
+            .get_one(REQUEST_ID_HEADER)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        let span = tracing::info_span!(
+            "request",
+            request_id = %id.0,
+            method = %request.method(),
+            uri = %request.uri(),
+        );
+
+        Self { id, span, started: Instant::now() }
+    }
+}
+
+// The id of the request being handled, assigning one if the fairing hasn't yet
+pub fn request_id(request: &Request<'_>) -> RequestId {
+    request.local_cache(|| RequestTrace::new(request)).id.clone()
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(request_id(request))
+    }
+}
+
+// Gives every request an id and a span, logs its outcome and returns the id to the client
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let trace = request.local_cache(|| RequestTrace::new(request));
+        tracing::debug!(parent: &trace.span, "request started");
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let trace = request.local_cache(|| RequestTrace::new(request));
+        let route = request.route()
+            .map(|route| route.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        let status = response.status().code;
+        let latency_ms = trace.started.elapsed().as_secs_f64() * 1000.0;
+
+        if status >= 500 {
+            tracing::error!(parent: &trace.span, route = %route, status, latency_ms, "request failed");
+        } else {
+            tracing::info!(parent: &trace.span, route = %route, status, latency_ms, "request completed");
+        }
+
+        response.set_header(Header::new(REQUEST_ID_HEADER, trace.id.0.clone()));
+    }
+}
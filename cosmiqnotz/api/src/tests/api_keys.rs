@@ -0,0 +1,120 @@
+use rocket::http::Status;
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+use super::{bearer, id, json, note_body, TestApp};
+
+async fn create_key(app: &TestApp, scopes: &[&str]) -> Value {
+    let response = app.post("alice", "/api/keys")
+        .json(&json!({ "name": "Script", "scopes": scopes }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    json(response).await
+}
+
+#[tokio::test]
+async fn keys_are_shown_once_and_listed_without_their_hash() {
+    let app = TestApp::start().await;
+
+    let created = create_key(&app, &["read"]).await;
+    let token = created["token"].as_str().expect("token");
+    assert!(token.starts_with("cnz_"));
+    assert!(token.starts_with(created["key"]["prefix"].as_str().expect("prefix")));
+    assert!(created["key"].get("key_hash").is_none());
+
+    let keys = json(app.get("alice", "/api/keys").dispatch().await).await;
+    assert_eq!(keys.as_array().expect("keys").len(), 1);
+    assert_eq!(keys[0]["name"], "Script");
+    assert!(keys[0].get("key_hash").is_none());
+}
+
+#[tokio::test]
+async fn keys_act_as_their_user_within_their_scopes() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Groceries", "").await;
+    let created = create_key(&app, &["read"]).await;
+    let token = created["token"].as_str().expect("token");
+
+    let response = app.client.get("/api/notes").header(bearer(token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await[0]["title"], "Groceries");
+
+    let response = app.client.post("/api/notes").header(bearer(token)).json(&note_body("Not allowed", "")).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = app.client.get("/api/notes").header(bearer("cnz_not_a_key")).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[tokio::test]
+async fn revoked_keys_stop_working() {
+    let app = TestApp::start().await;
+    let created = create_key(&app, &["write"]).await;
+    let token = created["token"].as_str().expect("token");
+
+    let response = app.delete("bob", format!("/api/keys/{}", id(&created["key"]))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.delete("alice", format!("/api/keys/{}", id(&created["key"]))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(json(response).await.get("revoked_at").is_some());
+
+    let response = app.client.get("/api/notes").header(bearer(token)).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.delete("alice", "/api/keys/missing").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn create_key_validates_the_request() {
+    let app = TestApp::start().await;
+    let invalid = [
+        json!({ "name": " ", "scopes": ["read"] }),
+        json!({ "name": "Script", "scopes": [] }),
+        json!({ "name": "Script", "scopes": ["read"], "expires_at": Utc::now() - Duration::days(1) }),
+    ];
+    for body in invalid {
+        let response = app.post("alice", "/api/keys").json(&body).dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", body);
+    }
+
+    let response = app.client.post("/api/keys").json(&json!({ "name": "Script", "scopes": ["read"] })).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[tokio::test]
+async fn keys_cannot_outrank_their_creator() {
+    let app = TestApp::configured(|figment| figment.merge(("admin_users", ["alice"]))).await;
+
+    let response = app.post("bob", "/api/keys").json(&json!({ "name": "Script", "scopes": ["admin"] })).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let created = create_key(&app, &["admin"]).await;
+    let token = created["token"].as_str().expect("token");
+    let response = app.client.post("/api/keys").header(bearer(token)).json(&json!({ "name": "Child", "scopes": ["admin"] })).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn keys_can_be_required() {
+    let app = TestApp::configured(|figment| figment.merge(("api_keys_required", true))).await;
+
+    let response = app.client.get("/api/notes").dispatch().await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.headers().get_one("WWW-Authenticate").is_some());
+}
+
+#[tokio::test]
+async fn admin_routes_always_need_credentials() {
+    let app = TestApp::start().await;
+
+    for uri in ["/api/admin/audit", "/api/admin/backups"] {
+        let response = app.client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized, "{}", uri);
+        let response = app.get("alice", uri).dispatch().await;
+        assert_eq!(response.status(), Status::Forbidden, "{}", uri);
+    }
+    let response = app.client.post("/api/admin/backups").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
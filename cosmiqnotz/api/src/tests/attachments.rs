@@ -0,0 +1,67 @@
+use rocket::http::{ContentType, Status};
+use serde_json::json;
+
+use super::{id, json, TestApp};
+
+#[tokio::test]
+async fn attachments_are_stored_and_downloaded() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Trip", "").await;
+    let path = format!("/api/notes/{}/attachments", id(&note));
+
+    let response = app.post("alice", &path)
+        .json(&json!({ "file_name": "ticket.txt", "content_type": "text/plain", "data": "SGVsbG8=" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let attachment = json(response).await;
+    assert_eq!(attachment["size"], 5);
+
+    let listed = json(app.get("alice", &path).dispatch().await).await;
+    assert_eq!(listed.as_array().expect("attachments").len(), 1);
+    assert_eq!(listed[0]["file_name"], "ticket.txt");
+
+    let response = app.get("alice", format!("/api/attachments/{}", id(&attachment))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::Plain));
+    assert_eq!(response.into_bytes().await.expect("body"), b"Hello");
+}
+
+#[tokio::test]
+async fn create_attachment_rejects_invalid_base64() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Trip", "").await;
+
+    let response = app.post("alice", format!("/api/notes/{}/attachments", id(&note)))
+        .json(&json!({ "file_name": "ticket.txt", "content_type": "text/plain", "data": "not base64!" }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[tokio::test]
+async fn attachments_of_missing_notes_are_not_found() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Trip", "").await;
+    let attachment = json(app.post("alice", format!("/api/notes/{}/attachments", id(&note)))
+        .json(&json!({ "file_name": "ticket.txt", "content_type": "text/plain", "data": "SGVsbG8=" }))
+        .dispatch()
+        .await).await;
+
+    let response = app.get("alice", "/api/notes/missing/attachments").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = app.post("alice", "/api/notes/missing/attachments")
+        .json(&json!({ "file_name": "ticket.txt", "content_type": "text/plain", "data": "SGVsbG8=" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = app.get("alice", "/api/attachments/missing").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // Attachments on notes in another workspace count as missing too
+    let response = app.get("bob", format!("/api/attachments/{}", id(&attachment))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
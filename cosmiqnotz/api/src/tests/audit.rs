@@ -0,0 +1,74 @@
+use rocket::http::{ContentType, Status};
+use serde_json::{json, Value};
+
+use super::{id, json, TestApp};
+
+// Alice and Bob can read the audit log; others sign in with `write`
+async fn start() -> TestApp {
+    TestApp::configured(|figment| figment.merge(("admin_users", ["alice", "bob"]))).await
+}
+
+#[tokio::test]
+async fn audit_log_records_note_changes() {
+    let app = start().await;
+    let note = app.create_note("alice", "Groceries", "Milk").await;
+    let mut changed = note.clone();
+    changed["content"] = json!("Milk, eggs");
+    app.put("alice", format!("/api/notes/{}", id(&note))).json(&changed).dispatch().await;
+
+    let events = json(app.get("alice", "/api/admin/audit").dispatch().await).await;
+    let actions: Vec<&str> = events.as_array().expect("events").iter().map(|event| event["action"].as_str().expect("action")).collect();
+    assert_eq!(actions, ["update", "create"]);
+    assert_eq!(events[0]["actor"], "alice");
+    assert_eq!(events[0]["note_id"], note["id"]);
+    assert_eq!(events[0]["before_version"], 1);
+    assert_eq!(events[0]["after_version"], 2);
+
+    let events = json(app.get("alice", "/api/admin/audit?action=create&actor=alice").dispatch().await).await;
+    assert_eq!(events.as_array().expect("events").len(), 1);
+    let events = json(app.get("alice", "/api/admin/audit?since=2999-01-01T00:00:00Z").dispatch().await).await;
+    assert!(events.as_array().expect("events").is_empty());
+}
+
+#[tokio::test]
+async fn audit_log_rejects_unknown_filters() {
+    let app = start().await;
+
+    let response = app.get("alice", "/api/admin/audit?action=rename").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = app.get("alice", "/api/admin/audit?until=yesterday").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[tokio::test]
+async fn audit_export_is_json_lines() {
+    let app = start().await;
+    app.create_note("alice", "Groceries", "").await;
+    app.create_note("alice", "Chores", "").await;
+
+    let response = app.get("alice", "/api/admin/audit/export").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::new("application", "x-ndjson")));
+    let disposition = response.headers().get_one("Content-Disposition").expect("disposition").to_string();
+    assert!(disposition.contains("audit-") && disposition.contains(".jsonl"));
+    let body = response.into_string().await.expect("body");
+    let events: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).expect("event")).collect();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event["action"] == "create"));
+}
+
+#[tokio::test]
+async fn audit_log_only_shows_the_callers_workspaces() {
+    let app = start().await;
+    let note = app.create_note("alice", "Diary", "").await;
+    app.create_note("bob", "Chores", "").await;
+
+    let events = json(app.get("bob", "/api/admin/audit").dispatch().await).await;
+    assert_eq!(events.as_array().expect("events").len(), 1);
+    assert_eq!(events[0]["actor"], "bob");
+    let events = json(app.get("bob", format!("/api/admin/audit?note={}", id(&note))).dispatch().await).await;
+    assert!(events.as_array().expect("events").is_empty());
+    let body = app.get("bob", "/api/admin/audit/export").dispatch().await.into_string().await.expect("body");
+    assert!(!body.contains("alice"));
+}
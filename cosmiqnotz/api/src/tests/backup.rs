@@ -0,0 +1,98 @@
+use rocket::http::Status;
+use serde_json::Value;
+
+use crate::backup::BackupConfig;
+use super::{json, TestApp};
+
+// Alice manages backups; others sign in with `write`
+async fn start() -> TestApp {
+    TestApp::configured(|figment| figment.merge(("admin_users", ["alice"]))).await
+}
+
+async fn create_backup(app: &TestApp) -> Value {
+    let response = app.post("alice", "/api/admin/backups").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    json(response).await
+}
+
+#[tokio::test]
+async fn backups_are_listed_and_verified() {
+    let app = start().await;
+    let backups = json(app.get("alice", "/api/admin/backups").dispatch().await).await;
+    assert!(backups.as_array().expect("backups").is_empty());
+    app.create_note("alice", "Groceries", "").await;
+
+    let backup = create_backup(&app).await;
+    let name = backup["name"].as_str().expect("name");
+    assert!(name.starts_with("cosmiqnotz-"));
+    assert_eq!(backup["note_count"], 1);
+    assert_eq!(backup["sha256"].as_str().expect("sha256").len(), 64);
+
+    let backups = json(app.get("alice", "/api/admin/backups").dispatch().await).await;
+    assert_eq!(backups[0]["name"], name);
+
+    let response = app.post("alice", format!("/api/admin/backups/{}/verify", name)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["checksum_ok"], true);
+    assert_eq!(report["loadable"], true);
+    assert_eq!(report["note_count"], 1);
+}
+
+#[tokio::test]
+async fn restore_brings_back_the_snapshot() {
+    let app = start().await;
+    app.create_note("alice", "Groceries", "").await;
+    let backup = create_backup(&app).await;
+    let name = backup["name"].as_str().expect("name");
+    app.create_note("alice", "Chores", "").await;
+    let snapshot = std::fs::read_to_string(app.backup_dir.join(format!("{}.surql", name))).expect("snapshot");
+    assert!(!snapshot.contains("DEFINE TOKEN"));
+
+    let response = app.post("alice", format!("/api/admin/backups/{}/restore", name)).dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["restored"], name);
+    assert_eq!(report["safety_backup"]["note_count"], 2);
+    // Session tokens issued before the restore still work
+    let notes = json(app.get("alice", "/api/notes").dispatch().await).await;
+    let titles: Vec<&str> = notes.as_array().expect("notes").iter().map(|note| note["title"].as_str().expect("title")).collect();
+    assert_eq!(titles, ["Groceries"]);
+}
+
+#[tokio::test]
+async fn backup_names_are_checked() {
+    let app = start().await;
+
+    let response = app.post("alice", "/api/admin/backups/nope/verify").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = app.post("alice", "/api/admin/backups/..%2Fsecrets/restore").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = app.post("alice", "/api/admin/backups/cosmiqnotz-missing/verify").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn restoring_needs_an_admin() {
+    let app = start().await;
+    app.create_note("alice", "Groceries", "").await;
+    let backup = create_backup(&app).await;
+    let name = backup["name"].as_str().expect("name");
+    app.create_note("alice", "Chores", "").await;
+
+    let response = app.client.post(format!("/api/admin/backups/{}/restore", name)).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.post("bob", format!("/api/admin/backups/{}/restore", name)).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let notes = json(app.get("alice", "/api/notes").dispatch().await).await;
+    assert_eq!(notes.as_array().expect("notes").len(), 2);
+}
+
+#[test]
+fn backups_default_to_the_data_directory() {
+    let dir = BackupConfig::default().backup_dir;
+    assert!(dir.is_absolute(), "{} isn't absolute", dir.display());
+    assert!(dir.ends_with("cosmiqnotz/backups"));
+}
@@ -0,0 +1,62 @@
+use rocket::http::Status;
+use serde_json::json;
+
+use super::{id, json, TestApp};
+
+#[tokio::test]
+async fn batch_applies_every_operation() {
+    let app = TestApp::start().await;
+    let groceries = app.create_note("alice", "Groceries", "").await;
+    let chores = app.create_note("alice", "Chores", "").await;
+
+    let response = app.post("alice", "/api/notes/batch")
+        .json(&json!({ "operations": [
+            { "note_id": id(&groceries), "op": "add_tag", "tag": "home" },
+            { "note_id": id(&groceries), "op": "move", "notebook": "Errands" },
+            { "note_id": id(&chores), "op": "delete" },
+        ] }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["committed"], true);
+    assert_eq!(report["notes"].as_array().expect("notes").len(), 2);
+
+    let note = json(app.get("alice", format!("/api/notes/{}", id(&groceries))).dispatch().await).await;
+    assert_eq!(note["tags"], json!(["home"]));
+    assert_eq!(note["notebook"], "Errands");
+    let trash = json(app.get("alice", "/api/notes?view=trash").dispatch().await).await;
+    assert_eq!(trash[0]["title"], "Chores");
+}
+
+#[tokio::test]
+async fn batch_applies_nothing_when_an_operation_fails() {
+    let app = TestApp::start().await;
+    let groceries = app.create_note("alice", "Groceries", "").await;
+
+    let response = app.post("alice", "/api/notes/batch")
+        .json(&json!({ "operations": [
+            { "note_id": id(&groceries), "op": "add_tag", "tag": "home" },
+            { "note_id": "missing", "op": "delete" },
+            { "note_id": id(&groceries), "op": "remove_tag", "tag": " " },
+        ] }))
+        .dispatch()
+        .await;
+
+    let report = json(response).await;
+    assert_eq!(report["committed"], false);
+    let statuses: Vec<&str> = report["results"].as_array().expect("results").iter().map(|result| result["status"].as_str().expect("status")).collect();
+    assert_eq!(statuses, ["skipped", "not_found", "invalid"]);
+    let note = json(app.get("alice", format!("/api/notes/{}", id(&groceries))).dispatch().await).await;
+    assert_eq!(note["tags"], json!([]));
+}
+
+#[tokio::test]
+async fn batch_rejects_empty_batches() {
+    let app = TestApp::start().await;
+
+    let response = app.post("alice", "/api/notes/batch").json(&json!({ "operations": [] })).dispatch().await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+}
@@ -0,0 +1,217 @@
+use rocket::http::Status;
+use serde_json::json;
+
+use super::{id, in_workspace, json, note_body, TestApp};
+
+#[tokio::test]
+async fn comments_are_listed_oldest_first() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "Ship it on Friday").await;
+    let path = format!("/api/notes/{}/comments", id(&note));
+
+    let thread = json(app.post("alice", &path)
+        .json(&json!({ "body": "Friday?", "anchor": { "start": 11, "end": 17, "quote": "Friday" } }))
+        .dispatch()
+        .await).await;
+    let response = app.post("alice", &path)
+        .json(&json!({ "body": "Or Monday", "parent_id": id(&thread) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let comments = json(app.get("alice", &path).dispatch().await).await;
+    assert_eq!(comments.as_array().expect("comments").len(), 2);
+    assert_eq!(comments[0]["body"], "Friday?");
+    assert_eq!(comments[0]["anchor"]["quote"], "Friday");
+    assert_eq!(comments[1]["parent_id"], thread["id"]);
+}
+
+#[tokio::test]
+async fn viewers_can_comment() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "viewer")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace)
+        .json(&note_body("Plan", ""))
+        .dispatch()
+        .await).await;
+
+    let response = in_workspace(app.post("bob", format!("/api/notes/{}/comments", id(&note))), &workspace)
+        .json(&json!({ "body": "Looks good" }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn comments_on_encrypted_notes_cannot_quote_them() {
+    let app = TestApp::start().await;
+    let mut body = note_body("c2VhbGVk", "c2VhbGVk");
+    body["encryption"] = json!({
+        "algorithm": "AES-256-GCM",
+        "kdf": "PBKDF2-SHA256",
+        "iterations": 100000,
+        "salt": "c2FsdA==",
+        "title_nonce": "bm9uY2U=",
+        "content_nonce": "bm9uY2U=",
+    });
+    let note = json(app.post("alice", "/api/notes").json(&body).dispatch().await).await;
+    let path = format!("/api/notes/{}/comments", id(&note));
+
+    let response = app.post("alice", &path)
+        .json(&json!({ "body": "Friday?", "anchor": { "start": 0, "end": 6, "quote": "Friday" } }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.post("alice", &path)
+        .json(&json!({ "body": "Friday?" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn create_comment_rejects_bad_comments() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "").await;
+    let path = format!("/api/notes/{}/comments", id(&note));
+    let thread = json(app.post("alice", &path).json(&json!({ "body": "Thread" })).dispatch().await).await;
+    let reply = json(app.post("alice", &path)
+        .json(&json!({ "body": "Reply", "parent_id": id(&thread) }))
+        .dispatch()
+        .await).await;
+
+    let bad = [
+        json!({ "body": "  " }),
+        json!({ "body": "Backwards", "anchor": { "start": 5, "end": 2, "quote": "" } }),
+        json!({ "body": "Too deep", "parent_id": id(&reply) }),
+        json!({ "body": "No parent", "parent_id": "missing" }),
+    ];
+    for body in bad {
+        let response = app.post("alice", &path).json(&body).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", body);
+    }
+}
+
+#[tokio::test]
+async fn comments_on_missing_notes_are_not_found() {
+    let app = TestApp::start().await;
+
+    let response = app.get("alice", "/api/notes/missing/comments").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = app.post("alice", "/api/notes/missing/comments")
+        .json(&json!({ "body": "Hello?" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn update_comment_changes_the_body() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "").await;
+    let comment = json(app.post("alice", format!("/api/notes/{}/comments", id(&note)))
+        .json(&json!({ "body": "Frday" }))
+        .dispatch()
+        .await).await;
+    let path = format!("/api/comments/{}", id(&comment));
+
+    let response = app.put("alice", &path).json(&json!({ "body": "Friday" })).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["body"], "Friday");
+
+    let response = app.put("alice", &path).json(&json!({ "body": "" })).dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = app.put("alice", "/api/comments/missing").json(&json!({ "body": "Friday" })).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn resolved_threads_can_be_hidden_and_reopened() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "").await;
+    let path = format!("/api/notes/{}/comments", id(&note));
+    let thread = json(app.post("alice", &path).json(&json!({ "body": "Thread" })).dispatch().await).await;
+    let reply = json(app.post("alice", &path)
+        .json(&json!({ "body": "Reply", "parent_id": id(&thread) }))
+        .dispatch()
+        .await).await;
+    app.post("alice", &path).json(&json!({ "body": "Open" })).dispatch().await;
+
+    let response = app.post("alice", format!("/api/comments/{}/resolve", id(&thread))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let resolved = json(response).await;
+    assert_eq!(resolved["resolved"], true);
+    assert_eq!(resolved["resolved_by"], "alice");
+
+    let open = json(app.get("alice", format!("{}?include_resolved=false", path)).dispatch().await).await;
+    assert_eq!(open.as_array().expect("comments").len(), 1);
+    assert_eq!(open[0]["body"], "Open");
+    let all = json(app.get("alice", &path).dispatch().await).await;
+    assert_eq!(all.as_array().expect("comments").len(), 3);
+
+    // Only whole threads are resolved
+    let response = app.post("alice", format!("/api/comments/{}/resolve", id(&reply))).dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = app.post("alice", format!("/api/comments/{}/unresolve", id(&thread))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let reopened = json(response).await;
+    assert_eq!(reopened["resolved"], false);
+    assert!(reopened.get("resolved_by").is_none());
+
+    let response = app.post("alice", "/api/comments/missing/unresolve").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn comments_belong_to_whoever_posts_them() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace)
+        .json(&note_body("Plan", ""))
+        .dispatch()
+        .await).await;
+
+    let thread = json(in_workspace(app.post("alice", format!("/api/notes/{}/comments", id(&note))), &workspace)
+        .json(&json!({ "author": "bob", "body": "Friday?" }))
+        .dispatch()
+        .await).await;
+    assert_eq!(thread["author"], "alice");
+    let path = format!("/api/comments/{}", id(&thread));
+
+    let response = in_workspace(app.put("bob", &path), &workspace).json(&json!({ "body": "Monday" })).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    let response = in_workspace(app.delete("bob", &path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    // Anyone on the note may resolve a thread, and is recorded as having done so
+    let response = in_workspace(app.post("bob", format!("{}/resolve", path)), &workspace)
+        .json(&json!({ "user": "alice" }))
+        .dispatch()
+        .await;
+    assert_eq!(json(response).await["resolved_by"], "bob");
+}
+
+#[tokio::test]
+async fn delete_comment_removes_the_thread_with_its_replies() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "").await;
+    let path = format!("/api/notes/{}/comments", id(&note));
+    let thread = json(app.post("alice", &path).json(&json!({ "body": "Thread" })).dispatch().await).await;
+    app.post("alice", &path)
+        .json(&json!({ "body": "Reply", "parent_id": id(&thread) }))
+        .dispatch()
+        .await;
+
+    let response = app.delete("alice", format!("/api/comments/{}", id(&thread))).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    let comments = json(app.get("alice", &path).dispatch().await).await;
+    assert!(comments.as_array().expect("comments").is_empty());
+    let response = app.delete("alice", format!("/api/comments/{}", id(&thread))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
@@ -0,0 +1,64 @@
+use rocket::http::{Header, Status};
+
+use super::TestApp;
+
+const ORIGIN: &str = "http://localhost:8080";
+
+#[tokio::test]
+async fn preflight_allows_the_route_methods() {
+    let app = TestApp::start().await;
+
+    let response = app.client.options("/api/notes/groceries")
+        .header(Header::new("Origin", ORIGIN))
+        .header(Header::new("Access-Control-Request-Method", "PUT"))
+        .header(Header::new("Access-Control-Request-Headers", "Content-Type, If-Match, X-Unknown"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NoContent);
+    let headers = response.headers();
+    assert_eq!(headers.get_one("Access-Control-Allow-Origin"), Some(ORIGIN));
+    assert_eq!(headers.get_one("Access-Control-Allow-Credentials"), Some("true"));
+    assert_eq!(headers.get_one("Access-Control-Allow-Methods"), Some("DELETE, GET, PUT"));
+    assert_eq!(headers.get_one("Access-Control-Allow-Headers"), Some("Content-Type, If-Match"));
+    assert_eq!(headers.get_one("Access-Control-Max-Age"), Some("600"));
+}
+
+#[tokio::test]
+async fn preflight_rejects_methods_the_route_lacks() {
+    let app = TestApp::start().await;
+
+    let response = app.client.options("/api/notes/groceries/html")
+        .header(Header::new("Origin", ORIGIN))
+        .header(Header::new("Access-Control-Request-Method", "DELETE"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::MethodNotAllowed);
+    assert!(response.headers().get_one("Access-Control-Allow-Methods").is_none());
+}
+
+#[tokio::test]
+async fn unknown_origins_get_no_cors_headers() {
+    let app = TestApp::start().await;
+
+    let response = app.client.get("/health")
+        .header(Header::new("Origin", "https://evil.example"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.headers().get_one("Access-Control-Allow-Origin").is_none());
+    assert_eq!(response.headers().get_one("Vary"), Some("Origin"));
+}
+
+#[tokio::test]
+async fn allowed_origins_can_read_exposed_headers() {
+    let app = TestApp::start().await;
+
+    let response = app.get("alice", "/api/notes").header(Header::new("Origin", ORIGIN)).dispatch().await;
+
+    assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), Some(ORIGIN));
+    let exposed = response.headers().get_one("Access-Control-Expose-Headers").expect("exposed headers");
+    assert!(exposed.contains("ETag"));
+}
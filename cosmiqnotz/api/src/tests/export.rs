@@ -0,0 +1,83 @@
+use rocket::http::{ContentType, Status};
+use std::io::Cursor;
+
+use serde_json::json;
+
+use super::{id, note_body, TestApp};
+
+#[tokio::test]
+async fn export_a_single_note_as_markdown_or_html() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "- Milk").await;
+
+    let response = app.get("alice", format!("/api/export?format=md&note={}", id(&note))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::new("text", "markdown")));
+    let disposition = response.headers().get_one("Content-Disposition").expect("Content-Disposition").to_string();
+    assert!(disposition.starts_with("attachment; filename=\"groceries-"));
+    assert!(disposition.ends_with(".md\""));
+    assert!(response.into_string().await.expect("body").contains("- Milk"));
+
+    let response = app.get("alice", format!("/api/export?format=html&note={}", id(&note))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    assert!(response.into_string().await.expect("body").contains("<li>Milk</li>"));
+}
+
+#[tokio::test]
+async fn export_everything_as_a_zip_archive() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Groceries", "Milk").await;
+    app.create_note("alice", "Chores", "Bins").await;
+    app.create_note("bob", "Bob's", "Not in Alice's export").await;
+
+    let response = app.get("alice", "/api/export").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::ZIP));
+    assert_eq!(response.headers().get_one("Content-Disposition"), Some("attachment; filename=\"cosmiqnotz-export.zip\""));
+    let bytes = response.into_bytes().await.expect("body");
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("zip archive");
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].starts_with("Unfiled/chores-"));
+    assert!(names[1].starts_with("Unfiled/groceries-"));
+}
+
+#[tokio::test]
+async fn archive_entries_stay_inside_the_archive() {
+    let app = TestApp::start().await;
+    let mut body = note_body("Trip", "");
+    body["notebook"] = json!("../../etc");
+    let note = super::json(app.post("alice", "/api/notes").json(&body).dispatch().await).await;
+    app.post("alice", format!("/api/notes/{}/attachments", id(&note)))
+        .json(&json!({ "file_name": "../../.bashrc", "content_type": "text/plain", "data": "SGVsbG8=" }))
+        .dispatch()
+        .await;
+    let mut body = note_body("Root", "");
+    body["notebook"] = json!("..");
+    app.post("alice", "/api/notes").json(&body).dispatch().await;
+
+    let response = app.get("alice", "/api/export").dispatch().await;
+
+    let bytes = response.into_bytes().await.expect("body");
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("zip archive");
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(names.len(), 3);
+    assert!(names[0].starts_with("..-..-etc/trip-"));
+    assert!(names[1].starts_with("Unfiled/root-"));
+    assert_eq!(names[2], format!("attachments/{}/..-..-.bashrc", id(&note)));
+}
+
+#[tokio::test]
+async fn export_rejects_unknown_formats_and_missing_notes() {
+    let app = TestApp::start().await;
+
+    let response = app.get("alice", "/api/export?format=pdf").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = app.get("alice", "/api/export?format=md&note=missing").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
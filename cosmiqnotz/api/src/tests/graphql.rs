@@ -0,0 +1,101 @@
+use rocket::http::{ContentType, RawStr, Status};
+use rocket::tokio::join;
+use rocket::tokio::time::sleep;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::{id, json, next_event, TestApp};
+
+// Runs a query or mutation as `user` and returns the whole response
+async fn graphql(app: &TestApp, user: &str, query: &str, variables: Value) -> Value {
+    let response = app.post(user, "/api/graphql")
+        .json(&json!({ "query": query, "variables": variables }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    json(response).await
+}
+
+fn error_code(response: &Value) -> &str {
+    response["errors"][0]["extensions"]["code"].as_str().expect("error code")
+}
+
+#[tokio::test]
+async fn notes_are_created_and_queried_with_their_links() {
+    let app = TestApp::start().await;
+    let created = graphql(&app, "alice", r#"
+        mutation { createNote(input: { title: "Trip", content: "See [[Packing]]", tags: ["travel"] }) { id title version } }
+    "#, json!({})).await;
+    assert!(created.get("errors").is_none(), "{}", created);
+    assert_eq!(created["data"]["createNote"]["version"], 1);
+    app.create_note("alice", "Packing", "Socks").await;
+
+    let response = graphql(&app, "alice", r#"
+        query($id: ID!) {
+            note(id: $id) { title links { title backlinks { title } } author { username } }
+            notes(tag: "travel") { title }
+            me { username }
+        }
+    "#, json!({ "id": created["data"]["createNote"]["id"] })).await;
+
+    let data = &response["data"];
+    assert_eq!(data["note"]["title"], "Trip");
+    assert_eq!(data["note"]["links"][0]["title"], "Packing");
+    assert_eq!(data["note"]["links"][0]["backlinks"][0]["title"], "Trip");
+    assert_eq!(data["note"]["author"]["username"], "alice");
+    assert_eq!(data["notes"], json!([{ "title": "Trip" }]));
+    assert_eq!(data["me"]["username"], "alice");
+}
+
+#[tokio::test]
+async fn mutations_answer_with_rest_error_codes() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "").await;
+    let update = r#"
+        mutation($id: ID!, $version: Int) { updateNote(id: $id, input: { content: "Later" }, expectedVersion: $version) { version } }
+    "#;
+
+    let response = graphql(&app, "alice", update, json!({ "id": id(&note), "version": 7 })).await;
+    assert_eq!(error_code(&response), "precondition_failed");
+    let response = graphql(&app, "alice", update, json!({ "id": "missing" })).await;
+    assert_eq!(error_code(&response), "not_found");
+    let response = graphql(&app, "alice", r#"{ notes(view: "everything") { id } }"#, json!({})).await;
+    assert_eq!(error_code(&response), "bad_request");
+
+    let response = graphql(&app, "alice", update, json!({ "id": id(&note), "version": 1 })).await;
+    assert_eq!(response["data"]["updateNote"]["version"], 2);
+    let response = graphql(&app, "alice", r#"mutation($id: ID!) { setNoteFlags(id: $id, flags: { pinned: true }) { pinned } }"#, json!({ "id": id(&note) })).await;
+    assert_eq!(response["data"]["setNoteFlags"]["pinned"], true);
+    let response = graphql(&app, "alice", r#"mutation($id: ID!) { deleteNote(id: $id) }"#, json!({ "id": id(&note) })).await;
+    assert_eq!(response["data"]["deleteNote"], true);
+}
+
+#[tokio::test]
+async fn schema_is_served_as_sdl() {
+    let app = TestApp::start().await;
+
+    let response = app.client.get("/api/graphql/schema").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::Plain));
+    let sdl = response.into_string().await.expect("body");
+    assert!(sdl.contains("createNote"));
+    assert!(sdl.contains("noteChanges"));
+}
+
+#[tokio::test]
+async fn subscriptions_stream_note_changes() {
+    let app = TestApp::start().await;
+    let query = RawStr::new("subscription { noteChanges { kind note { title } } }").percent_encode();
+    let mut response = app.get("alice", format!("/api/graphql/stream?query={}", query)).dispatch().await;
+    assert_eq!(response.content_type(), Some(ContentType::EventStream));
+
+    // The subscription starts listening once the stream is first read
+    let (event, _) = join!(next_event(&mut response), async {
+        sleep(Duration::from_millis(200)).await;
+        app.create_note("alice", "Groceries", "").await
+    });
+
+    assert!(event.contains(r#""kind":"CREATED""#), "{}", event);
+    assert!(event.contains(r#""title":"Groceries""#), "{}", event);
+}
@@ -0,0 +1,128 @@
+use rocket::http::Status;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+
+use super::{id, json, note_body, TestApp};
+
+// A Markdown folder with one note in a `Travel` notebook and a file that isn't one
+fn markdown_folder() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("cosmiqnotz-import-{}", uuid::Uuid::new_v4().simple()));
+    fs::create_dir_all(root.join("Travel")).expect("create folder");
+    fs::write(root.join("Travel").join("packing.md"), "# Packing\n\n- [ ] Passport\n").expect("write note");
+    fs::write(root.join("readme.txt"), "Not a note").expect("write file");
+    root
+}
+
+#[tokio::test]
+async fn import_a_markdown_folder() {
+    let app = TestApp::start().await;
+    let root = markdown_folder();
+    let request = json!({ "source": "markdown", "path": root });
+
+    let mut dry_run = request.clone();
+    dry_run["dry_run"] = json!(true);
+    let report = json(app.post("alice", "/api/import").json(&dry_run).dispatch().await).await;
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["skipped"], 1);
+    let notes = json(app.get("alice", "/api/notes").dispatch().await).await;
+    assert!(notes.as_array().expect("notes").is_empty());
+
+    let response = app.post("alice", "/api/import").json(&request).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["imported"], 1);
+    let imported: Vec<&Value> = report["files"].as_array().expect("files").iter().filter(|file| file["status"] == "imported").collect();
+    assert_eq!(imported[0]["title"], "Packing");
+    assert_eq!(imported[0]["notebook"], "Travel");
+
+    let notes = json(app.get("alice", "/api/notes").dispatch().await).await;
+    assert_eq!(notes[0]["title"], "Packing");
+    let tasks = json(app.get("alice", "/api/tasks").dispatch().await).await;
+    assert_eq!(tasks[0]["text"], "Passport");
+
+    fs::remove_dir_all(root).ok();
+}
+
+#[tokio::test]
+async fn import_a_missing_path_is_not_found() {
+    let app = TestApp::start().await;
+
+    let response = app.post("alice", "/api/import")
+        .json(&json!({ "source": "obsidian", "path": "/nowhere/to/be/found" }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn import_stays_inside_the_import_root() {
+    let app = TestApp::start().await;
+
+    // An existing folder outside the root looks no different from a missing one
+    let response = app.post("alice", "/api/import")
+        .json(&json!({ "source": "markdown", "path": "/etc" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let root = markdown_folder();
+    let response = app.post("alice", "/api/import")
+        .json(&json!({ "source": "markdown", "path": root.join("..").join("..").join("etc") }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    fs::remove_dir_all(root).ok();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn import_skips_symlinks() {
+    let app = TestApp::start().await;
+    let root = markdown_folder();
+    std::os::unix::fs::symlink(&root, root.join("Travel").join("loop")).expect("link folder");
+    std::os::unix::fs::symlink("/etc/hostname", root.join("hostname.md")).expect("link file");
+
+    let response = app.post("alice", "/api/import")
+        .json(&json!({ "source": "markdown", "path": root, "dry_run": true }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["files"].as_array().expect("files").len(), 2);
+
+    fs::remove_dir_all(root).ok();
+}
+
+#[tokio::test]
+async fn import_notes_gives_them_new_ids() {
+    let app = TestApp::start().await;
+    let existing = app.create_note("alice", "Existing", "Keep me").await;
+    let mut copy = note_body("Copy", "From another server");
+    copy["id"] = existing["id"].clone();
+    copy["created_by"] = json!("mallory");
+    let notes = json!([copy, note_body("Second", "")]);
+
+    let report = json(app.post("alice", "/api/notes/import?dry_run=true").json(&notes).dispatch().await).await;
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["imported"], 2);
+    assert_eq!(report["files"][0]["status"], "would_import");
+
+    let response = app.post("alice", "/api/notes/import").json(&notes).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let report = json(response).await;
+    assert_eq!(report["imported"], 2);
+    assert_ne!(report["files"][0]["note_id"], existing["id"]);
+
+    let listed = json(app.get("alice", "/api/notes").dispatch().await).await;
+    assert_eq!(listed.as_array().expect("notes").len(), 3);
+    // Imported notes belong to whoever imports them, whatever the file says
+    assert!(listed.as_array().expect("notes").iter().all(|note| note["created_by"] == "alice"));
+    let kept = json(app.get("alice", format!("/api/notes/{}", id(&existing))).dispatch().await).await;
+    assert_eq!(kept["content"], "Keep me");
+}
@@ -0,0 +1,74 @@
+use rocket::http::Status;
+use serde_json::json;
+
+use super::{error_code, id, in_workspace, json, note_body, TestApp};
+
+#[tokio::test]
+async fn only_the_lock_holder_may_save() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace).json(&note_body("Plan", "")).dispatch().await).await;
+    let lock_path = format!("/api/notes/{}/lock", id(&note));
+    let note_path = format!("/api/notes/{}", id(&note));
+
+    let response = in_workspace(app.post("alice", &lock_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["locked_by"], "alice");
+
+    let response = in_workspace(app.post("bob", &lock_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(json(response).await["locked_by"], "alice");
+
+    let response = in_workspace(app.put("bob", &note_path), &workspace).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::Locked);
+    assert_eq!(error_code(response).await, "locked");
+    let response = in_workspace(app.delete("bob", &note_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Locked);
+
+    // The holder saves as usual, and the lock stays in place
+    let response = in_workspace(app.put("alice", &note_path), &workspace).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["locked_by"], "alice");
+}
+
+#[tokio::test]
+async fn locks_are_released_by_the_holder_or_forced_by_owners() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace).json(&note_body("Plan", "")).dispatch().await).await;
+    let lock_path = format!("/api/notes/{}/lock", id(&note));
+
+    in_workspace(app.post("alice", &lock_path), &workspace).dispatch().await;
+    let response = in_workspace(app.delete("bob", &lock_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let response = in_workspace(app.delete("bob", format!("{}?force=true", lock_path)), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = in_workspace(app.delete("alice", &lock_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = in_workspace(app.post("bob", &lock_path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = in_workspace(app.delete("alice", format!("{}?force=true", lock_path)), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let note = json(in_workspace(app.get("alice", format!("/api/notes/{}", id(&note))), &workspace).dispatch().await).await;
+    assert!(note.get("locked_by").is_none());
+}
+
+#[tokio::test]
+async fn locks_need_a_note_outside_the_trash() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Old", "").await;
+    app.post("alice", "/api/notes/batch")
+        .json(&json!({ "operations": [{ "note_id": id(&note), "op": "delete" }] }))
+        .dispatch()
+        .await;
+
+    let response = app.post("alice", format!("/api/notes/{}/lock", id(&note))).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.post("alice", "/api/notes/missing/lock").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.delete("alice", "/api/notes/missing/lock").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
@@ -0,0 +1,31 @@
+use rocket::http::Status;
+
+use super::{json, TestApp};
+
+#[tokio::test]
+async fn health_and_readiness() {
+    let app = TestApp::start().await;
+
+    let response = app.client.get("/health").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["status"], "ok");
+
+    // The schema from init.surql is loaded, so every table is there
+    let response = app.client.get("/ready").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["status"], "ready");
+}
+
+#[tokio::test]
+async fn metrics_count_requests_and_notes() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Groceries", "").await;
+
+    let response = app.client.get("/metrics").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type().map(|content_type| content_type.sub().to_string()), Some("plain".to_string()));
+    let body = response.into_string().await.expect("body");
+    assert!(body.contains("cosmiqnotz_notes{encrypted=\"false\"} 1"));
+    assert!(body.contains("cosmiqnotz_http_requests_total{method=\"POST\",route=\"/api/notes\",status=\"200\"} 1"));
+}
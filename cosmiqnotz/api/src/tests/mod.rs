@@ -0,0 +1,245 @@
+// Integration tests for the API's routes. Each test boots the app through Rocket's
+// local client against its own SurrealDB server, started with `surreal start memory`
+// on a free port and loaded with init.surql. A real server is needed because sessions
+// open connections of their own and the backup routes call `surreal export`, so the
+// tests fail rather than pass unchecked when the `surreal` CLI isn't on the PATH.
+
+use rocket::figment::Figment;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::{Client, LocalRequest, LocalResponse};
+use rocket::tokio::io::AsyncReadExt;
+use rocket::tokio::time::{sleep, timeout};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use surrealdb::engine::remote::ws::{Client as WsClient, Ws};
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
+use std::fmt::Display;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::sessions::Sessions;
+use crate::workspaces::WORKSPACE_HEADER;
+use crate::{app, AppState, DB_DATABASE, DB_NAMESPACE};
+
+mod api_keys;
+mod attachments;
+mod audit;
+mod backup;
+mod batches;
+mod comments;
+mod cors;
+mod export;
+mod graphql;
+mod import;
+mod locks;
+mod metrics;
+mod notes;
+mod permissions;
+mod reminders;
+mod render;
+mod sessions;
+mod shares;
+mod tasks;
+mod webhooks;
+mod workspaces;
+
+const SCHEMA: &str = include_str!("../../../migrations/init.surql");
+
+// A SurrealDB server keeping everything in memory, stopped when dropped
+struct TestDatabase {
+    address: String,
+    server: Child,
+}
+
+impl TestDatabase {
+    async fn start() -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("find a free port")
+            .port();
+        let address = format!("127.0.0.1:{}", port);
+        let server = Command::new("surreal")
+            .args(["start", "--log", "none", "--user", "root", "--pass", "root", "--bind", address.as_str(), "memory"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("start surreal; install the SurrealDB CLI from https://surrealdb.com/install");
+
+        let database = TestDatabase { address, server };
+        let db = database.connect().await;
+        db.query(SCHEMA).await.expect("load schema").check().expect("apply schema");
+        database
+    }
+
+    // A root connection to the test database, once the server takes connections
+    async fn connect(&self) -> Surreal<WsClient> {
+        for _ in 0..100 {
+            let Ok(db) = Surreal::new::<Ws>(self.address.as_str()).await else {
+                sleep(Duration::from_millis(100)).await;
+                continue;
+            };
+            db.signin(Root { username: "root", password: "root" }).await.expect("sign in as root");
+            db.use_ns(DB_NAMESPACE).use_db(DB_DATABASE).await.expect("use database");
+            return db;
+        }
+        panic!("SurrealDB didn't start on {}", self.address);
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+// The API with a database and backup directory of its own
+pub struct TestApp {
+    pub client: Client,
+    pub backup_dir: PathBuf,
+    _database: TestDatabase,
+}
+
+impl TestApp {
+    pub async fn start() -> Self {
+        Self::configured(|figment| figment).await
+    }
+
+    // `configure` can override the test configuration, e.g. to require API keys
+    pub async fn configured(configure: impl FnOnce(Figment) -> Figment) -> Self {
+        let database = TestDatabase::start().await;
+        let backup_dir = std::env::temp_dir().join(format!("cosmiqnotz-test-{}", uuid::Uuid::new_v4().simple()));
+        let figment = Figment::from(rocket::Config::debug_default())
+            .merge(("log_level", "off"))
+            .merge(("db_address", database.address.as_str()))
+            .merge(("rate_limit_per_minute", 0))
+            .merge(("backup_dir", backup_dir.to_string_lossy().to_string()))
+            .merge(("backup_interval_hours", 0))
+            // Import tests write their folders to the temporary directory
+            .merge(("import_root", std::env::temp_dir().to_string_lossy().to_string()))
+            .merge(("webhook_max_attempts", 1))
+            .merge(("webhook_timeout_seconds", 5));
+
+        let client = Client::tracked(app(configure(figment)).await)
+            .await
+            .expect("valid rocket instance");
+        TestApp { client, backup_dir, _database: database }
+    }
+
+    pub fn state(&self) -> &Arc<AppState> {
+        self.client.rocket().state::<Arc<AppState>>().expect("application state")
+    }
+
+    // Requests made as `user`, in their personal workspace unless `in_workspace` picks another
+    pub fn get(&self, user: &str, uri: impl Display) -> LocalRequest<'_> {
+        self.as_user(self.client.get(uri.to_string()), user)
+    }
+
+    pub fn post(&self, user: &str, uri: impl Display) -> LocalRequest<'_> {
+        self.as_user(self.client.post(uri.to_string()), user)
+    }
+
+    pub fn put(&self, user: &str, uri: impl Display) -> LocalRequest<'_> {
+        self.as_user(self.client.put(uri.to_string()), user)
+    }
+
+    pub fn delete(&self, user: &str, uri: impl Display) -> LocalRequest<'_> {
+        self.as_user(self.client.delete(uri.to_string()), user)
+    }
+
+    // Signed in as `user` with a session token, as the frontend is
+    pub fn token(&self, user: &str) -> String {
+        let sessions = self.client.rocket().state::<Arc<Sessions>>().expect("sessions");
+        sessions.issue(user).expect("session token").0
+    }
+
+    fn as_user<'c>(&'c self, request: LocalRequest<'c>, user: &str) -> LocalRequest<'c> {
+        request.header(bearer(&self.token(user)))
+    }
+
+    pub async fn create_note(&self, user: &str, title: &str, content: &str) -> Value {
+        let response = self.post(user, "/api/notes").json(&note_body(title, content)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        json(response).await
+    }
+
+    // A workspace owned by `owner`, with each of `members` given their role
+    pub async fn create_workspace(&self, owner: &str, members: &[(&str, &str)]) -> String {
+        let response = self.post(owner, "/api/workspaces").json(&serde_json::json!({ "name": "Team" })).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let workspace = json(response).await["workspace"]["id"].as_str().expect("workspace id").to_string();
+
+        for (member, role) in members {
+            let response = self.put(owner, format!("/api/workspaces/{}/members/{}", workspace, member))
+                .json(&serde_json::json!({ "role": role }))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+        workspace
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.backup_dir);
+    }
+}
+
+pub fn bearer(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
+
+pub fn in_workspace<'c>(request: LocalRequest<'c>, workspace: &str) -> LocalRequest<'c> {
+    request.header(Header::new(WORKSPACE_HEADER, workspace.to_string()))
+}
+
+// A new note as clients send it; the epoch `created_at` asks the API to fill it in
+pub fn note_body(title: &str, content: &str) -> Value {
+    serde_json::json!({
+        "title": title,
+        "content": content,
+        "created_at": "1970-01-01T00:00:00Z",
+        "updated_at": "1970-01-01T00:00:00Z",
+        "created_by": "",
+        "is_shared": false,
+        "shared_with": [],
+        "version": 1,
+    })
+}
+
+// The next server-sent event on a stream, as sent
+pub async fn next_event(response: &mut LocalResponse<'_>) -> String {
+    let mut event = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !event.ends_with(b"\n\n") {
+        let read = timeout(Duration::from_secs(5), response.read(&mut chunk))
+            .await
+            .expect("event in time")
+            .expect("read stream");
+        assert!(read > 0, "stream ended");
+        event.extend_from_slice(&chunk[..read]);
+    }
+    String::from_utf8(event).expect("UTF-8 event")
+}
+
+pub async fn json(response: LocalResponse<'_>) -> Value {
+    response.into_json().await.expect("JSON body")
+}
+
+// The `code` of an error response, e.g. `not_found`
+pub async fn error_code(response: LocalResponse<'_>) -> String {
+    json(response).await["error"]["code"].as_str().expect("error code").to_string()
+}
+
+pub fn id(value: &Value) -> String {
+    value["id"].as_str().expect("id").to_string()
+}
+
+pub fn time(value: &Value, field: &str) -> DateTime<Utc> {
+    value[field].as_str().and_then(|at| at.parse().ok()).expect("timestamp")
+}
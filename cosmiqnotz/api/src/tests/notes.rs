@@ -0,0 +1,292 @@
+use rocket::http::{Header, Status};
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::{error_code, id, in_workspace, json, note_body, time, TestApp};
+
+#[tokio::test]
+async fn create_note_fills_in_missing_created_at() {
+    let app = TestApp::start().await;
+    let before = Utc::now() - Duration::seconds(1);
+
+    let note = app.create_note("alice", "Groceries", "Milk").await;
+
+    assert!(time(&note, "created_at") >= before);
+    assert!(time(&note, "updated_at") >= before);
+    assert_eq!(note["version"], 1);
+}
+
+#[tokio::test]
+async fn create_note_keeps_created_at_and_refreshes_updated_at() {
+    let app = TestApp::start().await;
+    let before = Utc::now() - Duration::seconds(1);
+    let mut body = note_body("Imported", "From an older device");
+    body["created_at"] = json!("2021-03-04T05:06:07Z");
+    body["updated_at"] = json!("2021-03-04T05:06:07Z");
+
+    let response = app.post("alice", "/api/notes").json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let note = json(response).await;
+
+    assert_eq!(time(&note, "created_at").to_rfc3339(), "2021-03-04T05:06:07+00:00");
+    assert!(time(&note, "updated_at") >= before);
+}
+
+#[tokio::test]
+async fn create_note_sets_author_and_workspace() {
+    let app = TestApp::start().await;
+    let mut body = note_body("Mine", "");
+    body["created_by"] = json!("mallory");
+    body["workspace_id"] = json!("somewhere_else");
+
+    let note = json(app.post("alice", "/api/notes").json(&body).dispatch().await).await;
+    let workspaces = json(app.get("alice", "/api/workspaces").dispatch().await).await;
+
+    assert_eq!(note["created_by"], "alice");
+    assert_eq!(note["workspace_id"], workspaces[0]["workspace"]["id"]);
+}
+
+#[tokio::test]
+async fn create_note_needs_a_user() {
+    let app = TestApp::start().await;
+
+    let response = app.client.post("/api/notes").json(&note_body("Anonymous", "")).dispatch().await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(error_code(response).await, "unauthorized");
+}
+
+#[tokio::test]
+async fn create_note_rejects_invalid_bodies() {
+    let app = TestApp::start().await;
+
+    let response = app.post("alice", "/api/notes").json(&json!({ "title": "No content" })).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let mut body = note_body("Plan", "");
+    body["notebook"] = json!("  ");
+    let response = app.post("alice", "/api/notes").json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let mut body = note_body("Plan", "");
+    body["tags"] = json!(["work", ""]);
+    let response = app.post("alice", "/api/notes").json(&body).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[tokio::test]
+async fn viewers_cannot_create_notes() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "viewer")]).await;
+
+    let response = in_workspace(app.post("bob", "/api/notes"), &workspace)
+        .json(&note_body("Not allowed", ""))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+}
+
+#[tokio::test]
+async fn get_note_returns_the_note_with_an_etag() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "Milk").await;
+
+    let response = app.get("alice", format!("/api/notes/{}", id(&note))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let etag = response.headers().get_one("ETag").expect("ETag").to_string();
+    assert_eq!(json(response).await["title"], "Groceries");
+
+    let response = app.get("alice", format!("/api/notes/{}", id(&note)))
+        .header(Header::new("If-None-Match", etag))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotModified);
+}
+
+#[tokio::test]
+async fn get_note_not_found() {
+    let app = TestApp::start().await;
+
+    let response = app.get("alice", "/api/notes/missing").dispatch().await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(error_code(response).await, "not_found");
+}
+
+#[tokio::test]
+async fn notes_in_other_workspaces_are_not_found() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Private", "").await;
+
+    let response = app.get("bob", format!("/api/notes/{}", id(&note))).dispatch().await;
+
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn get_notes_lists_views() {
+    let app = TestApp::start().await;
+    let pinned = app.create_note("alice", "Pinned", "").await;
+    let archived = app.create_note("alice", "Archived", "").await;
+    app.create_note("alice", "Plain", "").await;
+    for (note, flags) in [(&pinned, json!({ "pinned": true })), (&archived, json!({ "archived": true }))] {
+        let response = app.put("alice", format!("/api/notes/{}/flags", id(note))).json(&flags).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let all = json(app.get("alice", "/api/notes").dispatch().await).await;
+    let titles: Vec<&str> = all.as_array().expect("notes").iter().map(|note| note["title"].as_str().expect("title")).collect();
+    assert_eq!(titles, ["Pinned", "Plain"]);
+
+    let archived = json(app.get("alice", "/api/notes?view=archived").dispatch().await).await;
+    assert_eq!(archived.as_array().expect("notes").len(), 1);
+    assert_eq!(archived[0]["title"], "Archived");
+
+    let response = app.get("alice", "/api/notes?view=everything").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[tokio::test]
+async fn get_notes_answers_not_modified_for_a_current_list() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Groceries", "").await;
+
+    let response = app.get("alice", "/api/notes").dispatch().await;
+    let etag = response.headers().get_one("ETag").expect("ETag").to_string();
+
+    let response = app.get("alice", "/api/notes").header(Header::new("If-None-Match", etag.clone())).dispatch().await;
+    assert_eq!(response.status(), Status::NotModified);
+
+    app.create_note("alice", "Chores", "").await;
+    let response = app.get("alice", "/api/notes").header(Header::new("If-None-Match", etag)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn search_notes_matches_title_and_content() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Groceries", "Milk and eggs").await;
+    app.create_note("alice", "Chores", "Take out the bins").await;
+
+    let found = json(app.get("alice", "/api/notes/search?q=MILK").dispatch().await).await;
+    assert_eq!(found.as_array().expect("notes").len(), 1);
+    assert_eq!(found[0]["title"], "Groceries");
+
+    let found = json(app.get("alice", "/api/notes/search?q=chores").dispatch().await).await;
+    assert_eq!(found[0]["title"], "Chores");
+
+    let found = json(app.get("alice", "/api/notes/search?q=nothing").dispatch().await).await;
+    assert!(found.as_array().expect("notes").is_empty());
+}
+
+#[tokio::test]
+async fn update_note_saves_a_new_version() {
+    let app = TestApp::start().await;
+    let mut note = app.create_note("alice", "Groceries", "Milk").await;
+    note["content"] = json!("Milk and eggs");
+
+    let response = app.put("alice", format!("/api/notes/{}", id(&note))).json(&note).dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let updated = json(response).await;
+    assert_eq!(updated["content"], "Milk and eggs");
+    assert_eq!(updated["version"], 2);
+    assert!(time(&updated, "updated_at") >= time(&note, "updated_at"));
+}
+
+#[tokio::test]
+async fn update_note_rejects_a_mismatched_id() {
+    let app = TestApp::start().await;
+    let mut note = app.create_note("alice", "Groceries", "Milk").await;
+    let path = format!("/api/notes/{}", id(&note));
+    note["id"] = json!("another_note");
+
+    let response = app.put("alice", path).json(&note).dispatch().await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(error_code(response).await, "bad_request");
+}
+
+#[tokio::test]
+async fn update_note_not_found() {
+    let app = TestApp::start().await;
+
+    let response = app.put("alice", "/api/notes/missing").json(&note_body("Missing", "")).dispatch().await;
+
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn update_note_checks_if_match() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "Milk").await;
+    let path = format!("/api/notes/{}", id(&note));
+    let etag = app.get("alice", &path).dispatch().await.headers().get_one("ETag").expect("ETag").to_string();
+
+    let response = app.put("alice", &path).header(Header::new("If-Match", etag.clone())).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // The first save changed the version, so the same ETag is stale now
+    let response = app.put("alice", &path).header(Header::new("If-Match", etag)).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::PreconditionFailed);
+}
+
+#[tokio::test]
+async fn set_note_flags_leaves_updated_at_alone() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "").await;
+
+    let response = app.put("alice", format!("/api/notes/{}/flags", id(&note)))
+        .json(&json!({ "pinned": true, "favorite": true }))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let flagged = json(response).await;
+    assert_eq!(flagged["pinned"], true);
+    assert_eq!(flagged["favorite"], true);
+    assert_eq!(flagged["archived"], false);
+    assert_eq!(flagged["version"], 2);
+    assert_eq!(flagged["updated_at"], note["updated_at"]);
+
+    let response = app.put("alice", "/api/notes/missing/flags").json(&json!({ "pinned": true })).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn delete_note_removes_it_and_its_data() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let response = in_workspace(app.post("alice", "/api/notes"), &workspace).json(&note_body("Groceries", "- [ ] Milk")).dispatch().await;
+    let note = json(response).await;
+    let path = format!("/api/notes/{}", id(&note));
+    let response = in_workspace(app.post("alice", format!("{}/attachments", path)), &workspace)
+        .json(&json!({ "file_name": "list.txt", "content_type": "text/plain", "data": "SGVsbG8=" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    // Comments from other members go with the note too
+    for user in ["alice", "bob"] {
+        let response = in_workspace(app.post(user, format!("{}/comments", path)), &workspace)
+            .json(&json!({ "body": "Oat milk?" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+    let tasks = json(in_workspace(app.get("alice", "/api/tasks"), &workspace).dispatch().await).await;
+    assert_eq!(tasks.as_array().expect("tasks").len(), 1);
+
+    let response = in_workspace(app.delete("alice", &path), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    assert_eq!(in_workspace(app.get("alice", &path), &workspace).dispatch().await.status(), Status::NotFound);
+    assert_eq!(in_workspace(app.delete("alice", &path), &workspace).dispatch().await.status(), Status::NotFound);
+    for table in ["task", "attachment", "comment"] {
+        let rows: Vec<serde_json::Value> = app.state().db.query(format!("SELECT * FROM {}", table))
+            .await
+            .and_then(|mut response| response.take(0))
+            .expect("query table");
+        assert!(rows.is_empty(), "{} left behind", table);
+    }
+}
@@ -0,0 +1,274 @@
+// Checks the table permissions in init.surql directly against the test database,
+// seeded as root and then signed in as different users through the `user` scope.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::remote::ws::Client as Db;
+use surrealdb::opt::auth::{Jwt, Scope};
+use surrealdb::Surreal;
+
+use crate::{DB_DATABASE, DB_NAMESPACE};
+use super::TestDatabase;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+#[derive(Serialize)]
+struct Credentials<'a> {
+    username: &'a str,
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct SeedNote<'a> {
+    title: String,
+    content: &'a str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    created_by: &'a str,
+    is_shared: bool,
+    shared_with: Vec<&'a str>,
+    version: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct SeedMember<'a> {
+    workspace_id: &'a str,
+    user: &'a str,
+    role: &'a str,
+    added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Titled {
+    title: String,
+}
+
+// The server stops when its `TestDatabase` is dropped, so tests hold on to both
+async fn database() -> (TestDatabase, Surreal<Db>) {
+    let database = TestDatabase::start().await;
+    let db = database.connect().await;
+    (database, db)
+}
+
+fn note<'a>(created_by: &'a str, shared_with: Vec<&'a str>, workspace_id: Option<&'a str>) -> SeedNote<'a> {
+    let now = Utc::now();
+    SeedNote {
+        title: format!("{}'s note", created_by),
+        content: "",
+        created_at: now,
+        updated_at: now,
+        created_by,
+        is_shared: !shared_with.is_empty(),
+        shared_with,
+        version: 1,
+        workspace_id,
+    }
+}
+
+// Runs as root, before anyone signs in
+async fn seed_note(db: &Surreal<Db>, id: &str, note: SeedNote<'_>) {
+    let _: Option<Titled> = db.create(("note", id)).content(note).await.expect("seed note");
+}
+
+async fn seed_member(db: &Surreal<Db>, workspace_id: &str, user: &str, role: &str) {
+    let member = SeedMember { workspace_id, user, role, added_at: Utc::now() };
+    let _: Option<serde_json::Value> = db.create("workspace_member").content(member).await.expect("seed member");
+}
+
+async fn sign_up(db: &Surreal<Db>, username: &str) -> Jwt {
+    db.signup(Scope {
+        namespace: DB_NAMESPACE,
+        database: DB_DATABASE,
+        scope: "user",
+        params: Credentials { username, password: PASSWORD },
+    })
+    .await
+    .expect("sign up")
+}
+
+async fn visible_titles(db: &Surreal<Db>) -> Vec<String> {
+    let notes: Vec<Titled> = db.select("note").await.expect("select notes");
+    let mut titles: Vec<String> = notes.into_iter().map(|note| note.title).collect();
+    titles.sort();
+    titles
+}
+
+#[tokio::test]
+async fn users_only_see_their_own_and_shared_notes() {
+    let (_database, db) = database().await;
+    seed_note(&db, "alice", note("alice", vec![], None)).await;
+    seed_note(&db, "bob", note("bob", vec![], None)).await;
+    seed_note(&db, "carol", note("carol", vec!["alice"], None)).await;
+    let alice = sign_up(&db, "alice").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    assert_eq!(visible_titles(&db).await, vec!["alice's note", "carol's note"]);
+
+    let bobs: Option<Titled> = db.select(("note", "bob")).await.expect("select note");
+    assert!(bobs.is_none());
+}
+
+#[tokio::test]
+async fn users_cannot_change_or_delete_notes_of_others() {
+    let (_database, db) = database().await;
+    seed_note(&db, "bob", note("bob", vec![], None)).await;
+    let alice = sign_up(&db, "alice").await;
+    let bob = sign_up(&db, "bob").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    let updated: Result<Option<Titled>, surrealdb::Error> = db.update(("note", "bob"))
+        .merge(serde_json::json!({ "title": "taken over" }))
+        .await;
+    assert!(!matches!(updated, Ok(Some(_))));
+    let deleted: Result<Option<Titled>, surrealdb::Error> = db.delete(("note", "bob")).await;
+    assert!(!matches!(deleted, Ok(Some(_))));
+
+    db.authenticate(bob).await.expect("authenticate");
+    assert_eq!(visible_titles(&db).await, vec!["bob's note"]);
+}
+
+#[tokio::test]
+async fn users_cannot_create_notes_as_someone_else() {
+    let (_database, db) = database().await;
+    let alice = sign_up(&db, "alice").await;
+    let bob = sign_up(&db, "bob").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    let forged: Result<Option<Titled>, surrealdb::Error> = db.create(("note", "forged"))
+        .content(note("bob", vec![], None))
+        .await;
+    assert!(!matches!(forged, Ok(Some(_))));
+
+    db.authenticate(bob).await.expect("authenticate");
+    assert!(visible_titles(&db).await.is_empty());
+}
+
+#[tokio::test]
+async fn shared_users_cannot_reassign_a_note() {
+    let (_database, db) = database().await;
+    seed_note(&db, "bob", note("bob", vec!["alice"], None)).await;
+    let alice = sign_up(&db, "alice").await;
+    let bob = sign_up(&db, "bob").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    let _: Result<Option<Titled>, surrealdb::Error> = db.update(("note", "bob"))
+        .merge(serde_json::json!({ "created_by": "alice", "shared_with": [] }))
+        .await;
+
+    // The note still belongs to bob, so he keeps access to it
+    db.authenticate(bob).await.expect("authenticate");
+    assert_eq!(visible_titles(&db).await, vec!["bob's note"]);
+}
+
+#[tokio::test]
+async fn workspace_viewers_can_read_but_not_edit() {
+    let (_database, db) = database().await;
+    seed_note(&db, "team", note("bob", vec![], Some("team"))).await;
+    seed_member(&db, "team", "bob", "owner").await;
+    seed_member(&db, "team", "alice", "viewer").await;
+    let alice = sign_up(&db, "alice").await;
+    let carol = sign_up(&db, "carol").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    assert_eq!(visible_titles(&db).await, vec!["bob's note"]);
+    let updated: Result<Option<Titled>, surrealdb::Error> = db.update(("note", "team"))
+        .merge(serde_json::json!({ "title": "edited" }))
+        .await;
+    assert!(!matches!(updated, Ok(Some(_))));
+
+    db.authenticate(carol).await.expect("authenticate");
+    assert!(visible_titles(&db).await.is_empty());
+}
+
+#[derive(Serialize)]
+struct SeedComment<'a> {
+    note_id: &'a str,
+    author: &'a str,
+    body: &'a str,
+    resolved: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Body {
+    body: String,
+}
+
+async fn seed_comment(db: &Surreal<Db>, id: &str, note_id: &str, author: &str) {
+    let now = Utc::now();
+    let comment = SeedComment { note_id, author, body: "Looks good", resolved: false, created_at: now, updated_at: now };
+    let _: Option<Body> = db.create(("comment", id)).content(comment).await.expect("seed comment");
+}
+
+#[tokio::test]
+async fn comments_follow_their_note() {
+    let (_database, db) = database().await;
+    seed_note(&db, "shared", note("bob", vec!["alice"], None)).await;
+    seed_note(&db, "private", note("bob", vec![], None)).await;
+    seed_comment(&db, "on_shared", "shared", "bob").await;
+    seed_comment(&db, "on_private", "private", "bob").await;
+    let alice = sign_up(&db, "alice").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    let comments: Vec<Body> = db.select("comment").await.expect("select comments");
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].body, "Looks good");
+
+    // Only the author deletes a comment
+    let deleted: Result<Option<Body>, surrealdb::Error> = db.delete(("comment", "on_private")).await;
+    assert!(!matches!(deleted, Ok(Some(_))));
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SeedEvent {
+    actor: String,
+    action: String,
+    note_id: String,
+    at: DateTime<Utc>,
+    workspace_id: String,
+}
+
+fn event(workspace_id: &str) -> SeedEvent {
+    SeedEvent {
+        actor: "bob".to_string(),
+        action: "update".to_string(),
+        note_id: "note".to_string(),
+        at: Utc::now(),
+        workspace_id: workspace_id.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn users_read_only_their_workspaces_audit_events_and_never_write_them() {
+    let (_database, db) = database().await;
+    seed_member(&db, "team", "alice", "viewer").await;
+    let _: Option<SeedEvent> = db.create("audit_event").content(event("team")).await.expect("seed event");
+    let _: Option<SeedEvent> = db.create("audit_event").content(event("elsewhere")).await.expect("seed event");
+    let alice = sign_up(&db, "alice").await;
+
+    db.authenticate(alice).await.expect("authenticate");
+    let events: Vec<SeedEvent> = db.select("audit_event").await.expect("select events");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].workspace_id, "team");
+
+    let forged: Result<Option<SeedEvent>, surrealdb::Error> = db.create("audit_event").content(event("team")).await;
+    assert!(!matches!(forged, Ok(Some(_))));
+}
+
+#[tokio::test]
+async fn sign_in_rejects_a_wrong_password() {
+    let (_database, db) = database().await;
+    sign_up(&db, "alice").await;
+
+    let signed_in = db.signin(Scope {
+        namespace: DB_NAMESPACE,
+        database: DB_DATABASE,
+        scope: "user",
+        params: Credentials { username: "alice", password: "wrong" },
+    })
+    .await;
+    assert!(signed_in.is_err());
+}
@@ -0,0 +1,106 @@
+use rocket::http::{ContentType, Status};
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use crate::reminder::{ReminderEvent, ReminderKind};
+use crate::reminders::fire_due_reminders;
+use super::{id, in_workspace, json, next_event, note_body, TestApp};
+
+#[tokio::test]
+async fn upcoming_reminders_within_the_window() {
+    let app = TestApp::start().await;
+    let mut body = note_body("Dentist", "");
+    body["remind_at"] = json!(Utc::now() + Duration::hours(2));
+    body["due_at"] = json!(Utc::now() + Duration::hours(48));
+    app.post("alice", "/api/notes").json(&body).dispatch().await;
+
+    let upcoming = json(app.get("alice", "/api/reminders/upcoming").dispatch().await).await;
+    assert_eq!(upcoming.as_array().expect("reminders").len(), 1);
+    assert_eq!(upcoming[0]["kind"], "reminder");
+    assert_eq!(upcoming[0]["title"], "Dentist");
+
+    let upcoming = json(app.get("alice", "/api/reminders/upcoming?hours=72").dispatch().await).await;
+    let kinds: Vec<&str> = upcoming.as_array().expect("reminders").iter().map(|event| event["kind"].as_str().expect("kind")).collect();
+    assert_eq!(kinds, ["reminder", "due"]);
+
+    let upcoming = json(app.get("bob", "/api/reminders/upcoming?hours=72").dispatch().await).await;
+    assert!(upcoming.as_array().expect("reminders").is_empty());
+}
+
+#[tokio::test]
+async fn reminders_and_due_dates_fire_once() {
+    let app = TestApp::start().await;
+    let mut body = note_body("Dentist", "");
+    body["remind_at"] = json!(Utc::now() - Duration::minutes(5));
+    body["due_at"] = json!(Utc::now() - Duration::minutes(1));
+    app.post("alice", "/api/notes").json(&body).dispatch().await;
+    let mut events = app.state().reminders.subscribe();
+
+    assert_eq!(fire_due_reminders(app.state()).await.expect("fire reminders"), 2);
+    let kinds = [events.recv().await.expect("event").kind, events.recv().await.expect("event").kind];
+    assert_eq!(kinds, [ReminderKind::Reminder, ReminderKind::Due]);
+
+    assert_eq!(fire_due_reminders(app.state()).await.expect("fire reminders"), 0);
+}
+
+#[tokio::test]
+async fn saving_a_note_does_not_refire_its_reminder() {
+    let app = TestApp::start().await;
+    let mut body = note_body("Dentist", "");
+    body["remind_at"] = json!(Utc::now() - Duration::minutes(5));
+    let mut note = json(app.post("alice", "/api/notes").json(&body).dispatch().await).await;
+    assert_eq!(fire_due_reminders(app.state()).await.expect("fire reminders"), 1);
+
+    // The client's copy predates the delivery and still has no `reminder_sent_at`
+    note["content"] = json!("Bring the forms");
+    let response = app.put("alice", format!("/api/notes/{}", id(&note))).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(fire_due_reminders(app.state()).await.expect("fire reminders"), 0);
+
+    // Moving the reminder later schedules it again
+    note["remind_at"] = json!(Utc::now() - Duration::seconds(1));
+    let response = app.put("alice", format!("/api/notes/{}", id(&note))).json(&note).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(fire_due_reminders(app.state()).await.expect("fire reminders"), 1);
+}
+
+#[tokio::test]
+async fn upcoming_reminders_are_listed_for_workspace_members() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "viewer")]).await;
+    let mut body = note_body("Dentist", "");
+    body["remind_at"] = json!(Utc::now() + Duration::hours(2));
+    in_workspace(app.post("alice", "/api/notes"), &workspace).json(&body).dispatch().await;
+
+    let upcoming = json(in_workspace(app.get("bob", "/api/reminders/upcoming"), &workspace).dispatch().await).await;
+    assert_eq!(upcoming.as_array().expect("reminders").len(), 1);
+
+    let response = in_workspace(app.get("carol", "/api/reminders/upcoming"), &workspace).dispatch().await;
+    assert_ne!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn reminder_events_only_stream_the_workspace() {
+    let app = TestApp::start().await;
+    let workspaces = json(app.get("alice", "/api/workspaces").dispatch().await).await;
+    let workspace = workspaces[0]["workspace"]["id"].as_str().expect("workspace id").to_string();
+
+    let mut response = app.get("alice", format!("/api/reminders/events?workspace={}", workspace)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::EventStream));
+
+    for (note_id, workspace_id) in [("elsewhere", "personal_someone_else"), ("dentist", workspace.as_str())] {
+        let event = ReminderEvent {
+            note_id: note_id.into(),
+            title: "Dentist".to_string(),
+            kind: ReminderKind::Reminder,
+            at: Utc::now(),
+            workspace_id: Some(workspace_id.into()),
+        };
+        app.state().reminders.send(event).expect("a listener");
+    }
+
+    let event = next_event(&mut response).await;
+    assert!(event.contains("data:"));
+    assert!(event.contains("\"note_id\":\"dentist\""));
+}
@@ -0,0 +1,54 @@
+use rocket::http::{ContentType, Header, Status};
+use serde_json::json;
+
+use super::{id, json, note_body, TestApp};
+
+#[tokio::test]
+async fn note_html_renders_sanitized_markdown_with_wiki_links() {
+    let app = TestApp::start().await;
+    let target = app.create_note("alice", "Groceries", "").await;
+    let note = app.create_note("alice", "Plan", "**Buy** [[Groceries]] and [[Nowhere]]\n\n<script>alert(1)</script>").await;
+
+    let response = app.get("alice", format!("/api/notes/{}/html", id(&note))).dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    let html = response.into_string().await.expect("body");
+    assert!(html.contains("<strong>Buy</strong>"));
+    assert!(html.contains(&format!("data-note-id=\"{}\"", id(&target))));
+    assert!(html.contains("<span class=\"wiki-link missing\">Nowhere</span>"));
+    assert!(!html.contains("<script>"));
+}
+
+#[tokio::test]
+async fn note_html_answers_not_modified_for_a_current_copy() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Plan", "Text").await;
+    let path = format!("/api/notes/{}/html", id(&note));
+
+    let etag = app.get("alice", &path).dispatch().await.headers().get_one("ETag").expect("ETag").to_string();
+    let response = app.get("alice", &path).header(Header::new("If-None-Match", etag)).dispatch().await;
+
+    assert_eq!(response.status(), Status::NotModified);
+}
+
+#[tokio::test]
+async fn note_html_refuses_encrypted_and_missing_notes() {
+    let app = TestApp::start().await;
+    let mut body = note_body("c2VhbGVk", "c2VhbGVk");
+    body["encryption"] = json!({
+        "algorithm": "AES-256-GCM",
+        "kdf": "PBKDF2-SHA256",
+        "iterations": 100000,
+        "salt": "c2FsdA==",
+        "title_nonce": "bm9uY2U=",
+        "content_nonce": "bm9uY2U=",
+    });
+    let note = json(app.post("alice", "/api/notes").json(&body).dispatch().await).await;
+
+    let response = app.get("alice", format!("/api/notes/{}/html", id(&note))).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.get("alice", "/api/notes/missing/html").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
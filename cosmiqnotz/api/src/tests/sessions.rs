@@ -0,0 +1,61 @@
+use rocket::http::{Header, Status};
+use serde_json::{json, Value};
+
+use super::{json, note_body, TestApp};
+
+async fn sign_up(app: &TestApp, username: &str, password: &str) -> Value {
+    let response = app.client.post("/api/auth/signup")
+        .json(&json!({ "username": username, "password": password }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    json(response).await
+}
+
+#[tokio::test]
+async fn signed_in_users_act_with_their_token() {
+    let app = TestApp::start().await;
+    let session = sign_up(&app, "alice", "correct horse").await;
+    assert_eq!(session["user"], "alice");
+
+    let response = app.client.post("/api/auth/signin")
+        .json(&json!({ "username": "alice", "password": "correct horse" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let token = json(response).await["token"].as_str().expect("token").to_string();
+    let authorization = Header::new("Authorization", format!("Bearer {}", token));
+
+    let response = app.client.post("/api/notes").header(authorization.clone()).json(&note_body("Diary", "")).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["created_by"], "alice");
+
+    let notes = json(app.client.get("/api/notes").header(authorization).dispatch().await).await;
+    assert_eq!(notes[0]["title"], "Diary");
+}
+
+#[tokio::test]
+async fn sign_up_rejects_blank_and_taken_usernames() {
+    let app = TestApp::start().await;
+    sign_up(&app, "alice", "correct horse").await;
+
+    let response = app.client.post("/api/auth/signup").json(&json!({ "username": " ", "password": "secret" })).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let response = app.client.post("/api/auth/signup").json(&json!({ "username": "alice", "password": "other" })).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[tokio::test]
+async fn sign_in_rejects_wrong_passwords_and_forged_tokens() {
+    let app = TestApp::start().await;
+    sign_up(&app, "alice", "correct horse").await;
+
+    let response = app.client.post("/api/auth/signin")
+        .json(&json!({ "username": "alice", "password": "battery staple" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = app.client.get("/api/notes").header(Header::new("Authorization", "Bearer not.a.token")).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
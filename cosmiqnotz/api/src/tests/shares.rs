@@ -0,0 +1,88 @@
+use rocket::http::{ContentType, Status};
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::{id, json, TestApp};
+
+#[tokio::test]
+async fn share_links_show_the_note_to_anyone() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Recipe", "**Pancakes**").await;
+
+    let response = app.post("alice", format!("/api/notes/{}/shares", id(&note))).json(&json!({})).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let created = json(response).await;
+    let token = created["token"].as_str().expect("token");
+    assert_eq!(created["path"], format!("/s/{}", token));
+    assert!(created["link"].get("token_hash").is_none());
+
+    let response = app.client.get(format!("/s/{}", token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    assert_eq!(response.headers().get_one("Cache-Control"), Some("no-store"));
+    assert!(response.headers().get_one("Content-Security-Policy").is_some());
+    assert!(response.into_string().await.expect("body").contains("<strong>Pancakes</strong>"));
+
+    let links = json(app.get("alice", format!("/api/notes/{}/shares", id(&note))).dispatch().await).await;
+    assert_eq!(links.as_array().expect("links").len(), 1);
+    assert_eq!(links[0]["views"], 1);
+}
+
+#[tokio::test]
+async fn password_protected_links_ask_for_the_password() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Recipe", "Pancakes").await;
+    let created = json(app.post("alice", format!("/api/notes/{}/shares", id(&note)))
+        .json(&json!({ "password": "syrup" }))
+        .dispatch()
+        .await).await;
+    let path = created["path"].as_str().expect("path").to_string();
+    assert_eq!(created["link"]["password_protected"], true);
+
+    let response = app.client.get(path.as_str()).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(!response.into_string().await.expect("body").contains("Pancakes"));
+
+    let response = app.client.post(path.as_str()).header(ContentType::Form).body("password=butter").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = app.client.post(path.as_str()).header(ContentType::Form).body("password=syrup").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().await.expect("body").contains("Pancakes"));
+}
+
+#[tokio::test]
+async fn revoked_links_stop_working() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Recipe", "Pancakes").await;
+    let created = json(app.post("alice", format!("/api/notes/{}/shares", id(&note))).json(&json!({})).dispatch().await).await;
+    let path = created["path"].as_str().expect("path").to_string();
+
+    let response = app.delete("alice", format!("/api/shares/{}", id(&created["link"]))).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(json(response).await.get("revoked_at").is_some());
+
+    let response = app.client.get(path.as_str()).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.client.get("/s/unknown").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.delete("alice", "/api/shares/missing").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn create_share_link_rejects_past_expiry_and_missing_notes() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Recipe", "Pancakes").await;
+
+    let response = app.post("alice", format!("/api/notes/{}/shares", id(&note)))
+        .json(&json!({ "expires_at": Utc::now() - Duration::hours(1) }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.post("alice", "/api/notes/missing/shares").json(&json!({})).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.get("alice", "/api/notes/missing/shares").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
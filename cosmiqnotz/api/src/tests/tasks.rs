@@ -0,0 +1,93 @@
+use rocket::http::Status;
+use serde_json::json;
+
+use super::{id, in_workspace, json, note_body, TestApp};
+
+#[tokio::test]
+async fn get_tasks_filters_by_status_and_note() {
+    let app = TestApp::start().await;
+    let groceries = app.create_note("alice", "Groceries", "- [ ] Milk\n- [x] Eggs").await;
+    app.create_note("alice", "Chores", "- [ ] Bins").await;
+
+    let all = json(app.get("alice", "/api/tasks").dispatch().await).await;
+    assert_eq!(all.as_array().expect("tasks").len(), 3);
+
+    let open = json(app.get("alice", "/api/tasks?status=open").dispatch().await).await;
+    assert_eq!(open.as_array().expect("tasks").len(), 2);
+
+    let done = json(app.get("alice", "/api/tasks?status=done").dispatch().await).await;
+    assert_eq!(done.as_array().expect("tasks").len(), 1);
+    assert_eq!(done[0]["text"], "Eggs");
+
+    let in_note = json(app.get("alice", format!("/api/tasks?note={}", id(&groceries))).dispatch().await).await;
+    assert_eq!(in_note.as_array().expect("tasks").len(), 2);
+    assert!(in_note.as_array().expect("tasks").iter().all(|task| task["note_title"] == "Groceries"));
+
+    let response = app.get("alice", "/api/tasks?status=someday").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[tokio::test]
+async fn update_task_checks_the_item_in_the_note() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "Shopping\n- [ ] Milk").await;
+    let tasks = json(app.get("alice", "/api/tasks").dispatch().await).await;
+
+    let response = app.put("alice", format!("/api/tasks/{}", id(&tasks[0]))).json(&json!({ "done": true })).dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["done"], true);
+    let note = json(app.get("alice", format!("/api/notes/{}", id(&note))).dispatch().await).await;
+    assert_eq!(note["content"], "Shopping\n- [x] Milk");
+    assert_eq!(note["version"], 2);
+}
+
+#[tokio::test]
+async fn update_task_conflicts_when_the_line_changed() {
+    let app = TestApp::start().await;
+    let note = app.create_note("alice", "Groceries", "- [ ] Milk").await;
+    let tasks = json(app.get("alice", "/api/tasks").dispatch().await).await;
+    let task_path = format!("/api/tasks/{}", id(&tasks[0]));
+
+    // Saved straight to the database, so the stored task still names the old line
+    let _: Option<serde_json::Value> = app.state().db.update(("note", id(&note).as_str()))
+        .merge(json!({ "content": "- [ ] Oat milk" }))
+        .await
+        .expect("edit note");
+
+    let response = app.put("alice", task_path).json(&json!({ "done": true })).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = app.put("alice", "/api/tasks/missing").json(&json!({ "done": true })).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn checklists_in_code_blocks_are_not_tasks() {
+    let app = TestApp::start().await;
+    app.create_note("alice", "Docs", "- [ ] Write\n```md\n- [ ] Example\n```\n~~~\n- [x] Another\n~~~\n- [ ] Publish").await;
+
+    let tasks = json(app.get("alice", "/api/tasks").dispatch().await).await;
+    let texts: Vec<&str> = tasks.as_array().expect("tasks").iter().map(|task| task["text"].as_str().expect("text")).collect();
+    assert_eq!(texts.len(), 2);
+    assert!(texts.contains(&"Write") && texts.contains(&"Publish"));
+}
+
+#[tokio::test]
+async fn update_task_respects_the_edit_lock() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace)
+        .json(&note_body("Groceries", "- [ ] Milk"))
+        .dispatch()
+        .await).await;
+    in_workspace(app.post("alice", format!("/api/notes/{}/lock", id(&note))), &workspace).dispatch().await;
+    let tasks = json(in_workspace(app.get("bob", "/api/tasks"), &workspace).dispatch().await).await;
+    let task_path = format!("/api/tasks/{}", id(&tasks[0]));
+
+    let response = in_workspace(app.put("bob", &task_path), &workspace).json(&json!({ "done": true })).dispatch().await;
+    assert_eq!(response.status(), Status::Locked);
+
+    let response = in_workspace(app.put("alice", &task_path), &workspace).json(&json!({ "done": true })).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
@@ -0,0 +1,203 @@
+use rocket::http::Status;
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::task::JoinHandle;
+use rocket::tokio::time::sleep;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::webhooks::signature;
+use super::{id, json, TestApp};
+
+// A request as the receiving end saw it
+struct Received {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> &str {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .expect("header")
+    }
+}
+
+// Accepts one request on a free port, answers 200 and hands the request back
+async fn receiver() -> (String, JoinHandle<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind receiver");
+    let url = format!("http://{}/hook", listener.local_addr().expect("receiver address"));
+
+    let handle = rocket::tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept delivery");
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stream.read(&mut buffer).await.expect("read delivery");
+            assert!(read > 0, "connection closed mid-request");
+            request.extend_from_slice(&buffer[..read]);
+            let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") else {
+                continue;
+            };
+
+            let head = String::from_utf8_lossy(&request[..end]).to_string();
+            let headers: Vec<(String, String)> = head.lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .collect();
+            let length: usize = headers.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+                .and_then(|(_, value)| value.parse().ok())
+                .unwrap_or(0);
+            if request.len() < end + 4 + length {
+                continue;
+            }
+
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.expect("answer delivery");
+            let body = request[end + 4..].to_vec();
+            return Received { headers, body };
+        }
+    });
+    (url, handle)
+}
+
+// Webhooks are managed by workspace owners whose session has the admin scope
+async fn start() -> TestApp {
+    TestApp::configured(|figment| figment.merge(("admin_users", ["alice", "bob"]))).await
+}
+
+async fn create_webhook(app: &TestApp, url: &str, events: &[&str]) -> Value {
+    let response = app.post("alice", "/api/webhooks")
+        .json(&json!({ "url": url, "events": events, "secret": "whsec_test" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    json(response).await
+}
+
+#[tokio::test]
+async fn test_deliveries_are_signed() {
+    let app = start().await;
+    let (url, received) = receiver().await;
+    let webhook = create_webhook(&app, &url, &["note.created"]).await;
+    assert_eq!(webhook["secret"], "whsec_test");
+
+    let response = app.post("alice", format!("/api/webhooks/{}/test", id(&webhook))).dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let delivery = json(response).await;
+    assert_eq!(delivery["event"], "ping");
+    assert_eq!(delivery["status"], "succeeded");
+    assert_eq!(delivery["response_status"], 200);
+
+    let received = received.await.expect("delivery");
+    assert_eq!(received.header("X-Cosmiq-Event"), "ping");
+    let timestamp: i64 = received.header("X-Cosmiq-Timestamp").parse().expect("timestamp");
+    assert_eq!(received.header("X-Cosmiq-Signature"), signature("whsec_test", timestamp, &received.body));
+}
+
+#[tokio::test]
+async fn note_changes_are_delivered() {
+    let app = start().await;
+    let (url, received) = receiver().await;
+    let webhook = create_webhook(&app, &url, &["note.created"]).await;
+
+    let note = app.create_note("alice", "Groceries", "").await;
+
+    let received = received.await.expect("delivery");
+    assert_eq!(received.header("X-Cosmiq-Event"), "note.created");
+    let payload: Value = serde_json::from_slice(&received.body).expect("payload");
+    assert_eq!(payload["data"]["note_id"], note["id"]);
+    assert_eq!(payload["data"]["actor"], "alice");
+
+    // The outcome is saved right after the receiver answers
+    let deliveries_path = format!("/api/webhooks/{}/deliveries", id(&webhook));
+    for _ in 0..50 {
+        let deliveries = json(app.get("alice", deliveries_path.as_str()).dispatch().await).await;
+        if deliveries[0]["status"] == "succeeded" {
+            return;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("delivery was never marked as succeeded");
+}
+
+#[tokio::test]
+async fn webhooks_are_listed_without_their_secret() {
+    let app = start().await;
+    let webhook = create_webhook(&app, "https://example.com/hook", &["*"]).await;
+
+    let webhooks = json(app.get("alice", "/api/webhooks").dispatch().await).await;
+    assert_eq!(webhooks[0]["id"], webhook["id"]);
+    assert!(webhooks[0].get("secret").is_none());
+
+    let response = app.delete("alice", format!("/api/webhooks/{}", id(&webhook))).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = app.delete("alice", format!("/api/webhooks/{}", id(&webhook))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.get("alice", "/api/webhooks/missing/deliveries").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.post("alice", "/api/webhooks/missing/test").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn create_webhook_checks_the_url_and_events() {
+    let app = start().await;
+    let invalid = [
+        json!({ "url": "ftp://example.com", "events": ["note.created"] }),
+        json!({ "url": "https://example.com/hook", "events": [] }),
+        json!({ "url": "https://example.com/hook", "events": ["note.renamed"] }),
+    ];
+
+    for body in invalid {
+        let response = app.post("alice", "/api/webhooks").json(&body).dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", body);
+    }
+}
+
+#[tokio::test]
+async fn webhooks_belong_to_their_workspace() {
+    let app = start().await;
+    let webhook = create_webhook(&app, "https://example.com/hook", &["*"]).await;
+
+    let webhooks = json(app.get("bob", "/api/webhooks").dispatch().await).await;
+    assert!(webhooks.as_array().expect("webhooks").is_empty());
+    let response = app.post("bob", format!("/api/webhooks/{}/test", id(&webhook))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = app.delete("bob", format!("/api/webhooks/{}", id(&webhook))).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn webhooks_cannot_target_internal_addresses_by_default() {
+    let app = start().await;
+
+    for url in ["http://10.0.0.5/hook", "http://192.168.1.20/hook", "http://169.254.169.254/latest", "http://[fe80::1]/hook"] {
+        let response = app.post("alice", "/api/webhooks")
+            .json(&json!({ "url": url, "events": ["*"] }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", url);
+    }
+    // Listeners on the same machine are fine
+    create_webhook(&app, "http://localhost:9000/hook", &["*"]).await;
+}
+
+#[tokio::test]
+async fn webhooks_to_this_machine_can_be_refused() {
+    let app = TestApp::configured(|figment| figment
+        .merge(("admin_users", ["alice"]))
+        .merge(("webhook_allow_loopback", false)))
+        .await;
+
+    for url in ["http://127.0.0.1:8000/hook", "http://localhost/hook", "http://[::1]/hook", "http://[::ffff:127.0.0.1]/hook"] {
+        let response = app.post("alice", "/api/webhooks")
+            .json(&json!({ "url": url, "events": ["*"] }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", url);
+    }
+}
@@ -0,0 +1,100 @@
+use rocket::http::Status;
+use serde_json::json;
+
+use super::{id, in_workspace, json, note_body, TestApp};
+
+#[tokio::test]
+async fn list_workspaces_puts_the_personal_one_first() {
+    let app = TestApp::start().await;
+    app.create_workspace("alice", &[]).await;
+
+    let workspaces = json(app.get("alice", "/api/workspaces").dispatch().await).await;
+
+    assert_eq!(workspaces.as_array().expect("workspaces").len(), 2);
+    assert_eq!(workspaces[0]["workspace"]["personal"], true);
+    assert_eq!(workspaces[0]["role"], "owner");
+    assert_eq!(workspaces[1]["workspace"]["name"], "Team");
+}
+
+#[tokio::test]
+async fn create_workspace_needs_a_name_and_a_user() {
+    let app = TestApp::start().await;
+
+    let response = app.post("alice", "/api/workspaces").json(&json!({ "name": "  " })).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = app.client.post("/api/workspaces").json(&json!({ "name": "Team" })).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[tokio::test]
+async fn members_see_the_workspace_notes() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let note = json(in_workspace(app.post("alice", "/api/notes"), &workspace).json(&note_body("Roadmap", "")).dispatch().await).await;
+
+    let notes = json(in_workspace(app.get("bob", "/api/notes"), &workspace).dispatch().await).await;
+    assert_eq!(notes[0]["id"], note["id"]);
+    let notes = json(app.get("bob", format!("/api/notes?workspace={}", workspace)).dispatch().await).await;
+    assert_eq!(notes[0]["id"], note["id"]);
+
+    // Workspace notes stay out of personal workspaces
+    let notes = json(app.get("alice", "/api/notes").dispatch().await).await;
+    assert!(notes.as_array().expect("notes").is_empty());
+
+    let response = in_workspace(app.get("carol", "/api/notes"), &workspace).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn owners_manage_members() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let members_path = format!("/api/workspaces/{}/members", workspace);
+
+    let members = json(app.get("bob", &members_path).dispatch().await).await;
+    let users: Vec<&str> = members.as_array().expect("members").iter().map(|member| member["user"].as_str().expect("user")).collect();
+    assert_eq!(users, ["alice", "bob"]);
+    let response = app.get("carol", &members_path).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = app.put("bob", format!("{}/carol", members_path)).json(&json!({ "role": "viewer" })).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    let response = app.put("alice", format!("{}/bob", members_path)).json(&json!({ "role": "viewer" })).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response).await["role"], "viewer");
+
+    let response = app.delete("alice", format!("{}/bob", members_path)).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+    let response = app.delete("alice", format!("{}/bob", members_path)).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[tokio::test]
+async fn workspaces_keep_an_owner() {
+    let app = TestApp::start().await;
+    let workspace = app.create_workspace("alice", &[("bob", "editor")]).await;
+    let members_path = format!("/api/workspaces/{}/members", workspace);
+
+    let response = app.put("alice", format!("{}/alice", members_path)).json(&json!({ "role": "editor" })).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let response = app.delete("alice", format!("{}/alice", members_path)).dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    // Members may leave, but not remove others
+    let response = app.delete("bob", format!("{}/alice", members_path)).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    let response = app.delete("bob", format!("{}/bob", members_path)).dispatch().await;
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+#[tokio::test]
+async fn personal_workspaces_are_not_shared() {
+    let app = TestApp::start().await;
+    let workspaces = json(app.get("alice", "/api/workspaces").dispatch().await).await;
+    let personal = id(&workspaces[0]["workspace"]);
+
+    let response = app.put("alice", format!("/api/workspaces/{}/members/bob", personal)).json(&json!({ "role": "viewer" })).dispatch().await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
@@ -0,0 +1,564 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::net::lookup_host;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::time::{sleep, Duration};
+use rocket::State;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanAdmin};
+use crate::audit::{AuditAction, AuditEvent};
+use crate::limits::RateLimit;
+use crate::workspace::WorkspaceRole;
+use crate::workspaces::InWorkspace;
+use crate::AppState;
+
+// Headers sent with every delivery
+pub const EVENT_HEADER: &str = "X-Cosmiq-Event";
+pub const DELIVERY_HEADER: &str = "X-Cosmiq-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-Cosmiq-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Cosmiq-Signature";
+
+// Events a webhook can subscribe to; "*" subscribes to all of them
+const EVENTS: [&str; 5] = ["note.created", "note.updated", "note.deleted", "note.shared", "note.imported"];
+
+// Sent by the test endpoint, whatever the webhook subscribes to
+const PING_EVENT: &str = "ping";
+
+// Upper bound on deliveries returned by the log endpoint
+const MAX_DELIVERIES: usize = 200;
+
+// Read from Rocket's configuration, e.g. `ROCKET_WEBHOOK_MAX_ATTEMPTS`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    // Attempts per delivery, including the first
+    pub webhook_max_attempts: u32,
+    // Wait before the first retry; each later retry waits twice as long as the one before
+    pub webhook_retry_base_seconds: u64,
+    // How long to wait for the receiving end to answer
+    pub webhook_timeout_seconds: u64,
+    // Allow deliveries to this machine, e.g. a script running next to the desktop app
+    pub webhook_allow_loopback: bool,
+    // Allow deliveries to private and link-local addresses on the local network too
+    pub webhook_allow_private_addresses: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            webhook_max_attempts: 5,
+            webhook_retry_base_seconds: 10,
+            webhook_timeout_seconds: 10,
+            webhook_allow_loopback: true,
+            webhook_allow_private_addresses: false,
+        }
+    }
+}
+
+pub struct Webhooks {
+    config: WebhookConfig,
+}
+
+impl Webhooks {
+    // A client for one attempt that connects `host` to the addresses already checked,
+    // rather than looking the name up again when it sends
+    fn client(&self, host: &str, addresses: &[SocketAddr]) -> reqwest::Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .user_agent(concat!("cosmiqnotz-webhooks/", env!("CARGO_PKG_VERSION")))
+            // A redirect could lead past the destination check
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(self.config.webhook_timeout_seconds))
+            .resolve_to_addrs(host, addresses)
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Webhook {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // Only changes to notes in this workspace are delivered
+    pub workspace_id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    // Only returned when the webhook is created
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub secret: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    fn subscribes_to(&self, event: &str) -> bool {
+        event == PING_EVENT || self.events.iter().any(|subscribed| subscribed == "*" || subscribed == event)
+    }
+
+    fn redacted(self) -> Self {
+        Self { secret: String::new(), ..self }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewWebhook {
+    pub url: String,
+    pub events: Vec<String>,
+    // Generated when left out
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+fn event_name(action: AuditAction) -> &'static str {
+    match action {
+        AuditAction::Create => "note.created",
+        AuditAction::Update => "note.updated",
+        AuditAction::Delete => "note.deleted",
+        AuditAction::Share => "note.shared",
+        AuditAction::Import => "note.imported",
+    }
+}
+
+fn new_secret() -> String {
+    format!("whsec_{}", uuid::Uuid::new_v4().simple())
+}
+
+// `sha256=<hex>` of the HMAC-SHA256 of `<timestamp>.<body>` keyed with the webhook's secret
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+fn valid_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+// Addresses that don't lead out to the internet: loopback, private, link-local,
+// shared (CGNAT) and unspecified ones
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || (first == 100 && (64..128).contains(&second))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80
+            },
+        },
+    }
+}
+
+fn is_loopback(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback(),
+        IpAddr::V6(ip) => ip.is_loopback() || ip.to_ipv4_mapped().is_some_and(|ip| ip.is_loopback()),
+    }
+}
+
+fn allowed_address(ip: IpAddr, config: &WebhookConfig) -> bool {
+    if is_loopback(ip) {
+        config.webhook_allow_loopback || config.webhook_allow_private_addresses
+    } else {
+        config.webhook_allow_private_addresses || !is_private(ip)
+    }
+}
+
+// Where deliveries to `url` go: its host and the addresses it resolves to, as long as
+// every one of them is allowed. The check runs again before every attempt, and the
+// attempt connects to exactly these addresses.
+async fn destination(url: &str, config: &WebhookConfig) -> Option<(String, Vec<SocketAddr>)> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addresses: Vec<SocketAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => lookup_host((host, port)).await.ok()?.collect(),
+    };
+    if addresses.is_empty() || !addresses.iter().all(|address| allowed_address(address.ip(), config)) {
+        return None;
+    }
+    Some((host.to_string(), addresses))
+}
+
+async fn save(state: &AppState, delivery: &WebhookDelivery) {
+    let Some(id) = delivery.id.as_deref() else {
+        return;
+    };
+    let saved: Result<Option<WebhookDelivery>, surrealdb::Error> = state.db.update(("webhook_delivery", id))
+        .content(delivery)
+        .await;
+    if let Err(e) = saved {
+        error!("Failed to save webhook delivery {}: {}", id, e);
+    }
+}
+
+// Make one attempt and record its outcome on the delivery
+async fn attempt(state: &AppState, webhooks: &Webhooks, webhook: &Webhook, delivery: &mut WebhookDelivery) {
+    let body = serde_json::to_vec(&delivery.payload).unwrap_or_default();
+    let timestamp = Utc::now().timestamp();
+
+    let client = match destination(&webhook.url, &webhooks.config).await {
+        Some((host, addresses)) => webhooks.client(&host, &addresses).map_err(|e| e.to_string()),
+        None => Err("Destination address is not allowed".to_string()),
+    };
+    let response = match client {
+        Ok(client) => client.post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, delivery.event.as_str())
+            .header(DELIVERY_HEADER, delivery.id.as_deref().unwrap_or_default())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signature(&webhook.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+
+    delivery.attempts += 1;
+    delivery.next_attempt_at = None;
+    match response {
+        Ok(response) if response.status().is_success() => {
+            delivery.status = DeliveryStatus::Succeeded;
+            delivery.response_status = Some(response.status().as_u16());
+            delivery.error = None;
+            delivery.delivered_at = Some(Utc::now());
+        },
+        outcome => {
+            match outcome {
+                Ok(response) => {
+                    delivery.response_status = Some(response.status().as_u16());
+                    delivery.error = Some(format!("HTTP {}", response.status()));
+                },
+                Err(e) => {
+                    delivery.response_status = None;
+                    delivery.error = Some(e);
+                },
+            }
+
+            if delivery.attempts >= webhooks.config.webhook_max_attempts {
+                delivery.status = DeliveryStatus::Failed;
+            } else {
+                let backoff = webhooks.config.webhook_retry_base_seconds << (delivery.attempts - 1).min(16);
+                delivery.next_attempt_at = Some(Utc::now() + chrono::Duration::seconds(backoff as i64));
+            }
+        },
+    }
+
+    save(state, delivery).await;
+}
+
+// Keep attempting until the delivery succeeds or runs out of attempts
+async fn deliver(state: Arc<AppState>, webhooks: Arc<Webhooks>, webhook: Webhook, mut delivery: WebhookDelivery) {
+    while delivery.status == DeliveryStatus::Pending {
+        if let Some(next) = delivery.next_attempt_at {
+            if let Ok(wait) = (next - Utc::now()).to_std() {
+                sleep(wait).await;
+            }
+        }
+        attempt(&state, &webhooks, &webhook, &mut delivery).await;
+    }
+
+    if delivery.status == DeliveryStatus::Failed {
+        tracing::warn!(
+            webhook = %webhook.id.as_deref().unwrap_or_default(),
+            event = %delivery.event,
+            attempts = delivery.attempts,
+            "Webhook delivery failed"
+        );
+    }
+}
+
+async fn create_delivery(state: &AppState, webhook: &Webhook, event: &str, data: serde_json::Value) -> Result<WebhookDelivery, surrealdb::Error> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let now = Utc::now();
+    let delivery = WebhookDelivery {
+        id: Some(id.clone()),
+        webhook_id: webhook.id.clone().unwrap_or_default(),
+        event: event.to_string(),
+        payload: serde_json::json!({
+            "id": id,
+            "event": event,
+            "created_at": now,
+            "data": data,
+        }),
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        response_status: None,
+        error: None,
+        created_at: now,
+        next_attempt_at: None,
+        delivered_at: None,
+    };
+
+    let created: Option<WebhookDelivery> = state.db.create(("webhook_delivery", id.as_str()))
+        .content(&delivery)
+        .await?;
+    Ok(created.unwrap_or(delivery))
+}
+
+// Queue a delivery of a note change to every active webhook of the note's workspace
+// subscribed to it
+async fn dispatch(state: &Arc<AppState>, webhooks: &Arc<Webhooks>, event: &AuditEvent) -> Result<(), surrealdb::Error> {
+    let Some(workspace_id) = event.workspace_id.as_deref() else {
+        return Ok(());
+    };
+    let name = event_name(event.action);
+    let subscribed: Vec<Webhook> = state.db
+        .query("SELECT * FROM webhook WHERE active = true AND workspace_id = $workspace AND (events CONTAINS $event OR events CONTAINS '*')")
+        .bind(("workspace", workspace_id))
+        .bind(("event", name))
+        .await?
+        .take(0)?;
+
+    for webhook in subscribed.into_iter().filter(|webhook| webhook.subscribes_to(name)) {
+        let data = serde_json::json!({
+            "note_id": event.note_id,
+            "actor": event.actor,
+            "before_version": event.before_version,
+            "after_version": event.after_version,
+            "at": event.at,
+        });
+        let delivery = create_delivery(state, &webhook, name, data).await?;
+        rocket::tokio::spawn(deliver(state.clone(), webhooks.clone(), webhook, delivery));
+    }
+    Ok(())
+}
+
+// Pick up deliveries that were still being retried when the server last stopped.
+// Those of webhooks that have since been deactivated are given up on.
+async fn resume_pending(state: &Arc<AppState>, webhooks: &Arc<Webhooks>) -> Result<usize, surrealdb::Error> {
+    let pending: Vec<WebhookDelivery> = state.db
+        .query("SELECT * FROM webhook_delivery WHERE status = 'pending'")
+        .await?
+        .take(0)?;
+
+    let mut resumed = 0;
+    for mut delivery in pending {
+        let webhook: Option<Webhook> = state.db.select(("webhook", delivery.webhook_id.as_str())).await?;
+        match webhook {
+            Some(webhook) if webhook.active => {
+                rocket::tokio::spawn(deliver(state.clone(), webhooks.clone(), webhook, delivery));
+                resumed += 1;
+            },
+            Some(_) => {
+                delivery.status = DeliveryStatus::Failed;
+                delivery.next_attempt_at = None;
+                delivery.error = Some("Webhook is inactive".to_string());
+                save(state, &delivery).await;
+            },
+            None => {},
+        }
+    }
+    Ok(resumed)
+}
+
+// Deliveries run in the background, fed by the audit log's change events
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Webhooks", |rocket| async {
+        let config: WebhookConfig = rocket.figment().extract().unwrap_or_default();
+        let rocket = rocket.manage(Arc::new(Webhooks { config }));
+
+        rocket.attach(AdHoc::on_liftoff("Webhook Dispatcher", |rocket| Box::pin(async move {
+            let (Some(state), Some(webhooks)) = (rocket.state::<Arc<AppState>>().cloned(), rocket.state::<Arc<Webhooks>>().cloned()) else {
+                error!("Webhook dispatcher could not find application state");
+                return;
+            };
+            let mut changes = state.changes.subscribe();
+            let mut shutdown = rocket.shutdown();
+
+            rocket::tokio::spawn(async move {
+                if let Err(e) = resume_pending(&state, &webhooks).await {
+                    error!("Failed to resume webhook deliveries: {}", e);
+                }
+
+                loop {
+                    select! {
+                        change = changes.recv() => match change {
+                            Ok(event) => {
+                                if let Err(e) = dispatch(&state, &webhooks, &event).await {
+                                    error!("Failed to queue webhook deliveries: {}", e);
+                                }
+                            },
+                            Err(RecvError::Lagged(skipped)) => {
+                                error!("Webhook dispatcher fell behind and skipped {} change(s)", skipped);
+                            },
+                            Err(RecvError::Closed) => break,
+                        },
+                        _ = &mut shutdown => break,
+                    }
+                }
+            });
+        })))
+    })
+}
+
+// A webhook of the workspace; those of other workspaces are reported as missing
+async fn find_webhook(state: &AppState, workspace: &InWorkspace, id: &str) -> Result<Webhook, Status> {
+    let webhook: Option<Webhook> = state.db.select(("webhook", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    webhook.filter(|webhook| webhook.workspace_id == workspace.id).ok_or(Status::NotFound)
+}
+
+// Webhooks are managed by the owners of their workspace
+#[get("/webhooks")]
+pub async fn list_webhooks(workspace: InWorkspace, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>) -> Result<Json<Vec<Webhook>>, Status> {
+    workspace.require(WorkspaceRole::Owner)?;
+    let webhooks: Vec<Webhook> = state.db.query("SELECT * FROM webhook WHERE workspace_id = $workspace ORDER BY created_at")
+        .bind(("workspace", workspace.id.as_str()))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(webhooks.into_iter().map(Webhook::redacted).collect()))
+}
+
+// The response is the only time the secret is shown
+#[post("/webhooks", data = "<webhook>")]
+pub async fn create_webhook(webhook: Json<NewWebhook>, workspace: InWorkspace, _rate: RateLimit, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>, webhooks: &State<Arc<Webhooks>>) -> Result<Json<Webhook>, Status> {
+    workspace.require(WorkspaceRole::Owner)?;
+    let webhook = webhook.into_inner();
+    let url = webhook.url.trim().to_string();
+    if !valid_url(&url) || destination(&url, &webhooks.config).await.is_none() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let mut events: Vec<String> = webhook.events.iter().map(|event| event.trim().to_string()).collect();
+    events.sort();
+    events.dedup();
+    if events.is_empty() || !events.iter().all(|event| event == "*" || EVENTS.contains(&event.as_str())) {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let secret = webhook.secret
+        .map(|secret| secret.trim().to_string())
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(new_secret);
+
+    let webhook = Webhook {
+        id: None,
+        workspace_id: workspace.id.to_string(),
+        url,
+        events,
+        secret,
+        active: true,
+        created_at: Utc::now(),
+    };
+    let created: Vec<Webhook> = state.db.create("webhook")
+        .content(&webhook)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    created.into_iter().next().map(Json).ok_or(Status::InternalServerError)
+}
+
+#[delete("/webhooks/<id>")]
+pub async fn delete_webhook(id: &str, workspace: InWorkspace, _rate: RateLimit, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>) -> Status {
+    if let Err(status) = workspace.require(WorkspaceRole::Owner) {
+        return status;
+    }
+    if let Err(status) = find_webhook(state, &workspace, id).await {
+        return status;
+    }
+
+    let deleted: Result<Option<Webhook>, surrealdb::Error> = state.db.delete(("webhook", id)).await;
+    match deleted {
+        Ok(Some(_)) => {
+            let cleared = state.db.query("DELETE webhook_delivery WHERE webhook_id = $id")
+                .bind(("id", id))
+                .await;
+            if let Err(e) = cleared {
+                error!("Failed to delete deliveries of webhook {}: {}", id, e);
+            }
+            Status::NoContent
+        },
+        Ok(None) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
+
+// Newest first
+#[get("/webhooks/<id>/deliveries?<limit>")]
+pub async fn list_deliveries(id: &str, limit: Option<usize>, workspace: InWorkspace, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>) -> Result<Json<Vec<WebhookDelivery>>, Status> {
+    workspace.require(WorkspaceRole::Owner)?;
+    find_webhook(state, &workspace, id).await?;
+
+    state.db.query("SELECT * FROM webhook_delivery WHERE webhook_id = $id ORDER BY created_at DESC LIMIT $limit")
+        .bind(("id", id))
+        .bind(("limit", limit.unwrap_or(MAX_DELIVERIES).min(MAX_DELIVERIES)))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+// Send a `ping` right away and answer with the outcome of the first attempt.
+// If it fails, the usual retries follow in the background.
+#[post("/webhooks/<id>/test")]
+pub async fn test_webhook(id: &str, workspace: InWorkspace, _rate: RateLimit, _auth: Authorized<CanAdmin>, state: &State<Arc<AppState>>, webhooks: &State<Arc<Webhooks>>) -> Result<Json<WebhookDelivery>, Status> {
+    workspace.require(WorkspaceRole::Owner)?;
+    let webhook = find_webhook(state, &workspace, id).await?;
+
+    let data = serde_json::json!({ "webhook_id": id, "url": webhook.url });
+    let mut delivery = create_delivery(state, &webhook, PING_EVENT, data)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    attempt(state, webhooks, &webhook, &mut delivery).await;
+
+    if delivery.status == DeliveryStatus::Pending {
+        rocket::tokio::spawn(deliver(state.inner().clone(), webhooks.inner().clone(), webhook, delivery.clone()));
+    }
+    Ok(Json(delivery))
+}
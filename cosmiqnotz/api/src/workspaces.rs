@@ -0,0 +1,353 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use surrealdb::engine::remote::ws::Client;
+use surrealdb::Surreal;
+use std::sync::Arc;
+
+use crate::api_keys::{Authorized, CanRead, CanWrite};
+use crate::audit::ClientInfo;
+use crate::limits::RateLimit;
+use crate::id::WorkspaceId;
+use crate::note::Note;
+use crate::workspace::{MemberRole, NewWorkspace, UserWorkspace, Workspace, WorkspaceMember, WorkspaceRole};
+use crate::AppState;
+
+// Header naming the workspace a request acts in. Clients that can't set headers,
+// such as EventSource, pass `?workspace=` instead. Without either, the caller's
+// personal workspace is used.
+pub const WORKSPACE_HEADER: &str = "X-Cosmiq-Workspace";
+
+const PERSONAL_NAME: &str = "Personal";
+
+fn short_hash(value: &str) -> String {
+    Sha256::digest(value.as_bytes()).iter().take(12).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Personal workspaces and memberships have ids derived from their contents, so
+// creating one twice writes the same record instead of a duplicate
+fn personal_id(user: &str) -> String {
+    format!("personal_{}", short_hash(user))
+}
+
+fn member_id(workspace_id: &str, user: &str) -> String {
+    format!("{}_{}", short_hash(workspace_id), short_hash(user))
+}
+
+async fn set_member(state: &AppState, workspace_id: &str, user: &str, role: WorkspaceRole) -> Result<Option<WorkspaceMember>, surrealdb::Error> {
+    let member = WorkspaceMember {
+        id: None,
+        workspace_id: workspace_id.into(),
+        user: user.to_string(),
+        role,
+        added_at: Utc::now(),
+    };
+    state.db.update(("workspace_member", member_id(workspace_id, user)))
+        .content(&member)
+        .await
+}
+
+async fn membership(state: &AppState, workspace_id: &str, user: &str) -> Result<Option<WorkspaceMember>, surrealdb::Error> {
+    state.db.select(("workspace_member", member_id(workspace_id, user))).await
+}
+
+// The user's personal workspace, created the first time it is needed
+pub async fn ensure_personal(state: &AppState, user: &str) -> Result<String, surrealdb::Error> {
+    let id = personal_id(user);
+    let existing: Option<Workspace> = state.db.select(("workspace", id.as_str())).await?;
+    if existing.is_none() {
+        let workspace = Workspace {
+            id: None,
+            name: PERSONAL_NAME.to_string(),
+            created_by: user.to_string(),
+            personal: true,
+            created_at: Utc::now(),
+        };
+        // Two requests may race to create it; whichever loses finds it there already
+        let created: Result<Option<Workspace>, surrealdb::Error> = state.db.create(("workspace", id.as_str()))
+            .content(&workspace)
+            .await;
+        if created.is_ok() {
+            set_member(state, &id, user, WorkspaceRole::Owner).await?;
+        }
+    }
+    Ok(id)
+}
+
+// The caller's role in a workspace; 404 when they aren't a member, so workspaces
+// of other people can't be discovered
+async fn role_in(state: &AppState, workspace_id: &str, user: &str) -> Result<WorkspaceRole, Status> {
+    membership(state, workspace_id, user)
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .map(|member| member.role)
+        .ok_or(Status::NotFound)
+}
+
+fn caller(client: &ClientInfo) -> Result<String, Status> {
+    client.actor.clone().ok_or(Status::Unauthorized)
+}
+
+// The workspace a request acts in, and the caller's role there. Every note query
+// is limited to this workspace.
+pub struct InWorkspace {
+    pub id: WorkspaceId,
+    pub user: String,
+    pub role: WorkspaceRole,
+}
+
+impl InWorkspace {
+    // Fails with 403 when the caller's role is below `role`
+    pub fn require(&self, role: WorkspaceRole) -> Result<(), Status> {
+        if self.role >= role {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+
+    pub fn contains(&self, note: &Note) -> bool {
+        note.workspace_id.as_ref() == Some(&self.id)
+    }
+
+    // A note in this workspace that `db`'s user can see; anything else is reported
+    // as missing
+    pub async fn find_note(&self, db: &Surreal<Client>, id: &str) -> Result<Note, Status> {
+        let note: Option<Note> = db.select(("note", id))
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        note.filter(|note| self.contains(note)).ok_or(Status::NotFound)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for InWorkspace {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let client = match request.guard::<ClientInfo>().await {
+            Outcome::Success(client) => client,
+            _ => ClientInfo::default(),
+        };
+        let Ok(user) = caller(&client) else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+        let Some(state) = request.rocket().state::<Arc<AppState>>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        let requested = request.headers()
+            .get_one(WORKSPACE_HEADER)
+            .map(str::to_string)
+            .or_else(|| request.query_value::<String>("workspace").and_then(Result::ok))
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        let id = match requested {
+            Some(id) => id,
+            None => match ensure_personal(state, &user).await {
+                Ok(id) => id,
+                Err(e) => {
+                    error!("Failed to create personal workspace for {}: {}", user, e);
+                    return Outcome::Error((Status::InternalServerError, ()));
+                },
+            },
+        };
+
+        match role_in(state, &id, &user).await {
+            Ok(role) => Outcome::Success(InWorkspace { id: id.into(), user, role }),
+            Err(status) => Outcome::Error((status, ())),
+        }
+    }
+}
+
+// Notes saved before workspaces existed move into their creator's personal workspace
+async fn assign_unscoped_notes(state: &AppState) -> Result<usize, surrealdb::Error> {
+    #[derive(serde::Deserialize)]
+    struct Creator {
+        created_by: String,
+    }
+
+    let creators: Vec<Creator> = state.db
+        .query("SELECT created_by FROM note WHERE workspace_id = NONE GROUP BY created_by")
+        .await?
+        .take(0)?;
+
+    for creator in &creators {
+        let workspace_id = ensure_personal(state, &creator.created_by).await?;
+        state.db
+            .query("UPDATE note SET workspace_id = $workspace WHERE workspace_id = NONE AND created_by = $user; UPDATE task SET workspace_id = $workspace WHERE workspace_id = NONE AND created_by = $user;")
+            .bind(("workspace", workspace_id))
+            .bind(("user", creator.created_by.as_str()))
+            .await?
+            .check()?;
+    }
+    Ok(creators.len())
+}
+
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Workspace Migration", |rocket| Box::pin(async move {
+        let Some(state) = rocket.state::<Arc<AppState>>().cloned() else {
+            error!("Workspace migration could not find application state");
+            return;
+        };
+        match assign_unscoped_notes(&state).await {
+            Ok(0) => {},
+            Ok(users) => tracing::info!(users, "Moved notes without a workspace into personal workspaces"),
+            Err(e) => error!("Failed to move notes into workspaces: {}", e),
+        }
+    }))
+}
+
+// Every workspace the caller belongs to, personal first
+#[get("/workspaces")]
+pub async fn list_workspaces(client: ClientInfo, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Json<Vec<UserWorkspace>>, Status> {
+    let user = caller(&client)?;
+    ensure_personal(state, &user)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    let members: Vec<WorkspaceMember> = state.db.query("SELECT * FROM workspace_member WHERE user = $user")
+        .bind(("user", user.as_str()))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+
+    let mut workspaces = Vec::with_capacity(members.len());
+    for member in members {
+        let workspace: Option<Workspace> = state.db.select(("workspace", member.workspace_id.as_str()))
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        if let Some(workspace) = workspace {
+            workspaces.push(UserWorkspace { workspace, role: member.role });
+        }
+    }
+    workspaces.sort_by(|a, b| {
+        b.workspace.personal.cmp(&a.workspace.personal)
+            .then_with(|| a.workspace.name.to_lowercase().cmp(&b.workspace.name.to_lowercase()))
+    });
+
+    Ok(Json(workspaces))
+}
+
+// The caller becomes the new workspace's owner
+#[post("/workspaces", data = "<workspace>")]
+pub async fn create_workspace(workspace: Json<NewWorkspace>, client: ClientInfo, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<UserWorkspace>, Status> {
+    let user = caller(&client)?;
+    workspace.validate().map_err(|_| Status::UnprocessableEntity)?;
+    let name = workspace.name.trim().to_string();
+
+    let workspace = Workspace {
+        id: None,
+        name,
+        created_by: user.clone(),
+        personal: false,
+        created_at: Utc::now(),
+    };
+    let created: Vec<Workspace> = state.db.create("workspace")
+        .content(&workspace)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let created = created.into_iter().next().ok_or(Status::InternalServerError)?;
+    let id = created.id.clone().ok_or(Status::InternalServerError)?;
+
+    set_member(state, &id, &user, WorkspaceRole::Owner)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(UserWorkspace { workspace: created, role: WorkspaceRole::Owner }))
+}
+
+#[get("/workspaces/<id>/members")]
+pub async fn list_members(id: &str, client: ClientInfo, _auth: Authorized<CanRead>, state: &State<Arc<AppState>>) -> Result<Json<Vec<WorkspaceMember>>, Status> {
+    let user = caller(&client)?;
+    role_in(state, id, &user).await?;
+
+    state.db.query("SELECT * FROM workspace_member WHERE workspace_id = $workspace ORDER BY added_at")
+        .bind(("workspace", id))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+async fn owner_count(state: &AppState, workspace_id: &str) -> Result<usize, Status> {
+    let owners: Vec<WorkspaceMember> = state.db.query("SELECT * FROM workspace_member WHERE workspace_id = $workspace AND role = 'owner'")
+        .bind(("workspace", workspace_id))
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|_| Status::InternalServerError)?;
+    Ok(owners.len())
+}
+
+// Add a member or change their role. Only owners manage members, and a workspace
+// always keeps at least one owner.
+#[put("/workspaces/<id>/members/<member>", data = "<role>")]
+pub async fn set_member_role(id: &str, member: &str, role: Json<MemberRole>, client: ClientInfo, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Result<Json<WorkspaceMember>, Status> {
+    let user = caller(&client)?;
+    if role_in(state, id, &user).await? != WorkspaceRole::Owner {
+        return Err(Status::Forbidden);
+    }
+    let workspace: Option<Workspace> = state.db.select(("workspace", id))
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let workspace = workspace.ok_or(Status::NotFound)?;
+
+    let member = member.trim();
+    if member.is_empty() || (workspace.personal && member != user) {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let current = membership(state, id, member)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    let demoting_owner = matches!(&current, Some(current) if current.role == WorkspaceRole::Owner) && role.role != WorkspaceRole::Owner;
+    if demoting_owner && owner_count(state, id).await? <= 1 {
+        return Err(Status::Conflict);
+    }
+
+    set_member(state, id, member, role.role)
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .map(Json)
+        .ok_or(Status::InternalServerError)
+}
+
+// Owners can remove anyone; other members can only leave
+#[delete("/workspaces/<id>/members/<member>")]
+pub async fn remove_member(id: &str, member: &str, client: ClientInfo, _rate: RateLimit, _auth: Authorized<CanWrite>, state: &State<Arc<AppState>>) -> Status {
+    let user = match caller(&client) {
+        Ok(user) => user,
+        Err(status) => return status,
+    };
+    let role = match role_in(state, id, &user).await {
+        Ok(role) => role,
+        Err(status) => return status,
+    };
+    if role != WorkspaceRole::Owner && member != user {
+        return Status::Forbidden;
+    }
+
+    let current = match membership(state, id, member).await {
+        Ok(Some(current)) => current,
+        Ok(None) => return Status::NotFound,
+        Err(_) => return Status::InternalServerError,
+    };
+    if current.role == WorkspaceRole::Owner {
+        match owner_count(state, id).await {
+            Ok(count) if count <= 1 => return Status::Conflict,
+            Ok(_) => {},
+            Err(status) => return status,
+        }
+    }
+
+    let deleted: Result<Option<WorkspaceMember>, surrealdb::Error> = state.db.delete(("workspace_member", member_id(id, member))).await;
+    match deleted {
+        Ok(_) => Status::NoContent,
+        Err(_) => Status::InternalServerError,
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::error::ValidationError;
+use crate::id::ApiKeyId;
+
+// What a key may do. Each scope includes the ones before it, so `write` can also read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Read => "read",
+            ApiKeyScope::Write => "write",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+}
+
+// A named key for scripts and integrations. Only a hash of the key is stored;
+// `prefix` is kept so people can tell their keys apart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ApiKeyId>,
+    pub name: String,
+    pub user: String,
+    pub prefix: String,
+    // Never sent to clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_hash: Option<String>,
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && !matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+
+    pub fn allows(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|granted| *granted >= scope)
+    }
+}
+
+// Body of a request to create a key
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl NewApiKey {
+    // Keys need a name, at least one scope and an expiry that hasn't passed
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::new("name", "must not be blank"));
+        }
+        if self.scopes.is_empty() {
+            return Err(ValidationError::new("scopes", "must grant at least one scope"));
+        }
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ValidationError::new("expires_at", "must be in the future"));
+        }
+        Ok(())
+    }
+}
+
+// The key itself is only ever returned here, when it is created
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CreatedApiKey {
+    pub key: ApiKey,
+    pub token: String,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::id::NoteId;
+use crate::note::Note;
+
+// What to do to a note. `delete` moves it to the trash and `restore` takes it back out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchAction {
+    Delete,
+    Restore,
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    Move { notebook: Option<String> },
+    Share { shared_with: Vec<String> },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchOperation {
+    pub note_id: NoteId,
+    #[serde(flatten)]
+    pub action: BatchAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Ok,
+    NotFound,
+    Invalid,
+    // Not applied because another operation in the batch failed
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub note_id: NoteId,
+    pub status: BatchItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// Either every operation was applied or none were; `notes` holds the changed notes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub committed: bool,
+    pub results: Vec<BatchItemResult>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::error::ValidationError;
+use crate::id::{CommentId, NoteId};
+
+// A range of the note's content a comment refers to. `quote` keeps the original
+// text so the anchor can be found again after the note is edited.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommentAnchor {
+    pub start: usize,
+    pub end: usize,
+    pub quote: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<CommentId>,
+    pub note_id: NoteId,
+    pub author: String,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<CommentId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<CommentAnchor>,
+    #[serde(default)]
+    pub resolved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Body of a request to start a thread or reply to one; the author is whoever sends it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NewComment {
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<CommentId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<CommentAnchor>,
+}
+
+impl NewComment {
+    // Whether a reply fits its thread can only be checked against the thread itself
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.body.trim().is_empty() {
+            return Err(ValidationError::new("body", "must not be blank"));
+        }
+        if self.anchor.as_ref().is_some_and(|anchor| anchor.start > anchor.end) {
+            return Err(ValidationError::new("anchor", "must not end before it starts"));
+        }
+        Ok(())
+    }
+}
+
+impl Comment {
+    pub fn new(note_id: NoteId, author: String, comment: NewComment) -> Self {
+        let now = Utc::now();
+        Self {
+            id: None,
+            note_id,
+            author,
+            body: comment.body,
+            parent_id: comment.parent_id,
+            anchor: comment.anchor,
+            resolved: false,
+            resolved_by: None,
+            resolved_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
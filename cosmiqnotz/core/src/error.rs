@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+// Machine readable reasons the API gives in error bodies. Clients should match on
+// these rather than on the message, which is meant for people.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    PayloadTooLarge,
+    UnprocessableEntity,
+    Locked,
+    RateLimited,
+    InternalServerError,
+    ServiceUnavailable,
+}
+
+impl ErrorCode {
+    // The code the API uses for an HTTP status, if it has one of its own
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            400 => Some(Self::BadRequest),
+            401 => Some(Self::Unauthorized),
+            403 => Some(Self::Forbidden),
+            404 => Some(Self::NotFound),
+            409 => Some(Self::Conflict),
+            412 => Some(Self::PreconditionFailed),
+            413 => Some(Self::PayloadTooLarge),
+            422 => Some(Self::UnprocessableEntity),
+            423 => Some(Self::Locked),
+            429 => Some(Self::RateLimited),
+            500 => Some(Self::InternalServerError),
+            503 => Some(Self::ServiceUnavailable),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "bad_request",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::Conflict => "conflict",
+            Self::PreconditionFailed => "precondition_failed",
+            Self::PayloadTooLarge => "payload_too_large",
+            Self::UnprocessableEntity => "unprocessable_entity",
+            Self::Locked => "locked",
+            Self::RateLimited => "rate_limited",
+            Self::InternalServerError => "internal_server_error",
+            Self::ServiceUnavailable => "service_unavailable",
+        }
+    }
+}
+
+// `code` is kept as a string so clients still parse codes they don't know yet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub status: u16,
+    pub code: String,
+    pub message: String,
+    pub request_id: String,
+}
+
+// Body of every error response: `{"error": {"status", "code", "message", "request_id"}}`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: ErrorDetail,
+}
+
+// A request body that breaks one of the rules the API enforces. The frontend checks
+// the same rules before sending.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub reason: &'static str,
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, reason: &'static str) -> Self {
+        Self { field, reason }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for ValidationError {}
@@ -0,0 +1,27 @@
+// The note model and the request and response bodies of the API, shared by the API
+// server, the Yew frontend and the Tauri shell so they can't drift apart. Everything
+// here builds for native targets and wasm32 alike.
+
+pub mod api_key;
+pub mod batch;
+pub mod comment;
+pub mod error;
+pub mod id;
+pub mod note;
+pub mod reminder;
+pub mod session;
+pub mod share_link;
+pub mod task;
+pub mod workspace;
+
+pub use api_key::{ApiKey, ApiKeyScope, CreatedApiKey, NewApiKey};
+pub use batch::{BatchAction, BatchItemResult, BatchItemStatus, BatchOperation, BatchReport, BatchRequest};
+pub use comment::{Comment, CommentAnchor, NewComment};
+pub use error::{ErrorBody, ErrorCode, ErrorDetail, ValidationError};
+pub use id::{ApiKeyId, CommentId, NoteId, ShareLinkId, TaskId, WorkspaceId};
+pub use note::{Note, NoteEncryption, NoteFlags, NoteLock};
+pub use reminder::{ReminderEvent, ReminderKind};
+pub use session::{SessionToken, SignIn, SignUp};
+pub use share_link::{CreatedShareLink, NewShareLink, ShareLink};
+pub use task::Task;
+pub use workspace::{MemberRole, NewWorkspace, UserWorkspace, Workspace, WorkspaceMember, WorkspaceRole};
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::error::ValidationError;
+use crate::id::{NoteId, WorkspaceId};
+
+// How an encrypted note's title and content were sealed on the client.
+// The server only ever sees the ciphertext and these parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteEncryption {
+    pub algorithm: String,
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub title_nonce: String,
+    pub content_nonce: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<NoteId>,
+    pub title: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: String,
+    pub is_shared: bool,
+    pub shared_with: Vec<String>,
+    pub version: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notebook: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remind_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_sent_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_sent_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<NoteEncryption>,
+    // Set while the note is in the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub archived: bool,
+    // Set by the API when the note is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<WorkspaceId>,
+    // Advisory edit lock, managed through the lock endpoint. While it lasts only the
+    // holder may save the note.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_expires_at: Option<DateTime<Utc>>,
+}
+
+// Who holds the edit lock on a note, and until when unless they renew it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoteLock {
+    pub note_id: NoteId,
+    pub locked_by: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// A change to a note's pinned, favorite and archived flags; unset flags are left alone
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteFlags {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favorite: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+}
+
+impl Note {
+    pub fn new(title: String, content: String, user_id: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: None,
+            title,
+            content,
+            created_at: now,
+            updated_at: now,
+            created_by: user_id,
+            is_shared: false,
+            shared_with: vec![],
+            version: 1,
+            tags: vec![],
+            notebook: None,
+            due_at: None,
+            remind_at: None,
+            reminder_sent_at: None,
+            due_sent_at: None,
+            encryption: None,
+            deleted_at: None,
+            pinned: false,
+            favorite: false,
+            archived: false,
+            workspace_id: None,
+            locked_by: None,
+            lock_expires_at: None,
+        }
+    }
+
+    pub fn update(&mut self, title: String, content: String) {
+        self.title = title;
+        self.content = content;
+        self.updated_at = Utc::now();
+        self.version += 1;
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    pub fn set_flags(&mut self, flags: &NoteFlags) {
+        self.pinned = flags.pinned.unwrap_or(self.pinned);
+        self.favorite = flags.favorite.unwrap_or(self.favorite);
+        self.archived = flags.archived.unwrap_or(self.archived);
+    }
+
+    // The user holding an unexpired edit lock on the note, if any
+    pub fn lock_holder(&self, now: DateTime<Utc>) -> Option<&str> {
+        match (&self.locked_by, self.lock_expires_at) {
+            (Some(user), Some(expires_at)) if expires_at > now => Some(user),
+            _ => None,
+        }
+    }
+
+    // Whether someone other than `user` holds the edit lock
+    pub fn is_locked_for(&self, user: &str, now: DateTime<Utc>) -> bool {
+        self.lock_holder(now).is_some_and(|holder| holder != user)
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    // Checked before a note is saved, whether it was written in the app, sent to the API
+    // or imported. Clearing the notebook means leaving it out, not sending a blank one.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.notebook.as_deref().is_some_and(|notebook| notebook.trim().is_empty()) {
+            return Err(ValidationError::new("notebook", "must not be blank"));
+        }
+        if self.tags.iter().any(|tag| tag.trim().is_empty()) {
+            return Err(ValidationError::new("tags", "must not contain blank tags"));
+        }
+        if let Some(encryption) = &self.encryption {
+            let parameters = [&encryption.algorithm, &encryption.kdf, &encryption.salt, &encryption.title_nonce, &encryption.content_nonce];
+            if encryption.iterations == 0 || parameters.iter().any(|parameter| parameter.is_empty()) {
+                return Err(ValidationError::new("encryption", "must name every parameter used to seal the note"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the reminder is due and has not been delivered since it was last scheduled.
+    pub fn reminder_pending(&self, now: DateTime<Utc>) -> bool {
+        match (self.remind_at, self.reminder_sent_at) {
+            (Some(remind_at), Some(sent_at)) => remind_at <= now && sent_at < remind_at,
+            (Some(remind_at), None) => remind_at <= now,
+            _ => false,
+        }
+    }
+
+    /// Whether the due date has passed and not been announced since it was last set.
+    pub fn due_pending(&self, now: DateTime<Utc>) -> bool {
+        match (self.due_at, self.due_sent_at) {
+            (Some(due_at), Some(sent_at)) => due_at <= now && sent_at < due_at,
+            (Some(due_at), None) => due_at <= now,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::id::{NoteId, WorkspaceId};
+use crate::note::Note;
+
+// Shown instead of the ciphertext title of encrypted notes
+const ENCRYPTED_TITLE: &str = "Encrypted note";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderKind {
+    Reminder,
+    Due,
+}
+
+// Sent on the reminder event stream and listed by the upcoming reminders endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReminderEvent {
+    pub note_id: NoteId,
+    pub title: String,
+    pub kind: ReminderKind,
+    pub at: DateTime<Utc>,
+    // Lets the event stream only send a listener reminders from its own workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<WorkspaceId>,
+}
+
+impl ReminderEvent {
+    pub fn new(note: &Note, kind: ReminderKind, at: DateTime<Utc>) -> Self {
+        Self {
+            note_id: note.id.clone().unwrap_or_default(),
+            title: if note.is_encrypted() { ENCRYPTED_TITLE.to_string() } else { note.title.clone() },
+            kind,
+            at,
+            workspace_id: note.workspace_id.clone(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+// Body of a request to create an account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignUp {
+    pub username: String,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignIn {
+    pub username: String,
+    pub password: String,
+}
+
+// A session token for `Authorization: Bearer`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionToken {
+    pub user: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SessionToken {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
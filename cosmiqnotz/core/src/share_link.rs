@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::error::ValidationError;
+use crate::id::{NoteId, ShareLinkId, WorkspaceId};
+
+// A public, read-only link to one note. Only a hash of the link's token is stored;
+// `prefix` is kept so people can tell their links apart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShareLink {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ShareLinkId>,
+    pub note_id: NoteId,
+    pub workspace_id: WorkspaceId,
+    pub created_by: String,
+    pub prefix: String,
+    // Never sent to clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_hash: Option<String>,
+    // Never sent to clients; `password_protected` says whether there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub password_protected: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub views: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_viewed_at: Option<DateTime<Utc>>,
+}
+
+impl ShareLink {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && !matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+}
+
+// Body of a request to share a note
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewShareLink {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    // Visitors have to enter it before the note is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl NewShareLink {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), ValidationError> {
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ValidationError::new("expires_at", "must be in the future"));
+        }
+        Ok(())
+    }
+}
+
+// The link's token is only ever returned here, when it is created. `path` is
+// relative to the API server's root, e.g. `/s/<token>`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CreatedShareLink {
+    pub link: ShareLink,
+    pub token: String,
+    pub path: String,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::id::{NoteId, TaskId, WorkspaceId};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<TaskId>,
+    pub note_id: NoteId,
+    pub note_title: String,
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+    pub created_by: String,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<WorkspaceId>,
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::error::ValidationError;
+use crate::id::WorkspaceId;
+
+// What a member may do in a workspace. Each role includes the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceRole {
+    // Read notes and comment on them
+    Viewer,
+    // Create, edit and delete notes
+    Editor,
+    // Manage members
+    Owner,
+}
+
+// A set of notes shared by its members. Every user has a personal workspace
+// that nobody else can join.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<WorkspaceId>,
+    pub name: String,
+    pub created_by: String,
+    #[serde(default)]
+    pub personal: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub workspace_id: WorkspaceId,
+    pub user: String,
+    pub role: WorkspaceRole,
+    pub added_at: DateTime<Utc>,
+}
+
+// A workspace together with the caller's role in it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserWorkspace {
+    pub workspace: Workspace,
+    pub role: WorkspaceRole,
+}
+
+// Body of a request to create a workspace
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NewWorkspace {
+    pub name: String,
+}
+
+impl NewWorkspace {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::new("name", "must not be blank"));
+        }
+        Ok(())
+    }
+}
+
+// Body of a request to add a member or change their role
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MemberRole {
+    pub role: WorkspaceRole,
+}
@@ -1,20 +1,50 @@
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::fs;
 use std::path::Path;
-use std::io::Write;
-use tauri::command;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Mutex;
+use tauri::api::notification::Notification;
+use tauri::{command, AppHandle, Manager, State};
+use cosmiqnotz_core::{ReminderEvent, ReminderKind};
+
+// The session token of whoever is signed in to the app. The API only answers note
+// requests for a signed-in user, so the calls made from here send it too.
+#[derive(Default)]
+pub struct ApiSession(Mutex<Option<String>>);
+
+impl ApiSession {
+    fn token(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|token| token.clone())
+    }
+}
+
+// Called by the app when someone signs in or out
+#[command]
+pub fn set_session(token: Option<String>, session: State<'_, ApiSession>) {
+    if let Ok(mut current) = session.0.lock() {
+        *current = token.filter(|token| !token.is_empty());
+    }
+}
+
+// curl with the signed-in user's session token, if there is one
+fn api_request(session: &ApiSession) -> Command {
+    let mut command = Command::new("curl");
+    if let Some(token) = session.token() {
+        command.arg("-H").arg(format!("Authorization: Bearer {}", token));
+    }
+    command
+}
 
 #[command]
 pub fn check_api_status() -> bool {
-    // Simple check to see if the API is responding
+    // Ask the API's liveness endpoint whether it is responding
     let status = Command::new("curl")
         .arg("--silent")
-        .arg("--head")
         .arg("--output")
         .arg("/dev/null")
         .arg("--write-out")
         .arg("%{http_code}")
-        .arg("http://localhost:8000/api/notes")
+        .arg("http://localhost:8000/health")
         .output();
     
     match status {
@@ -31,46 +61,76 @@ pub fn check_api_status() -> bool {
 }
 
 #[command]
-pub fn export_notes(path: String) -> Result<String, String> {
-    // Export notes to a JSON file
-    let notes = Command::new("curl")
+pub fn export_notes(path: String, format: Option<String>, note: Option<String>, notebook: Option<String>, session: State<'_, ApiSession>) -> Result<String, String> {
+    // Export a note, a notebook or everything as Markdown, HTML or a zip archive
+    // rendered by the API
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair("format", format.as_deref().unwrap_or("zip"));
+    if let Some(note) = &note {
+        query.append_pair("note", note);
+    }
+    if let Some(notebook) = &notebook {
+        query.append_pair("notebook", notebook);
+    }
+    let url = format!("http://localhost:8000/api/export?{}", query.finish());
+
+    let status = api_request(&session)
         .arg("--silent")
-        .arg("http://localhost:8000/api/notes")
+        .arg("--fail")
+        .arg("--output")
+        .arg(&path)
+        .arg(&url)
         .output()
-        .map_err(|e| format!("Failed to fetch notes: {}", e))?;
+        .map_err(|e| format!("Failed to fetch export: {}", e))?;
     
-    if !notes.status.success() {
-        return Err("Failed to fetch notes from API".to_string());
+    if !status.status.success() {
+        return Err("Failed to export notes from API".to_string());
     }
     
-    let mut file = fs::File::create(&path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    
-    file.write_all(&notes.stdout)
-        .map_err(|e| format!("Failed to write to file: {}", e))?;
-    
     Ok(format!("Notes exported to {}", path))
 }
 
 #[command]
-pub fn import_notes(path: String) -> Result<String, String> {
-    // Import notes from a JSON file
-    if !Path::new(&path).exists() {
+pub fn import_notes(path: String, dry_run: Option<bool>, session: State<'_, ApiSession>) -> Result<String, String> {
+    // Import notes from our JSON export, a Markdown folder, an Obsidian vault or an
+    // Evernote .enex file, returning the API's per-file report
+    let source = Path::new(&path);
+    if !source.exists() {
         return Err("File does not exist".to_string());
     }
+    let dry_run = dry_run.unwrap_or(false);
     
-    let notes = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let is_json = source.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let (url, body) = if is_json {
+        let notes = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        (format!("http://localhost:8000/api/notes/import?dry_run={}", dry_run), notes)
+    } else {
+        let kind = if source.is_dir() && source.join(".obsidian").is_dir() {
+            "obsidian"
+        } else if source.is_dir() {
+            "markdown"
+        } else {
+            "enex"
+        };
+        let request = serde_json::json!({
+            "source": kind,
+            "path": path,
+            "dry_run": dry_run,
+        });
+        ("http://localhost:8000/api/import".to_string(), request.to_string())
+    };
     
-    let status = Command::new("curl")
+    let status = api_request(&session)
         .arg("--silent")
+        .arg("--fail")
         .arg("-X")
         .arg("POST")
         .arg("-H")
         .arg("Content-Type: application/json")
         .arg("-d")
-        .arg(&notes)
-        .arg("http://localhost:8000/api/notes/import")
+        .arg(&body)
+        .arg(&url)
         .output()
         .map_err(|e| format!("Failed to import notes: {}", e))?;
     
@@ -78,7 +138,46 @@ pub fn import_notes(path: String) -> Result<String, String> {
         return Err("Failed to import notes".to_string());
     }
     
-    Ok("Notes imported successfully".to_string())
+    Ok(String::from_utf8_lossy(&status.stdout).to_string())
+}
+
+#[command]
+pub fn list_backups(session: State<'_, ApiSession>) -> Result<String, String> {
+    let output = api_request(&session)
+        .arg("--silent")
+        .arg("--fail")
+        .arg("http://localhost:8000/api/admin/backups")
+        .output()
+        .map_err(|e| format!("Failed to list backups: {}", e))?;
+    
+    if !output.status.success() {
+        return Err("Failed to list backups".to_string());
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[command]
+pub fn restore_backup(name: String, session: State<'_, ApiSession>) -> Result<String, String> {
+    // The API verifies the backup and snapshots the current database before restoring
+    let output = api_request(&session)
+        .arg("--silent")
+        .arg("-X")
+        .arg("POST")
+        .arg("--write-out")
+        .arg("\n%{http_code}")
+        .arg(format!("http://localhost:8000/api/admin/backups/{}/restore", name))
+        .output()
+        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+    
+    let response = String::from_utf8_lossy(&output.stdout).to_string();
+    let (body, status_code) = response.rsplit_once('\n').unwrap_or(("", response.as_str()));
+    
+    if !status_code.starts_with('2') {
+        return Err(format!("Failed to restore backup: {}", body));
+    }
+    
+    Ok(body.to_string())
 }
 
 pub fn start_surrealdb() -> Result<(), String> {
@@ -142,7 +241,7 @@ pub fn start_surrealdb() -> Result<(), String> {
     Ok(())
 }
 
-pub fn start_api_server() -> Result<(), String> {
+pub fn start_api_server(backup_dir: &Path, log_dir: &Path, import_root: &Path) -> Result<(), String> {
     // Check if the API server is already running
     let status = Command::new("pgrep")
         .arg("-f")
@@ -162,6 +261,9 @@ pub fn start_api_server() -> Result<(), String> {
         .arg("--bin")
         .arg("cosmiqnotz_api")
         .current_dir("../api")
+        .env("ROCKET_BACKUP_DIR", backup_dir)
+        .env("ROCKET_LOG_DIR", log_dir)
+        .env("ROCKET_IMPORT_ROOT", import_root)
         .spawn()
         .map_err(|e| format!("Failed to start API server: {}", e))?;
     
@@ -169,4 +271,49 @@ pub fn start_api_server() -> Result<(), String> {
     std::thread::sleep(std::time::Duration::from_secs(2));
     
     Ok(())
+}
+
+pub fn start_reminder_listener(app: AppHandle) {
+    // Follow the API's reminder event stream and surface each event as a desktop notification
+    std::thread::spawn(move || loop {
+        let child = api_request(&app.state::<ApiSession>())
+            .arg("--silent")
+            .arg("--no-buffer")
+            .arg("http://localhost:8000/api/reminders/events")
+            .stdout(Stdio::piped())
+            .spawn();
+
+        if let Ok(mut child) = child {
+            if let Some(stdout) = child.stdout.take() {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if let Some(data) = line.strip_prefix("data:") {
+                        show_reminder(&app, data.trim());
+                    }
+                }
+            }
+            child.wait().ok();
+        }
+
+        // Stream ended or the API is down; try again shortly
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    });
+}
+
+fn show_reminder(app: &AppHandle, data: &str) {
+    let event: ReminderEvent = match serde_json::from_str(data) {
+        Ok(event) => event,
+        Err(_) => return,
+    };
+
+    let title = if event.title.is_empty() { "Untitled Note" } else { event.title.as_str() };
+    let body = match event.kind {
+        ReminderKind::Due => format!("\"{}\" is due", title),
+        ReminderKind::Reminder => format!("Reminder: {}", title),
+    };
+
+    Notification::new(&app.config().tauri.bundle.identifier)
+        .title("CosmiqNotz")
+        .body(body)
+        .show()
+        .ok();
 }
\ No newline at end of file
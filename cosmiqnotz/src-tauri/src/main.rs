@@ -0,0 +1,61 @@
+// Prevents an extra console window on Windows in release builds
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+mod commands;
+
+use tauri::{CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu};
+
+use commands::ApiSession;
+
+fn tray() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("show", "Show CosmiqNotz"))
+        .add_item(CustomMenuItem::new("quit", "Quit"));
+    SystemTray::new().with_menu(menu)
+}
+
+fn main() {
+    tauri::Builder::default()
+        .manage(ApiSession::default())
+        .system_tray(tray())
+        .on_system_tray_event(|app, event| {
+            if let SystemTrayEvent::MenuItemClick { id, .. } = event {
+                match id.as_str() {
+                    "show" => {
+                        if let Some(window) = app.get_window("main") {
+                            window.show().ok();
+                            window.set_focus().ok();
+                        }
+                    },
+                    "quit" => app.exit(0),
+                    _ => {},
+                }
+            }
+        })
+        .setup(|app| {
+            // Backups and logs are kept in the app's data directory
+            let data_dir = app.path_resolver().app_data_dir().ok_or("No app data directory")?;
+            // Notes can be imported from anywhere in the user's home directory
+            let import_root = tauri::api::path::home_dir().unwrap_or_else(|| data_dir.clone());
+
+            // The app still works offline when the database or API can't be started
+            if let Err(e) = commands::start_surrealdb() {
+                eprintln!("{}", e);
+            }
+            if let Err(e) = commands::start_api_server(&data_dir.join("backups"), &data_dir.join("logs"), &import_root) {
+                eprintln!("{}", e);
+            }
+            commands::start_reminder_listener(app.handle());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::set_session,
+            commands::check_api_status,
+            commands::export_notes,
+            commands::import_notes,
+            commands::list_backups,
+            commands::restore_backup,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running CosmiqNotz");
+}
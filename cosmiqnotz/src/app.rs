@@ -2,11 +2,16 @@ use yew::prelude::*;
 use gloo::timers::callback::Interval;
 use web_sys::console;
 
-use crate::components::{NoteEditor, NoteList, Toolbar};
-use crate::models::Note;
+use crate::components::{ApiKeyPanel, CommentPanel, NoteEditor, NoteList, SharePanel, SignInForm, TaskList, Toolbar};
+use cosmiqnotz_core::{ApiKey, BatchAction, BatchItemStatus, BatchOperation, BatchReport, Comment, CreatedApiKey, CreatedShareLink, NewApiKey, NewComment, NewShareLink, Note, NoteFlags, NoteId, NoteLock, SessionToken, ShareLink, SignIn, SignUp, Task, UserWorkspace, WorkspaceId};
+use crate::services::crypto_service::KeyRing;
 use crate::services::note_service;
 
 pub enum AppMsg {
+    SignIn(SignIn),
+    SignUp(SignUp),
+    SignedIn(Result<SessionToken, String>),
+    SignOut,
     LoadNotes,
     NotesLoaded(Result<Vec<Note>, String>),
     SelectNote(Note),
@@ -18,18 +23,163 @@ pub enum AppMsg {
     SyncCompleted(Result<(), String>),
     CheckOnline,
     OnlineStatusChanged(bool),
+    ToggleTaskView,
+    LoadTasks,
+    TasksLoaded(Result<Vec<Task>, String>),
+    SetTaskFilter(String),
+    ToggleTask(Task),
+    TaskToggled(Result<Task, String>),
+    OpenNote(NoteId),
+    Unlock(String),
+    Lock,
+    ToggleEncryption(bool, String, String),
+    LoadComments,
+    CommentsLoaded(Result<Vec<Comment>, String>),
+    AddComment(NewComment),
+    CommentAdded(Result<Comment, String>),
+    ResolveComment(Comment, bool),
+    CommentResolved(Result<Comment, String>),
+    SetNoteView(String),
+    LoadView,
+    ViewLoaded(String, Result<Vec<Note>, String>),
+    SetNoteFlags(Note, NoteFlags),
+    NoteFlagsSet(Result<Note, String>),
+    BatchNotes(Vec<NoteId>, BatchAction),
+    BatchCompleted(Result<BatchReport, String>),
+    ToggleKeyView,
+    LoadKeys,
+    KeysLoaded(Result<Vec<ApiKey>, String>),
+    CreateKey(NewApiKey),
+    KeyCreated(Result<CreatedApiKey, String>),
+    RevokeKey(ApiKey),
+    KeyRevoked(Result<ApiKey, String>),
+    DismissToken,
+    LoadWorkspaces,
+    WorkspacesLoaded(Result<Vec<UserWorkspace>, String>),
+    SwitchWorkspace(WorkspaceId),
+    CreateWorkspace(String),
+    WorkspaceCreated(Result<UserWorkspace, String>),
+    TogglePreview,
+    LoadPreview,
+    PreviewLoaded(NoteId, Result<String, String>),
+    LoadShareLinks,
+    ShareLinksLoaded(NoteId, Result<Vec<ShareLink>, String>),
+    CreateShareLink(NewShareLink),
+    ShareLinkCreated(Result<CreatedShareLink, String>),
+    RevokeShareLink(ShareLink),
+    ShareLinkRevoked(Result<ShareLink, String>),
+    DismissShareUrl,
+    AcquireLock,
+    LockAcquired(NoteId, Result<NoteLock, String>),
     Error(String),
 }
 
 pub struct App {
+    // Nothing is loaded until someone signs in
+    session: Option<SessionToken>,
+    signing_in: bool,
     notes: Vec<Note>,
     selected_note: Option<Note>,
     is_loading: bool,
     is_syncing: bool,
     is_online: bool,
+    showing_tasks: bool,
+    tasks: Vec<Task>,
+    task_filter: String,
+    key_ring: Option<KeyRing>,
+    locked_notes: Vec<Note>,
+    comments: Vec<Comment>,
+    note_view: String,
+    // Notes in the current view when it isn't "all"
+    view_notes: Vec<Note>,
+    showing_keys: bool,
+    api_keys: Vec<ApiKey>,
+    new_token: Option<String>,
+    workspaces: Vec<UserWorkspace>,
+    // Empty until the user switches away from their personal workspace
+    current_workspace: WorkspaceId,
+    previewing: bool,
+    // Server-rendered HTML of the selected note's saved version
+    preview_html: Option<String>,
+    // Public links to the selected note
+    share_links: Vec<ShareLink>,
+    new_share_url: Option<String>,
+    // The selected note's edit lock, whether we hold it or someone else does
+    note_lock: Option<NoteLock>,
     error: Option<String>,
     _sync_interval: Option<Interval>,
     _online_interval: Option<Interval>,
+    _lock_interval: Option<Interval>,
+}
+
+// Pinned notes first, then most recently updated, as the API lists them
+fn sort_notes(notes: &mut [Note]) {
+    notes.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.updated_at.cmp(&a.updated_at)));
+}
+
+impl App {
+    // Who is signed in; empty when nobody is
+    fn user(&self) -> String {
+        self.session.as_ref().map(|session| session.user.clone()).unwrap_or_default()
+    }
+
+    // Id of the workspace being shown, once the workspace list has loaded
+    fn workspace_id(&self) -> Option<WorkspaceId> {
+        if !self.current_workspace.is_empty() {
+            return Some(self.current_workspace.clone());
+        }
+        self.workspaces.iter()
+            .find(|workspace| workspace.workspace.personal)
+            .and_then(|workspace| workspace.workspace.id.clone())
+    }
+
+    // Notes saved locally may come from any workspace
+    fn in_workspace(&self, note: &Note) -> bool {
+        match (&note.workspace_id, self.workspace_id()) {
+            (Some(note_workspace), Some(workspace)) => *note_workspace == workspace,
+            _ => true,
+        }
+    }
+
+    // Decrypt what we can; notes we can't decrypt stay locked and out of the editor
+    fn open_notes(&mut self, notes: Vec<Note>) -> Vec<Note> {
+        let mut opened = Vec::new();
+        self.locked_notes.clear();
+        
+        for note in notes {
+            if !note.is_encrypted() {
+                opened.push(note);
+                continue;
+            }
+            match self.key_ring.as_mut().map(|key_ring| key_ring.decrypt(&note)) {
+                Some(Ok(note)) => opened.push(note),
+                _ => self.locked_notes.push(note),
+            }
+        }
+        
+        opened
+    }
+
+    fn open_note(&mut self, note: Note) -> Result<Note, String> {
+        if !note.is_encrypted() {
+            return Ok(note);
+        }
+        match self.key_ring.as_mut() {
+            Some(key_ring) => key_ring.decrypt(&note),
+            None => Err("Unlock encrypted notes to open this note".to_string()),
+        }
+    }
+
+    // Encrypted notes only ever leave memory as ciphertext
+    fn seal_note(&mut self, note: &Note) -> Result<Note, String> {
+        if !note.is_encrypted() {
+            return Ok(note.clone());
+        }
+        match self.key_ring.as_mut() {
+            Some(key_ring) => key_ring.encrypt(note),
+            None => Err("Unlock encrypted notes before saving".to_string()),
+        }
+    }
 }
 
 impl Component for App {
@@ -37,8 +187,14 @@ impl Component for App {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        // Start loading notes
-        ctx.link().send_message(AppMsg::LoadNotes);
+        // Start loading notes, once signed in
+        let session = note_service::current_session();
+        let signed_in = session.is_some();
+        note_service::share_session_with_desktop();
+        if signed_in {
+            ctx.link().send_message(AppMsg::LoadNotes);
+            ctx.link().send_message(AppMsg::LoadWorkspaces);
+        }
         
         // Check online status
         ctx.link().send_message(AppMsg::CheckOnline);
@@ -58,20 +214,98 @@ impl Component for App {
             })
         };
 
+        // Renews our edit lock well before it expires, and notices when someone
+        // else's lock is released
+        let lock_interval = {
+            let link = ctx.link().clone();
+            Interval::new(30_000, move || {
+                link.send_message(AppMsg::AcquireLock);
+            })
+        };
+
         Self {
+            session,
+            signing_in: false,
             notes: Vec::new(),
             selected_note: None,
-            is_loading: true,
+            is_loading: signed_in,
             is_syncing: false,
             is_online: false,
+            showing_tasks: false,
+            tasks: Vec::new(),
+            task_filter: "open".to_string(),
+            key_ring: None,
+            locked_notes: Vec::new(),
+            comments: Vec::new(),
+            note_view: "all".to_string(),
+            view_notes: Vec::new(),
+            showing_keys: false,
+            api_keys: Vec::new(),
+            new_token: None,
+            workspaces: Vec::new(),
+            current_workspace: note_service::current_workspace(),
+            previewing: false,
+            preview_html: None,
+            share_links: Vec::new(),
+            new_share_url: None,
+            note_lock: None,
             error: None,
             _sync_interval: Some(sync_interval),
             _online_interval: Some(online_interval),
+            _lock_interval: Some(lock_interval),
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            AppMsg::SignIn(credentials) => {
+                self.signing_in = true;
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    link.send_message(AppMsg::SignedIn(note_service::sign_in(&credentials).await));
+                });
+                true
+            },
+            AppMsg::SignUp(account) => {
+                self.signing_in = true;
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    link.send_message(AppMsg::SignedIn(note_service::sign_up(&account).await));
+                });
+                true
+            },
+            AppMsg::SignedIn(result) => {
+                self.signing_in = false;
+                match result {
+                    Ok(session) => {
+                        self.session = Some(session);
+                        self.error = None;
+                        ctx.link().send_message(AppMsg::LoadNotes);
+                        ctx.link().send_message(AppMsg::LoadWorkspaces);
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::SignOut => {
+                note_service::sign_out();
+                self.session = None;
+                self.notes.clear();
+                self.view_notes.clear();
+                self.locked_notes.clear();
+                self.selected_note = None;
+                self.comments.clear();
+                self.tasks.clear();
+                self.api_keys.clear();
+                self.new_token = None;
+                self.workspaces.clear();
+                self.current_workspace = WorkspaceId::default();
+                self.share_links.clear();
+                self.new_share_url = None;
+                self.note_lock = None;
+                self.key_ring = None;
+                true
+            },
             AppMsg::LoadNotes => {
                 self.is_loading = true;
                 // First try to load from API
@@ -94,10 +328,16 @@ impl Component for App {
                 self.is_loading = false;
                 match result {
                     Ok(notes) => {
-                        self.notes = notes;
+                        // Notes read back from local storage may include archived ones
+                        let notes = notes.into_iter().filter(|note| !note.archived && self.in_workspace(note)).collect();
+                        self.notes = self.open_notes(notes);
+                        sort_notes(&mut self.notes);
                         // Select the first note if none is selected
                         if self.selected_note.is_none() && !self.notes.is_empty() {
                             self.selected_note = Some(self.notes[0].clone());
+                            ctx.link().send_message(AppMsg::LoadComments);
+                            ctx.link().send_message(AppMsg::LoadShareLinks);
+                            ctx.link().send_message(AppMsg::AcquireLock);
                         }
                     },
                     Err(e) => {
@@ -108,15 +348,19 @@ impl Component for App {
             },
             AppMsg::SelectNote(note) => {
                 self.selected_note = Some(note);
+                ctx.link().send_message(AppMsg::LoadComments);
+                ctx.link().send_message(AppMsg::LoadShareLinks);
+                ctx.link().send_message(AppMsg::LoadPreview);
+                ctx.link().send_message(AppMsg::AcquireLock);
                 true
             },
             AppMsg::CreateNote => {
-                let user_id = "current_user".to_string(); // In a real app, this would come from auth
-                let new_note = Note::new(
+                let mut new_note = Note::new(
                     "Untitled Note".to_string(),
                     "".to_string(),
-                    user_id,
+                    self.user(),
                 );
+                new_note.workspace_id = self.workspace_id();
                 
                 self.selected_note = Some(new_note.clone());
                 
@@ -144,9 +388,21 @@ impl Component for App {
             AppMsg::SaveNote(title, content) => {
                 if let Some(mut note) = self.selected_note.clone() {
                     note.update(title, content);
+                    if let Err(e) = note.validate() {
+                        self.error = Some(e.to_string());
+                        return true;
+                    }
+                    
+                    let sealed = match self.seal_note(&note) {
+                        Ok(sealed) => sealed,
+                        Err(e) => {
+                            self.error = Some(e);
+                            return true;
+                        }
+                    };
                     
                     // Always save locally first
-                    match note_service::save_note_locally(&note) {
+                    match note_service::save_note_locally(&sealed) {
                         Ok(_) => {
                             // Update in notes list
                             if let Some(idx) = self.notes.iter().position(|n| {
@@ -170,7 +426,7 @@ impl Component for App {
                     // If online, also save to API
                     if self.is_online {
                         let link = ctx.link().clone();
-                        let note_clone = note.clone();
+                        let note_clone = sealed;
                         wasm_bindgen_futures::spawn_local(async move {
                             let result = if note_clone.id.is_some() {
                                 note_service::update_note(&note_clone).await
@@ -185,7 +441,7 @@ impl Component for App {
                 true
             },
             AppMsg::NoteSaved(result) => {
-                match result {
+                match result.and_then(|note| self.open_note(note)) {
                     Ok(note) => {
                         // Update in notes list with the server version
                         if let Some(idx) = self.notes.iter().position(|n| {
@@ -200,6 +456,8 @@ impl Component for App {
                         }
                         
                         self.selected_note = Some(note);
+                        // The preview shows the saved version, which just changed
+                        ctx.link().send_message(AppMsg::LoadPreview);
                     },
                     Err(e) => {
                         self.error = Some(e);
@@ -236,6 +494,10 @@ impl Component for App {
                 true
             },
             AppMsg::CheckOnline => {
+                // Sessions run out; sign in again rather than failing every request
+                if self.session.is_some() && note_service::current_session().is_none() {
+                    ctx.link().send_message(AppMsg::SignOut);
+                }
                 let is_online = note_service::check_online_status();
                 ctx.link().send_message(AppMsg::OnlineStatusChanged(is_online));
                 false
@@ -247,8 +509,569 @@ impl Component for App {
                 // If we just came online, try to sync
                 if is_online && was_offline {
                     ctx.link().send_message(AppMsg::SyncNotes);
+                    ctx.link().send_message(AppMsg::AcquireLock);
+                }
+                
+                true
+            },
+            AppMsg::ToggleTaskView => {
+                self.showing_tasks = !self.showing_tasks;
+                if self.showing_tasks {
+                    self.showing_keys = false;
+                    ctx.link().send_message(AppMsg::LoadTasks);
+                }
+                true
+            },
+            AppMsg::LoadTasks => {
+                let link = ctx.link().clone();
+                let status = self.task_filter.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::get_tasks(&status).await;
+                    link.send_message(AppMsg::TasksLoaded(result));
+                });
+                false
+            },
+            AppMsg::TasksLoaded(result) => {
+                match result {
+                    Ok(tasks) => self.tasks = tasks,
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::SetTaskFilter(filter) => {
+                self.task_filter = filter;
+                ctx.link().send_message(AppMsg::LoadTasks);
+                false
+            },
+            AppMsg::ToggleTask(task) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let done = !task.done;
+                    let result = note_service::set_task_done(&task, done).await;
+                    link.send_message(AppMsg::TaskToggled(result));
+                });
+                false
+            },
+            AppMsg::TaskToggled(result) => {
+                match result {
+                    Ok(_) => {
+                        // The note content was rewritten on the server
+                        ctx.link().send_message(AppMsg::LoadTasks);
+                        ctx.link().send_message(AppMsg::LoadNotes);
+                    },
+                    Err(e) => {
+                        self.error = Some(e);
+                    }
+                }
+                true
+            },
+            AppMsg::OpenNote(note_id) => {
+                if let Some(note) = self.notes.iter().find(|n| n.id.as_deref() == Some(note_id.as_str())) {
+                    self.selected_note = Some(note.clone());
+                    self.showing_tasks = false;
+                    ctx.link().send_message(AppMsg::LoadComments);
+                    ctx.link().send_message(AppMsg::LoadShareLinks);
+                    ctx.link().send_message(AppMsg::LoadPreview);
+                    ctx.link().send_message(AppMsg::AcquireLock);
+                }
+                true
+            },
+            AppMsg::Unlock(passphrase) => {
+                let mut key_ring = KeyRing::new(passphrase);
+                if !self.locked_notes.is_empty()
+                    && self.locked_notes.iter().all(|note| key_ring.decrypt(note).is_err())
+                {
+                    self.error = Some("Wrong passphrase".to_string());
+                    return true;
                 }
                 
+                self.key_ring = Some(key_ring);
+                let locked = std::mem::take(&mut self.locked_notes);
+                let opened = self.open_notes(locked);
+                self.notes.extend(opened);
+                sort_notes(&mut self.notes);
+                true
+            },
+            AppMsg::Lock => {
+                self.key_ring = None;
+                if self.selected_note.as_ref().is_some_and(Note::is_encrypted) {
+                    self.selected_note = None;
+                }
+                self.notes.retain(|note| !note.is_encrypted());
+                ctx.link().send_message(AppMsg::LoadNotes);
+                true
+            },
+            AppMsg::ToggleEncryption(enabled, title, content) => {
+                if let Some(note) = self.selected_note.as_mut() {
+                    if enabled {
+                        match self.key_ring.as_mut().map(|key_ring| key_ring.parameters()) {
+                            Some(Ok(parameters)) => note.encryption = Some(parameters),
+                            Some(Err(e)) => {
+                                self.error = Some(e);
+                                return true;
+                            },
+                            None => {
+                                self.error = Some("Unlock with a passphrase before encrypting notes".to_string());
+                                return true;
+                            }
+                        }
+                    } else {
+                        note.encryption = None;
+                    }
+                    ctx.link().send_message(AppMsg::SaveNote(title, content));
+                }
+                true
+            },
+            AppMsg::LoadComments => {
+                self.comments.clear();
+                // Notes that haven't reached the server yet can't have comments
+                if let Some(note_id) = self.selected_note.as_ref().and_then(|note| note.id.clone()) {
+                    if self.is_online {
+                        let link = ctx.link().clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let result = note_service::get_comments(&note_id).await;
+                            link.send_message(AppMsg::CommentsLoaded(result));
+                        });
+                    }
+                }
+                true
+            },
+            AppMsg::CommentsLoaded(result) => {
+                match result {
+                    // Ignore responses for a note that is no longer selected
+                    Ok(comments) => {
+                        let selected_id = self.selected_note.as_ref().and_then(|note| note.id.as_deref());
+                        if comments.iter().all(|comment| Some(comment.note_id.as_str()) == selected_id) {
+                            self.comments = comments;
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::AddComment(comment) => {
+                let Some(note_id) = self.selected_note.as_ref().and_then(|note| note.id.clone()) else {
+                    self.error = Some("Save the note before commenting on it".to_string());
+                    return true;
+                };
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::add_comment(&note_id, &comment).await;
+                    link.send_message(AppMsg::CommentAdded(result));
+                });
+                false
+            },
+            AppMsg::CommentAdded(result) => {
+                match result {
+                    Ok(comment) => self.comments.push(comment),
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::ResolveComment(comment, resolved) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::set_comment_resolved(&comment, resolved).await;
+                    link.send_message(AppMsg::CommentResolved(result));
+                });
+                false
+            },
+            AppMsg::CommentResolved(result) => {
+                match result {
+                    Ok(comment) => {
+                        if let Some(existing) = self.comments.iter_mut().find(|c| c.id == comment.id) {
+                            *existing = comment;
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::SetNoteView(view) => {
+                self.note_view = view;
+                self.view_notes.clear();
+                ctx.link().send_message(AppMsg::LoadView);
+                true
+            },
+            AppMsg::LoadView => {
+                if self.note_view == "all" {
+                    return false;
+                }
+                let view = self.note_view.clone();
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::get_notes_in_view(&view).await;
+                    link.send_message(AppMsg::ViewLoaded(view, result));
+                });
+                false
+            },
+            AppMsg::ViewLoaded(view, result) => {
+                // Ignore responses for a view that is no longer shown
+                if view != self.note_view {
+                    return false;
+                }
+                match result {
+                    // Encrypted notes stay listed even when they can't be decrypted
+                    Ok(notes) => {
+                        self.view_notes = notes.into_iter()
+                            .map(|note| match self.open_note(note.clone()) {
+                                Ok(opened) => opened,
+                                Err(_) => Note { title: "Encrypted note".to_string(), ..note },
+                            })
+                            .collect();
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::BatchNotes(note_ids, action) => {
+                let operations = note_ids.into_iter()
+                    .map(|note_id| BatchOperation { note_id, action: action.clone() })
+                    .collect();
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::batch_notes(operations).await;
+                    link.send_message(AppMsg::BatchCompleted(result));
+                });
+                false
+            },
+            AppMsg::BatchCompleted(result) => {
+                match result {
+                    Ok(report) if report.committed => {
+                        if self.selected_note.as_ref().is_some_and(|selected| {
+                            report.notes.iter().any(|note| note.id == selected.id && note.is_trashed())
+                        }) {
+                            self.selected_note = None;
+                        }
+                        ctx.link().send_message(AppMsg::LoadNotes);
+                        ctx.link().send_message(AppMsg::LoadView);
+                    },
+                    Ok(report) => {
+                        let failed: Vec<String> = report.results.iter()
+                            .filter(|item| item.status != BatchItemStatus::Skipped)
+                            .map(|item| match &item.message {
+                                Some(message) => format!("{}: {}", item.note_id, message),
+                                None => format!("{}: {:?}", item.note_id, item.status),
+                            })
+                            .collect();
+                        self.error = Some(format!("No notes were changed. {}", failed.join("; ")));
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::SetNoteFlags(note, flags) => {
+                let Some(note_id) = note.id.clone() else {
+                    return false;
+                };
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::set_note_flags(&note_id, &flags).await;
+                    link.send_message(AppMsg::NoteFlagsSet(result));
+                });
+                false
+            },
+            AppMsg::NoteFlagsSet(result) => {
+                match result.and_then(|note| self.open_note(note)) {
+                    Ok(note) => {
+                        if let Some(selected) = self.selected_note.as_mut().filter(|selected| selected.id == note.id) {
+                            selected.version = note.version;
+                            selected.pinned = note.pinned;
+                            selected.favorite = note.favorite;
+                            selected.archived = note.archived;
+                        }
+                        self.notes.retain(|existing| existing.id != note.id);
+                        if !note.archived {
+                            self.notes.push(note);
+                            sort_notes(&mut self.notes);
+                        }
+                        ctx.link().send_message(AppMsg::LoadView);
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::ToggleKeyView => {
+                self.showing_keys = !self.showing_keys;
+                if self.showing_keys {
+                    self.showing_tasks = false;
+                    ctx.link().send_message(AppMsg::LoadKeys);
+                } else {
+                    self.new_token = None;
+                }
+                true
+            },
+            AppMsg::LoadKeys => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::get_api_keys().await;
+                    link.send_message(AppMsg::KeysLoaded(result));
+                });
+                false
+            },
+            AppMsg::KeysLoaded(result) => {
+                match result {
+                    Ok(keys) => self.api_keys = keys,
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::CreateKey(key) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::create_api_key(&key).await;
+                    link.send_message(AppMsg::KeyCreated(result));
+                });
+                false
+            },
+            AppMsg::KeyCreated(result) => {
+                match result {
+                    Ok(created) => {
+                        self.new_token = Some(created.token);
+                        self.api_keys.insert(0, created.key);
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::RevokeKey(key) => {
+                let confirmed = gloo::dialogs::confirm(&format!("Revoke \"{}\"? Anything using it will stop working.", key.name));
+                if confirmed {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = note_service::revoke_api_key(&key).await;
+                        link.send_message(AppMsg::KeyRevoked(result));
+                    });
+                }
+                false
+            },
+            AppMsg::KeyRevoked(result) => {
+                match result {
+                    Ok(key) => {
+                        if let Some(existing) = self.api_keys.iter_mut().find(|k| k.id == key.id) {
+                            *existing = key;
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::DismissToken => {
+                self.new_token = None;
+                true
+            },
+            AppMsg::LoadWorkspaces => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::get_workspaces().await;
+                    link.send_message(AppMsg::WorkspacesLoaded(result));
+                });
+                false
+            },
+            AppMsg::WorkspacesLoaded(result) => {
+                match result {
+                    Ok(workspaces) => {
+                        self.workspaces = workspaces;
+                        // Fall back to the personal workspace after being removed from the current one
+                        let known = self.workspaces.iter().any(|workspace| workspace.workspace.id.as_deref() == Some(self.current_workspace.as_str()));
+                        if !self.current_workspace.is_empty() && !known {
+                            ctx.link().send_message(AppMsg::SwitchWorkspace(WorkspaceId::default()));
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::SwitchWorkspace(id) => {
+                note_service::set_current_workspace(&id);
+                self.current_workspace = id;
+                self.selected_note = None;
+                self.comments.clear();
+                self.share_links.clear();
+                self.new_share_url = None;
+                self.notes.clear();
+                // Lets go of the lock on the note that was open
+                ctx.link().send_message(AppMsg::AcquireLock);
+                self.view_notes.clear();
+                ctx.link().send_message(AppMsg::LoadNotes);
+                ctx.link().send_message(AppMsg::LoadView);
+                if self.showing_tasks {
+                    ctx.link().send_message(AppMsg::LoadTasks);
+                }
+                true
+            },
+            AppMsg::CreateWorkspace(name) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::create_workspace(&name).await;
+                    link.send_message(AppMsg::WorkspaceCreated(result));
+                });
+                false
+            },
+            AppMsg::TogglePreview => {
+                self.previewing = !self.previewing;
+                ctx.link().send_message(AppMsg::LoadPreview);
+                true
+            },
+            AppMsg::LoadPreview => {
+                self.preview_html = None;
+                if !self.previewing || !self.is_online {
+                    return true;
+                }
+                // Encrypted notes never reach the server in the clear, so it can't render them
+                let note_id = self.selected_note.as_ref()
+                    .filter(|note| !note.is_encrypted())
+                    .and_then(|note| note.id.clone());
+                if let Some(note_id) = note_id {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = note_service::get_note_html(&note_id).await;
+                        link.send_message(AppMsg::PreviewLoaded(note_id, result));
+                    });
+                }
+                true
+            },
+            AppMsg::PreviewLoaded(note_id, result) => {
+                // Ignore previews of notes that are no longer selected
+                if self.selected_note.as_ref().and_then(|note| note.id.as_deref()) != Some(note_id.as_str()) {
+                    return false;
+                }
+                match result {
+                    Ok(html) => self.preview_html = Some(html),
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::WorkspaceCreated(result) => {
+                match result {
+                    Ok(workspace) => {
+                        let id = workspace.workspace.id.clone().unwrap_or_default();
+                        self.workspaces.push(workspace);
+                        ctx.link().send_message(AppMsg::SwitchWorkspace(id));
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::LoadShareLinks => {
+                self.share_links.clear();
+                self.new_share_url = None;
+                // Notes that haven't reached the server yet can't be shared
+                if let Some(note_id) = self.selected_note.as_ref().and_then(|note| note.id.clone()) {
+                    if self.is_online {
+                        let link = ctx.link().clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let result = note_service::get_share_links(&note_id).await;
+                            link.send_message(AppMsg::ShareLinksLoaded(note_id, result));
+                        });
+                    }
+                }
+                true
+            },
+            AppMsg::ShareLinksLoaded(note_id, result) => {
+                // Ignore links of notes that are no longer selected
+                if self.selected_note.as_ref().and_then(|note| note.id.as_deref()) != Some(note_id.as_str()) {
+                    return false;
+                }
+                match result {
+                    Ok(links) => self.share_links = links,
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::CreateShareLink(new_link) => {
+                let Some(note_id) = self.selected_note.as_ref().and_then(|note| note.id.clone()) else {
+                    self.error = Some("Save the note before sharing it".to_string());
+                    return true;
+                };
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::create_share_link(&note_id, &new_link).await;
+                    link.send_message(AppMsg::ShareLinkCreated(result));
+                });
+                false
+            },
+            AppMsg::ShareLinkCreated(result) => {
+                match result {
+                    Ok(created) => {
+                        let selected_id = self.selected_note.as_ref().and_then(|note| note.id.as_deref());
+                        if selected_id == Some(created.link.note_id.as_str()) {
+                            self.new_share_url = Some(note_service::share_url(&created.path));
+                            self.share_links.insert(0, created.link);
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::RevokeShareLink(share_link) => {
+                let confirmed = gloo::dialogs::confirm("Revoke this link? Anyone who has it will no longer be able to read the note.");
+                if confirmed {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = note_service::revoke_share_link(&share_link).await;
+                        link.send_message(AppMsg::ShareLinkRevoked(result));
+                    });
+                }
+                false
+            },
+            AppMsg::ShareLinkRevoked(result) => {
+                match result {
+                    Ok(share_link) => {
+                        if let Some(existing) = self.share_links.iter_mut().find(|l| l.id == share_link.id) {
+                            *existing = share_link;
+                        }
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+                true
+            },
+            AppMsg::DismissShareUrl => {
+                self.new_share_url = None;
+                true
+            },
+            AppMsg::AcquireLock => {
+                let note_id = self.selected_note.as_ref().and_then(|note| note.id.clone());
+                // Let go of the lock on a note that is no longer selected
+                if let Some(lock) = self.note_lock.take() {
+                    if Some(&lock.note_id) != note_id.as_ref() {
+                        if lock.locked_by == self.user() && self.is_online {
+                            wasm_bindgen_futures::spawn_local(async move {
+                                if let Err(e) = note_service::release_note_lock(&lock.note_id).await {
+                                    console::log_1(&e.into());
+                                }
+                            });
+                        }
+                    } else {
+                        self.note_lock = Some(lock);
+                    }
+                }
+                // Notes that haven't reached the server yet can't be locked; offline
+                // edits are reconciled by version when they sync
+                let Some(note_id) = note_id.filter(|_| self.is_online) else {
+                    self.note_lock = None;
+                    return true;
+                };
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = note_service::acquire_note_lock(&note_id).await;
+                    link.send_message(AppMsg::LockAcquired(note_id, result));
+                });
+                true
+            },
+            AppMsg::LockAcquired(note_id, result) => {
+                // Ignore locks of notes that are no longer selected
+                if self.selected_note.as_ref().and_then(|note| note.id.as_deref()) != Some(note_id.as_str()) {
+                    return false;
+                }
+                match result {
+                    Ok(lock) => self.note_lock = Some(lock),
+                    Err(e) => {
+                        // Viewers can't lock notes; they just read them
+                        console::log_1(&e.into());
+                        self.note_lock = None;
+                    }
+                }
                 true
             },
             AppMsg::Error(error) => {
@@ -263,27 +1086,146 @@ impl Component for App {
         let on_note_select = ctx.link().callback(AppMsg::SelectNote);
         let on_note_create = ctx.link().callback(|_| AppMsg::CreateNote);
         let on_note_save = ctx.link().callback(|(title, content)| AppMsg::SaveNote(title, content));
+        let on_toggle_preview = ctx.link().callback(|_| AppMsg::TogglePreview);
+        let on_open_linked_note = ctx.link().callback(AppMsg::OpenNote);
         let on_sync = ctx.link().callback(|_| AppMsg::SyncNotes);
+        let on_toggle_tasks = ctx.link().callback(|_| AppMsg::ToggleTaskView);
+        let on_toggle_keys = ctx.link().callback(|_| AppMsg::ToggleKeyView);
+        let on_create_key = ctx.link().callback(AppMsg::CreateKey);
+        let on_revoke_key = ctx.link().callback(AppMsg::RevokeKey);
+        let on_dismiss_token = ctx.link().callback(|_| AppMsg::DismissToken);
+        let on_task_filter = ctx.link().callback(AppMsg::SetTaskFilter);
+        let on_task_toggle = ctx.link().callback(AppMsg::ToggleTask);
+        let on_open_note = ctx.link().callback(AppMsg::OpenNote);
+        let on_unlock = ctx.link().batch_callback(|_| {
+            gloo::dialogs::prompt("Passphrase for encrypted notes", None)
+                .filter(|passphrase| !passphrase.is_empty())
+                .map(AppMsg::Unlock)
+        });
+        let on_lock = ctx.link().callback(|_| AppMsg::Lock);
+        let on_switch_workspace = ctx.link().callback(AppMsg::SwitchWorkspace);
+        let on_create_workspace = ctx.link().batch_callback(|_| {
+            gloo::dialogs::prompt("Name of the new workspace", None)
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .map(AppMsg::CreateWorkspace)
+        });
+        let on_toggle_encryption = ctx.link().callback(|(enabled, title, content)| AppMsg::ToggleEncryption(enabled, title, content));
+        let on_view_change = ctx.link().callback(AppMsg::SetNoteView);
+        let on_set_flags = ctx.link().callback(|(note, flags)| AppMsg::SetNoteFlags(note, flags));
+        let on_batch = ctx.link().callback(|(note_ids, action)| AppMsg::BatchNotes(note_ids, action));
+        let on_add_comment = ctx.link().callback(AppMsg::AddComment);
+        let on_resolve_comment = ctx.link().callback(|(comment, resolved)| AppMsg::ResolveComment(comment, resolved));
+        let on_create_share_link = ctx.link().callback(AppMsg::CreateShareLink);
+        let on_revoke_share_link = ctx.link().callback(AppMsg::RevokeShareLink);
+        let on_dismiss_share_url = ctx.link().callback(|_| AppMsg::DismissShareUrl);
+        let on_sign_out = ctx.link().callback(|_| AppMsg::SignOut);
+
+        if self.session.is_none() {
+            let on_sign_in = ctx.link().callback(AppMsg::SignIn);
+            let on_sign_up = ctx.link().callback(AppMsg::SignUp);
+            return html! {
+                <div class="app">
+                    <SignInForm on_sign_in={on_sign_in} on_sign_up={on_sign_up} is_submitting={self.signing_in} />
+                    if let Some(error) = &self.error {
+                        <div class="error-notification">
+                            <p>{ error }</p>
+                            <button onclick={ctx.link().callback(|_| AppMsg::Error(String::new()))}>
+                                { "×" }
+                            </button>
+                        </div>
+                    }
+                </div>
+            };
+        }
 
         html! {
             <div class="app">
                 <Toolbar 
                     on_sync={on_sync}
+                    on_toggle_tasks={on_toggle_tasks}
+                    showing_tasks={self.showing_tasks}
+                    on_toggle_keys={on_toggle_keys}
+                    showing_keys={self.showing_keys}
+                    is_unlocked={self.key_ring.is_some()}
+                    on_unlock={on_unlock}
+                    on_lock={on_lock}
+                    workspaces={self.workspaces.clone()}
+                    current_workspace={self.workspace_id().unwrap_or_default()}
+                    on_switch_workspace={on_switch_workspace}
+                    on_create_workspace={on_create_workspace}
                     is_syncing={self.is_syncing}
                     is_online={self.is_online}
+                    user={self.user()}
+                    on_sign_out={on_sign_out}
                 />
                 <div class="main-content">
                     <NoteList 
-                        notes={self.notes.clone()}
+                        notes={if self.note_view == "all" { self.notes.clone() } else { self.view_notes.clone() }}
+                        locked_count={self.locked_notes.len()}
                         on_select={on_note_select}
                         on_create={on_note_create}
+                        view={self.note_view.clone()}
+                        on_view_change={on_view_change}
+                        on_batch={on_batch}
+                        on_set_flags={on_set_flags}
                     />
                     <div class="content-area">
                         {
-                            if self.is_loading {
+                            if self.showing_tasks {
+                                html! {
+                                    <TaskList
+                                        tasks={self.tasks.clone()}
+                                        filter={self.task_filter.clone()}
+                                        on_filter_change={on_task_filter}
+                                        on_toggle={on_task_toggle}
+                                        on_open_note={on_open_note}
+                                    />
+                                }
+                            } else if self.showing_keys {
+                                html! {
+                                    <ApiKeyPanel
+                                        keys={self.api_keys.clone()}
+                                        new_token={self.new_token.clone()}
+                                        on_create={on_create_key}
+                                        on_revoke={on_revoke_key}
+                                        on_dismiss_token={on_dismiss_token}
+                                    />
+                                }
+                            } else if self.is_loading {
                                 html! { <div class="loading">{ "Loading..." }</div> }
                             } else if let Some(note) = &self.selected_note {
-                                html! { <NoteEditor note={note.clone()} on_save={on_note_save} /> }
+                                html! {
+                                    <div class="note-workspace">
+                                        <NoteEditor
+                                            note={note.clone()}
+                                            on_save={on_note_save}
+                                            on_toggle_encryption={on_toggle_encryption}
+                                            previewing={self.previewing}
+                                            preview_html={self.preview_html.clone()}
+                                            on_toggle_preview={on_toggle_preview}
+                                            on_open_note={on_open_linked_note}
+                                            locked_by={self.note_lock.as_ref()
+                                                .map(|lock| lock.locked_by.clone())
+                                                .filter(|holder| *holder != self.user())}
+                                        />
+                                        <div class="note-sidebar">
+                                            <CommentPanel
+                                                comments={self.comments.clone()}
+                                                encrypted={note.is_encrypted()}
+                                                on_add={on_add_comment}
+                                                on_resolve={on_resolve_comment}
+                                            />
+                                            <SharePanel
+                                                links={self.share_links.clone()}
+                                                new_url={self.new_share_url.clone()}
+                                                on_create={on_create_share_link}
+                                                on_revoke={on_revoke_share_link}
+                                                on_dismiss_url={on_dismiss_share_url}
+                                            />
+                                        </div>
+                                    </div>
+                                }
                             } else {
                                 html! { 
                                     <div class="empty-state">
@@ -0,0 +1,170 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use chrono::{DateTime, Duration, Utc};
+use cosmiqnotz_core::{ApiKey, ApiKeyScope, NewApiKey};
+
+#[derive(Properties, PartialEq)]
+pub struct ApiKeyPanelProps {
+    pub keys: Vec<ApiKey>,
+    // The key just created; it can't be shown again once dismissed
+    pub new_token: Option<String>,
+    pub on_create: Callback<NewApiKey>,
+    pub on_revoke: Callback<ApiKey>,
+    pub on_dismiss_token: Callback<()>,
+}
+
+fn format_time(at: Option<DateTime<Utc>>, none: &str) -> String {
+    at.map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| none.to_string())
+}
+
+fn key_status(key: &ApiKey) -> &'static str {
+    if key.revoked_at.is_some() {
+        "Revoked"
+    } else if !key.is_active(Utc::now()) {
+        "Expired"
+    } else {
+        "Active"
+    }
+}
+
+#[function_component(ApiKeyPanel)]
+pub fn api_key_panel(props: &ApiKeyPanelProps) -> Html {
+    let name = use_state(String::new);
+    let scope = use_state(|| ApiKeyScope::Read);
+    let expires_in_days = use_state(|| "90".to_string());
+
+    let on_name = {
+        let name = name.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            name.set(input.value());
+        })
+    };
+
+    let on_scope = {
+        let scope = scope.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            scope.set(match select.value().as_str() {
+                "admin" => ApiKeyScope::Admin,
+                "write" => ApiKeyScope::Write,
+                _ => ApiKeyScope::Read,
+            });
+        })
+    };
+
+    let on_expiry = {
+        let expires_in_days = expires_in_days.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            expires_in_days.set(select.value());
+        })
+    };
+
+    let on_submit = {
+        let name = name.clone();
+        let scope = scope.clone();
+        let expires_in_days = expires_in_days.clone();
+        let on_create = props.on_create.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let key = NewApiKey {
+                name: (*name).clone(),
+                scopes: vec![*scope],
+                expires_at: expires_in_days.parse::<i64>()
+                    .ok()
+                    .map(|days| Utc::now() + Duration::days(days)),
+            };
+            // The same rules the API checks
+            if key.validate(Utc::now()).is_err() {
+                return;
+            }
+            on_create.emit(key);
+            name.set(String::new());
+        })
+    };
+
+    let on_dismiss = {
+        let on_dismiss_token = props.on_dismiss_token.clone();
+        Callback::from(move |_| {
+            on_dismiss_token.emit(());
+        })
+    };
+
+    html! {
+        <div class="api-key-panel">
+            <div class="api-key-header">
+                <h2>{ "API Keys" }</h2>
+            </div>
+            if let Some(token) = &props.new_token {
+                <div class="api-key-token">
+                    <p>{ "Copy this key now. It won't be shown again." }</p>
+                    <code>{ token }</code>
+                    <button class="api-key-button" onclick={on_dismiss}>{ "Done" }</button>
+                </div>
+            }
+            <form class="api-key-form" onsubmit={on_submit}>
+                <input
+                    type="text"
+                    placeholder="Key name"
+                    value={(*name).clone()}
+                    oninput={on_name}
+                />
+                <select onchange={on_scope}>
+                    <option value="read" selected={*scope == ApiKeyScope::Read}>{ "Read" }</option>
+                    <option value="write" selected={*scope == ApiKeyScope::Write}>{ "Read and write" }</option>
+                    <option value="admin" selected={*scope == ApiKeyScope::Admin}>{ "Admin" }</option>
+                </select>
+                <select onchange={on_expiry}>
+                    <option value="30" selected={*expires_in_days == "30"}>{ "30 days" }</option>
+                    <option value="90" selected={*expires_in_days == "90"}>{ "90 days" }</option>
+                    <option value="365" selected={*expires_in_days == "365"}>{ "1 year" }</option>
+                    <option value="never" selected={*expires_in_days == "never"}>{ "Never expires" }</option>
+                </select>
+                <button type="submit" class="api-key-button">{ "Create key" }</button>
+            </form>
+            <div class="api-key-items">
+                if props.keys.is_empty() {
+                    <div class="empty-list">
+                        <p>{ "No API keys. Create one for scripts and integrations." }</p>
+                    </div>
+                } else {
+                    { for props.keys.iter().map(|key| {
+                        let status = key_status(key);
+                        let on_revoke = {
+                            let key = key.clone();
+                            let on_revoke = props.on_revoke.clone();
+                            Callback::from(move |_| {
+                                on_revoke.emit(key.clone());
+                            })
+                        };
+                        let scopes: Vec<&str> = key.scopes.iter().map(ApiKeyScope::label).collect();
+
+                        html! {
+                            <div class={classes!("api-key-item", (status != "Active").then(|| "inactive"))}>
+                                <div class="api-key-name">
+                                    <strong>{ &key.name }</strong>
+                                    <code>{ format!("{}…", key.prefix) }</code>
+                                    <span class="api-key-status">{ status }</span>
+                                </div>
+                                <div class="api-key-meta">
+                                    { format!(
+                                        "Scopes: {} · Created {} · Expires {} · Last used {}",
+                                        scopes.join(", "),
+                                        format_time(Some(key.created_at), ""),
+                                        format_time(key.expires_at, "never"),
+                                        format_time(key.last_used_at, "never"),
+                                    ) }
+                                </div>
+                                if key.revoked_at.is_none() {
+                                    <button class="api-key-button" onclick={on_revoke}>{ "Revoke" }</button>
+                                }
+                            </div>
+                        }
+                    }) }
+                }
+            </div>
+        </div>
+    }
+}
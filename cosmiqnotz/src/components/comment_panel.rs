@@ -0,0 +1,209 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use wasm_bindgen::JsCast;
+use cosmiqnotz_core::{Comment, CommentAnchor, CommentId, NewComment};
+
+#[derive(Properties, PartialEq)]
+pub struct CommentPanelProps {
+    pub comments: Vec<Comment>,
+    // Comments on encrypted notes aren't anchored, since the quote would reach the
+    // server in the clear
+    pub encrypted: bool,
+    pub on_add: Callback<NewComment>,
+    pub on_resolve: Callback<(Comment, bool)>,
+}
+
+// The text currently selected in the note editor, if any. Offsets are the
+// textarea's own (UTF-16) selection offsets.
+fn editor_selection() -> Option<CommentAnchor> {
+    let textarea = web_sys::window()?
+        .document()?
+        .query_selector(".content-textarea")
+        .ok()??
+        .dyn_into::<HtmlTextAreaElement>()
+        .ok()?;
+
+    let start = textarea.selection_start().ok()?? as usize;
+    let end = textarea.selection_end().ok()?? as usize;
+    if start >= end {
+        return None;
+    }
+
+    let units: Vec<u16> = textarea.value().encode_utf16().collect();
+    let quote = String::from_utf16_lossy(units.get(start..end)?);
+    Some(CommentAnchor { start, end, quote })
+}
+
+#[derive(Properties, PartialEq)]
+struct ReplyFormProps {
+    parent_id: CommentId,
+    on_add: Callback<NewComment>,
+}
+
+#[function_component(ReplyForm)]
+fn reply_form(props: &ReplyFormProps) -> Html {
+    let body = use_state(String::new);
+
+    let on_input = {
+        let body = body.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            body.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let body = body.clone();
+        let parent_id = props.parent_id.clone();
+        let on_add = props.on_add.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let comment = NewComment {
+                body: (*body).clone(),
+                parent_id: Some(parent_id.clone()),
+                anchor: None,
+            };
+            if comment.validate().is_err() {
+                return;
+            }
+            on_add.emit(comment);
+            body.set(String::new());
+        })
+    };
+
+    html! {
+        <form class="comment-reply" onsubmit={on_submit}>
+            <input
+                type="text"
+                placeholder="Reply..."
+                value={(*body).clone()}
+                oninput={on_input}
+            />
+            <button type="submit">{ "Reply" }</button>
+        </form>
+    }
+}
+
+#[function_component(CommentPanel)]
+pub fn comment_panel(props: &CommentPanelProps) -> Html {
+    let body = use_state(String::new);
+    let show_resolved = use_state(|| false);
+
+    let on_input = {
+        let body = body.clone();
+        Callback::from(move |e: InputEvent| {
+            let textarea: HtmlTextAreaElement = e.target_unchecked_into();
+            body.set(textarea.value());
+        })
+    };
+
+    // New threads are anchored to whatever is selected in the editor
+    let on_submit = {
+        let body = body.clone();
+        let encrypted = props.encrypted;
+        let on_add = props.on_add.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let comment = NewComment {
+                body: (*body).clone(),
+                parent_id: None,
+                anchor: if encrypted { None } else { editor_selection() },
+            };
+            if comment.validate().is_err() {
+                return;
+            }
+            on_add.emit(comment);
+            body.set(String::new());
+        })
+    };
+
+    let on_toggle_resolved = {
+        let show_resolved = show_resolved.clone();
+        Callback::from(move |_| show_resolved.set(!*show_resolved))
+    };
+
+    let threads: Vec<&Comment> = props.comments.iter()
+        .filter(|comment| comment.parent_id.is_none())
+        .filter(|comment| *show_resolved || !comment.resolved)
+        .collect();
+    let resolved_count = props.comments.iter()
+        .filter(|comment| comment.parent_id.is_none() && comment.resolved)
+        .count();
+
+    html! {
+        <div class="comment-panel">
+            <div class="comment-panel-header">
+                <h3>{ "Comments" }</h3>
+                if resolved_count > 0 {
+                    <button class="comment-show-resolved" onclick={on_toggle_resolved}>
+                        { if *show_resolved { "Hide resolved".to_string() } else { format!("Show resolved ({})", resolved_count) } }
+                    </button>
+                }
+            </div>
+            <form class="comment-new" onsubmit={on_submit}>
+                <textarea
+                    placeholder={if props.encrypted { "Comment on the note..." } else { "Comment on the note or the selected text..." }}
+                    value={(*body).clone()}
+                    oninput={on_input}
+                />
+                <button type="submit">{ "Comment" }</button>
+            </form>
+            <div class="comment-threads">
+                if threads.is_empty() {
+                    <div class="empty-list">
+                        <p>{ "No comments yet." }</p>
+                    </div>
+                } else {
+                    { for threads.into_iter().map(|thread| {
+                        let replies = props.comments.iter()
+                            .filter(|comment| comment.parent_id.is_some() && comment.parent_id == thread.id);
+
+                        let on_resolve = {
+                            let thread = thread.clone();
+                            let on_resolve = props.on_resolve.clone();
+                            Callback::from(move |_| {
+                                on_resolve.emit((thread.clone(), !thread.resolved));
+                            })
+                        };
+
+                        html! {
+                            <div class={classes!("comment-thread", thread.resolved.then(|| "resolved"))}>
+                                if let Some(anchor) = &thread.anchor {
+                                    <blockquote class="comment-quote">{ &anchor.quote }</blockquote>
+                                }
+                                <div class="comment">
+                                    <span class="comment-author">{ &thread.author }</span>
+                                    <span class="comment-date">{ thread.created_at.format("%Y-%m-%d %H:%M").to_string() }</span>
+                                    <p class="comment-body">{ &thread.body }</p>
+                                </div>
+                                { for replies.map(|reply| html! {
+                                    <div class="comment reply">
+                                        <span class="comment-author">{ &reply.author }</span>
+                                        <span class="comment-date">{ reply.created_at.format("%Y-%m-%d %H:%M").to_string() }</span>
+                                        <p class="comment-body">{ &reply.body }</p>
+                                    </div>
+                                }) }
+                                if let Some(resolved_by) = &thread.resolved_by {
+                                    <p class="comment-resolved-by">{ format!("Resolved by {}", resolved_by) }</p>
+                                }
+                                <div class="comment-actions">
+                                    if !thread.resolved {
+                                        if let Some(parent_id) = &thread.id {
+                                            <ReplyForm
+                                                parent_id={parent_id.clone()}
+                                                on_add={props.on_add.clone()}
+                                            />
+                                        }
+                                    }
+                                    <button class="comment-resolve" onclick={on_resolve}>
+                                        { if thread.resolved { "Reopen" } else { "Resolve" } }
+                                    </button>
+                                </div>
+                            </div>
+                        }
+                    }) }
+                }
+            </div>
+        </div>
+    }
+}
@@ -1,7 +1,17 @@
+pub mod api_key_panel;
+pub mod comment_panel;
 pub mod note_editor;
 pub mod note_list;
+pub mod share_panel;
+pub mod sign_in_form;
+pub mod task_list;
 pub mod toolbar;
 
+pub use api_key_panel::ApiKeyPanel;
+pub use comment_panel::CommentPanel;
 pub use note_editor::NoteEditor;
 pub use note_list::NoteList;
+pub use share_panel::SharePanel;
+pub use sign_in_form::SignInForm;
+pub use task_list::TaskList;
 pub use toolbar::Toolbar;
\ No newline at end of file
@@ -0,0 +1,189 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use wasm_bindgen::JsCast;
+use cosmiqnotz_core::{Note, NoteId};
+
+#[derive(Properties, PartialEq)]
+pub struct NoteEditorProps {
+    pub note: Note,
+    pub on_save: Callback<(String, String)>,
+    pub on_toggle_encryption: Callback<(bool, String, String)>,
+    pub previewing: bool,
+    pub preview_html: Option<String>,
+    pub on_toggle_preview: Callback<()>,
+    // Called with the id of a note a `[[link]]` in the preview points at
+    pub on_open_note: Callback<NoteId>,
+    // Someone else holds the note's edit lock; the editor is read-only until it's free
+    #[prop_or_default]
+    pub locked_by: Option<String>,
+}
+
+// The server sanitizes rendered notes, so their HTML can be inserted as is
+fn rendered_html(html: &str) -> Html {
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("div").ok());
+    match element {
+        Some(element) => {
+            element.set_inner_html(html);
+            Html::VRef(element.into())
+        },
+        None => html! {},
+    }
+}
+
+#[function_component(NoteEditor)]
+pub fn note_editor(props: &NoteEditorProps) -> Html {
+    let title = use_state(|| props.note.title.clone());
+    let content = use_state(|| props.note.content.clone());
+    let is_dirty = use_state(|| false);
+
+    // Update states when note changes
+    {
+        let title = title.clone();
+        let content = content.clone();
+        let is_dirty = is_dirty.clone();
+        
+        use_effect_with_deps(move |note| {
+            title.set(note.title.clone());
+            content.set(note.content.clone());
+            is_dirty.set(false);
+            || ()
+        }, props.note.clone());
+    }
+
+    let on_title_change = {
+        let title = title.clone();
+        let is_dirty = is_dirty.clone();
+        
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            title.set(input.value());
+            is_dirty.set(true);
+        })
+    };
+
+    let on_content_change = {
+        let content = content.clone();
+        let is_dirty = is_dirty.clone();
+        
+        Callback::from(move |e: Event| {
+            let textarea: HtmlTextAreaElement = e.target_unchecked_into();
+            content.set(textarea.value());
+            is_dirty.set(true);
+        })
+    };
+
+    let on_save_click = {
+        let title = title.clone();
+        let content = content.clone();
+        let on_save = props.on_save.clone();
+        let is_dirty = is_dirty.clone();
+        
+        Callback::from(move |_| {
+            on_save.emit(((*title).clone(), (*content).clone()));
+            is_dirty.set(false);
+        })
+    };
+
+    let on_encryption_change = {
+        let title = title.clone();
+        let content = content.clone();
+        let on_toggle_encryption = props.on_toggle_encryption.clone();
+        let is_dirty = is_dirty.clone();
+        
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            on_toggle_encryption.emit((input.checked(), (*title).clone(), (*content).clone()));
+            is_dirty.set(false);
+        })
+    };
+
+    let on_preview_click = {
+        let on_toggle_preview = props.on_toggle_preview.clone();
+        Callback::from(move |_| {
+            on_toggle_preview.emit(());
+        })
+    };
+
+    let on_preview_link = {
+        let on_open_note = props.on_open_note.clone();
+        Callback::from(move |e: MouseEvent| {
+            let Some(target) = e.target().and_then(|target| target.dyn_into::<web_sys::Element>().ok()) else {
+                return;
+            };
+            if let Ok(Some(link)) = target.closest("a.wiki-link") {
+                if let Some(note_id) = link.get_attribute("data-note-id") {
+                    e.prevent_default();
+                    on_open_note.emit(note_id.into());
+                }
+            }
+        })
+    };
+
+    let read_only = props.locked_by.is_some();
+    let can_save = *is_dirty && !read_only;
+
+    html! {
+        <div class="note-editor">
+            if let Some(holder) = &props.locked_by {
+                <div class="lock-banner">{ format!("Being edited by {}. You can read the note until they're done.", holder) }</div>
+            }
+            <div class="editor-header">
+                <input 
+                    type="text"
+                    class="title-input"
+                    placeholder="Note title"
+                    value={(*title).clone()}
+                    readonly={read_only}
+                    onchange={on_title_change}
+                />
+                <label class="encrypt-toggle">
+                    <input
+                        type="checkbox"
+                        checked={props.note.is_encrypted()}
+                        disabled={read_only}
+                        onchange={on_encryption_change}
+                    />
+                    { "Encrypted" }
+                </label>
+                <button
+                    onclick={on_preview_click}
+                    class={classes!("preview-button", props.previewing.then(|| "active"))}
+                >
+                    { if props.previewing { "Edit" } else { "Preview" } }
+                </button>
+                <button 
+                    onclick={on_save_click}
+                    disabled={!can_save}
+                    class={classes!("save-button", (!can_save).then(|| "disabled"))}
+                >
+                    { "Save" }
+                </button>
+            </div>
+            <div class="editor-content">
+                if !props.previewing {
+                    <textarea 
+                        class="content-textarea"
+                        placeholder="Write your note here..."
+                        value={(*content).clone()}
+                        readonly={read_only}
+                        onchange={on_content_change}
+                    />
+                } else if props.note.is_encrypted() {
+                    <div class="preview-message">{ "Preview isn't available for encrypted notes." }</div>
+                } else {
+                    if *is_dirty {
+                        <div class="preview-message">{ "Showing the last saved version." }</div>
+                    }
+                    <div class="note-preview" onclick={on_preview_link}>
+                        { match &props.preview_html {
+                            Some(html) => rendered_html(html),
+                            None => html! { <div class="loading">{ "Loading preview..." }</div> },
+                        } }
+                    </div>
+                }
+            </div>
+        </div>
+    }
+}
\ No newline at end of file
@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use web_sys::HtmlSelectElement;
+use cosmiqnotz_core::{BatchAction, Note, NoteFlags, NoteId};
+
+#[derive(Properties, PartialEq)]
+pub struct NoteListProps {
+    pub notes: Vec<Note>,
+    pub locked_count: usize,
+    pub on_select: Callback<Note>,
+    pub on_create: Callback<()>,
+    // One of "all", "pinned", "favorites", "archived" or "trash"
+    pub view: String,
+    pub on_view_change: Callback<String>,
+    pub on_batch: Callback<(Vec<NoteId>, BatchAction)>,
+    pub on_set_flags: Callback<(Note, NoteFlags)>,
+}
+
+fn view_title(view: &str) -> &'static str {
+    match view {
+        "pinned" => "Pinned",
+        "favorites" => "Favorites",
+        "archived" => "Archive",
+        "trash" => "Trash",
+        _ => "Notes",
+    }
+}
+
+fn empty_message(view: &str) -> &'static str {
+    match view {
+        "pinned" => "No pinned notes.",
+        "favorites" => "No favorites yet. Star a note to find it here.",
+        "archived" => "No archived notes.",
+        "trash" => "The trash is empty.",
+        _ => "No notes yet. Create one to get started!",
+    }
+}
+
+// Ask for the argument of a batch action; `None` if the user cancels
+fn prompt_action(action: &str) -> Option<BatchAction> {
+    match action {
+        "delete" => Some(BatchAction::Delete),
+        "restore" => Some(BatchAction::Restore),
+        "add_tag" => gloo::dialogs::prompt("Tag to add", None)
+            .filter(|tag| !tag.trim().is_empty())
+            .map(|tag| BatchAction::AddTag { tag }),
+        "remove_tag" => gloo::dialogs::prompt("Tag to remove", None)
+            .filter(|tag| !tag.trim().is_empty())
+            .map(|tag| BatchAction::RemoveTag { tag }),
+        "move" => gloo::dialogs::prompt("Move to notebook (leave empty for none)", None)
+            .map(|notebook| BatchAction::Move {
+                notebook: (!notebook.trim().is_empty()).then_some(notebook),
+            }),
+        "share" => gloo::dialogs::prompt("Share with (comma separated, empty to stop sharing)", None)
+            .map(|users| BatchAction::Share {
+                shared_with: users.split(',')
+                    .map(|user| user.trim().to_string())
+                    .filter(|user| !user.is_empty())
+                    .collect(),
+            }),
+        _ => None,
+    }
+}
+
+#[function_component(NoteList)]
+pub fn note_list(props: &NoteListProps) -> Html {
+    let on_create = {
+        let on_create = props.on_create.clone();
+        Callback::from(move |_| {
+            on_create.emit(());
+        })
+    };
+
+    let query = use_state(String::new);
+    let on_search = {
+        let query = query.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            query.set(input.value());
+        })
+    };
+
+    let selected = use_state(HashSet::<NoteId>::new);
+
+    let on_view_change = {
+        let on_view_change = props.on_view_change.clone();
+        let selected = selected.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            selected.set(HashSet::new());
+            on_view_change.emit(select.value());
+        })
+    };
+    let showing_trash = props.view == "trash";
+
+    // Only notes still listed count as selected
+    let selected_ids: Vec<NoteId> = props.notes.iter()
+        .filter_map(|note| note.id.clone())
+        .filter(|id| selected.contains(id))
+        .collect();
+
+    let batch_button = |action: &'static str, label: &'static str| {
+        let on_batch = props.on_batch.clone();
+        let selected = selected.clone();
+        let ids = selected_ids.clone();
+        let onclick = Callback::from(move |_| {
+            if let Some(batch_action) = prompt_action(action) {
+                on_batch.emit((ids.clone(), batch_action));
+                selected.set(HashSet::new());
+            }
+        });
+        html! { <button class="batch-button" onclick={onclick}>{ label }</button> }
+    };
+
+    // Searching happens here rather than on the server so decrypted notes are included
+    let needle = query.to_lowercase();
+    let notes: Vec<&Note> = props.notes.iter()
+        .filter(|note| {
+            needle.is_empty()
+                || note.title.to_lowercase().contains(&needle)
+                || note.content.to_lowercase().contains(&needle)
+        })
+        .collect();
+
+    html! {
+        <div class="note-list">
+            <div class="note-list-header">
+                <h2>{ view_title(&props.view) }</h2>
+                <select class="note-view" onchange={on_view_change}>
+                    <option value="all" selected={props.view == "all"}>{ "All" }</option>
+                    <option value="pinned" selected={props.view == "pinned"}>{ "Pinned" }</option>
+                    <option value="favorites" selected={props.view == "favorites"}>{ "Favorites" }</option>
+                    <option value="archived" selected={props.view == "archived"}>{ "Archive" }</option>
+                    <option value="trash" selected={showing_trash}>{ "Trash" }</option>
+                </select>
+                if !showing_trash {
+                    <button onclick={on_create} class="create-button">{ "New Note" }</button>
+                }
+            </div>
+            <div class="note-list-search">
+                <input
+                    type="search"
+                    class="search-input"
+                    placeholder="Search notes"
+                    value={(*query).clone()}
+                    oninput={on_search}
+                />
+            </div>
+            if props.locked_count > 0 {
+                <div class="locked-notes">
+                    { format!("{} encrypted note(s) locked", props.locked_count) }
+                </div>
+            }
+            if !selected_ids.is_empty() {
+                <div class="batch-bar">
+                    <span class="batch-count">{ format!("{} selected", selected_ids.len()) }</span>
+                    if showing_trash {
+                        { batch_button("restore", "Restore") }
+                    } else {
+                        { batch_button("add_tag", "Tag") }
+                        { batch_button("remove_tag", "Untag") }
+                        { batch_button("move", "Move") }
+                        { batch_button("share", "Share") }
+                        { batch_button("delete", "Delete") }
+                    }
+                </div>
+            }
+            <div class="note-list-items">
+                if props.notes.is_empty() {
+                    <div class="empty-list">
+                        <p>{ empty_message(&props.view) }</p>
+                    </div>
+                } else {
+                    { for notes.into_iter().map(|note| {
+                        let on_click = {
+                            let note = note.clone();
+                            let on_select = props.on_select.clone();
+                            Callback::from(move |_| {
+                                on_select.emit(note.clone());
+                            })
+                        };
+                        
+                        let is_selected = note.id.as_ref().is_some_and(|id| selected.contains(id));
+                        let on_check = {
+                            let id = note.id.clone();
+                            let selected = selected.clone();
+                            Callback::from(move |e: MouseEvent| {
+                                // Selecting shouldn't also open the note
+                                e.stop_propagation();
+                                if let Some(id) = &id {
+                                    let mut ids = (*selected).clone();
+                                    if !ids.remove(id) {
+                                        ids.insert(id.clone());
+                                    }
+                                    selected.set(ids);
+                                }
+                            })
+                        };
+                        
+                        // Flag buttons also stop propagation so they don't open the note
+                        let flag_button = |flags: NoteFlags, active: bool, label: &'static str, title: &'static str| {
+                            let note = note.clone();
+                            let on_set_flags = props.on_set_flags.clone();
+                            let onclick = Callback::from(move |e: MouseEvent| {
+                                e.stop_propagation();
+                                on_set_flags.emit((note.clone(), flags.clone()));
+                            });
+                            html! {
+                                <button class={classes!("flag-button", active.then(|| "active"))} title={title} onclick={onclick}>
+                                    { label }
+                                </button>
+                            }
+                        };
+                        
+                        html! {
+                            <div class={classes!("note-item", is_selected.then(|| "selected"), note.pinned.then(|| "pinned"))} onclick={on_click}>
+                                if note.id.is_some() {
+                                    <input
+                                        type="checkbox"
+                                        class="note-select"
+                                        checked={is_selected}
+                                        onclick={on_check}
+                                    />
+                                }
+                                if note.id.is_some() && !showing_trash {
+                                    <div class="note-flags">
+                                        { flag_button(
+                                            NoteFlags { pinned: Some(!note.pinned), ..NoteFlags::default() },
+                                            note.pinned,
+                                            "📌",
+                                            if note.pinned { "Unpin" } else { "Pin" },
+                                        ) }
+                                        { flag_button(
+                                            NoteFlags { favorite: Some(!note.favorite), ..NoteFlags::default() },
+                                            note.favorite,
+                                            "★",
+                                            if note.favorite { "Remove from favorites" } else { "Add to favorites" },
+                                        ) }
+                                        { flag_button(
+                                            NoteFlags { archived: Some(!note.archived), ..NoteFlags::default() },
+                                            note.archived,
+                                            "🗄",
+                                            if note.archived { "Unarchive" } else { "Archive" },
+                                        ) }
+                                    </div>
+                                }
+                                <h3 class="note-title">
+                                    if note.is_encrypted() {
+                                        <span class="encrypted-badge">{ "🔒 " }</span>
+                                    }
+                                    { &note.title }
+                                </h3>
+                                <p class="note-date">
+                                    { format!("Updated: {}", note.updated_at.format("%Y-%m-%d %H:%M")) }
+                                </p>
+                                if let Some(due_at) = note.due_at {
+                                    <p class="note-due">
+                                        { format!("Due: {}", due_at.format("%Y-%m-%d %H:%M")) }
+                                    </p>
+                                }
+                            </div>
+                        }
+                    }) }
+                }
+            </div>
+        </div>
+    }
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use chrono::{DateTime, Duration, Utc};
+use cosmiqnotz_core::{NewShareLink, ShareLink};
+
+#[derive(Properties, PartialEq)]
+pub struct SharePanelProps {
+    pub links: Vec<ShareLink>,
+    // The link just created; it can't be shown again once dismissed
+    pub new_url: Option<String>,
+    pub on_create: Callback<NewShareLink>,
+    pub on_revoke: Callback<ShareLink>,
+    pub on_dismiss_url: Callback<()>,
+}
+
+fn format_time(at: Option<DateTime<Utc>>, none: &str) -> String {
+    at.map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| none.to_string())
+}
+
+fn link_status(link: &ShareLink) -> &'static str {
+    if link.revoked_at.is_some() {
+        "Revoked"
+    } else if !link.is_active(Utc::now()) {
+        "Expired"
+    } else {
+        "Active"
+    }
+}
+
+#[function_component(SharePanel)]
+pub fn share_panel(props: &SharePanelProps) -> Html {
+    let expires_in_days = use_state(|| "7".to_string());
+    let password = use_state(String::new);
+
+    let on_expiry = {
+        let expires_in_days = expires_in_days.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            expires_in_days.set(select.value());
+        })
+    };
+
+    let on_password = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            password.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let expires_in_days = expires_in_days.clone();
+        let password = password.clone();
+        let on_create = props.on_create.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            on_create.emit(NewShareLink {
+                expires_at: expires_in_days.parse::<i64>()
+                    .ok()
+                    .map(|days| Utc::now() + Duration::days(days)),
+                password: Some((*password).clone()).filter(|password| !password.is_empty()),
+            });
+            password.set(String::new());
+        })
+    };
+
+    let on_dismiss = {
+        let on_dismiss_url = props.on_dismiss_url.clone();
+        Callback::from(move |_| {
+            on_dismiss_url.emit(());
+        })
+    };
+
+    html! {
+        <div class="share-panel">
+            <div class="share-panel-header">
+                <h3>{ "Public links" }</h3>
+            </div>
+            if let Some(url) = &props.new_url {
+                <div class="share-url">
+                    <p>{ "Anyone with this link can read the note. Copy it now; it won't be shown again." }</p>
+                    <code>{ url }</code>
+                    <button class="share-button" onclick={on_dismiss}>{ "Done" }</button>
+                </div>
+            }
+            <form class="share-form" onsubmit={on_submit}>
+                <select onchange={on_expiry}>
+                    <option value="1" selected={*expires_in_days == "1"}>{ "1 day" }</option>
+                    <option value="7" selected={*expires_in_days == "7"}>{ "7 days" }</option>
+                    <option value="30" selected={*expires_in_days == "30"}>{ "30 days" }</option>
+                    <option value="never" selected={*expires_in_days == "never"}>{ "Never expires" }</option>
+                </select>
+                <input
+                    type="password"
+                    placeholder="Password (optional)"
+                    value={(*password).clone()}
+                    oninput={on_password}
+                />
+                <button type="submit" class="share-button">{ "Create link" }</button>
+            </form>
+            <div class="share-items">
+                if props.links.is_empty() {
+                    <div class="empty-list">
+                        <p>{ "Not shared publicly." }</p>
+                    </div>
+                } else {
+                    { for props.links.iter().map(|link| {
+                        let status = link_status(link);
+                        let on_revoke = {
+                            let link = link.clone();
+                            let on_revoke = props.on_revoke.clone();
+                            Callback::from(move |_| {
+                                on_revoke.emit(link.clone());
+                            })
+                        };
+
+                        html! {
+                            <div class={classes!("share-item", (status != "Active").then(|| "inactive"))}>
+                                <div class="share-name">
+                                    <code>{ format!("{}…", link.prefix) }</code>
+                                    if link.password_protected {
+                                        <span class="share-protected">{ "Password" }</span>
+                                    }
+                                    <span class="share-status">{ status }</span>
+                                </div>
+                                <div class="share-meta">
+                                    { format!(
+                                        "Created {} · Expires {} · {} views · Last viewed {}",
+                                        format_time(Some(link.created_at), ""),
+                                        format_time(link.expires_at, "never"),
+                                        link.views,
+                                        format_time(link.last_viewed_at, "never"),
+                                    ) }
+                                </div>
+                                if link.revoked_at.is_none() {
+                                    <button class="share-button" onclick={on_revoke}>{ "Revoke" }</button>
+                                }
+                            </div>
+                        }
+                    }) }
+                }
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,89 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use cosmiqnotz_core::{SignIn, SignUp};
+
+#[derive(Properties, PartialEq)]
+pub struct SignInFormProps {
+    pub on_sign_in: Callback<SignIn>,
+    pub on_sign_up: Callback<SignUp>,
+    pub is_submitting: bool,
+}
+
+#[function_component(SignInForm)]
+pub fn sign_in_form(props: &SignInFormProps) -> Html {
+    let username = use_state(String::new);
+    let password = use_state(String::new);
+    // Creating an account rather than signing in to one
+    let signing_up = use_state(|| false);
+
+    let on_username = {
+        let username = username.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            username.set(input.value());
+        })
+    };
+
+    let on_password = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            password.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let username = username.clone();
+        let password = password.clone();
+        let signing_up = signing_up.clone();
+        let on_sign_in = props.on_sign_in.clone();
+        let on_sign_up = props.on_sign_up.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let username = username.trim().to_string();
+            if username.is_empty() || password.is_empty() {
+                return;
+            }
+            if *signing_up {
+                on_sign_up.emit(SignUp { username, password: (*password).clone(), email: None, display_name: None });
+            } else {
+                on_sign_in.emit(SignIn { username, password: (*password).clone() });
+            }
+        })
+    };
+
+    let on_toggle_mode = {
+        let signing_up = signing_up.clone();
+        Callback::from(move |_| {
+            signing_up.set(!*signing_up);
+        })
+    };
+
+    html! {
+        <div class="sign-in">
+            <h1>{ "CosmiqNotz" }</h1>
+            <form class="sign-in-form" onsubmit={on_submit}>
+                <input
+                    type="text"
+                    placeholder="Username"
+                    autocomplete="username"
+                    value={(*username).clone()}
+                    oninput={on_username}
+                />
+                <input
+                    type="password"
+                    placeholder="Password"
+                    autocomplete={if *signing_up { "new-password" } else { "current-password" }}
+                    value={(*password).clone()}
+                    oninput={on_password}
+                />
+                <button type="submit" class="sign-in-button" disabled={props.is_submitting}>
+                    { if *signing_up { "Create account" } else { "Sign in" } }
+                </button>
+            </form>
+            <button class="sign-in-toggle" onclick={on_toggle_mode}>
+                { if *signing_up { "Have an account? Sign in" } else { "New here? Create an account" } }
+            </button>
+        </div>
+    }
+}
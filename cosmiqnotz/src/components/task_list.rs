@@ -0,0 +1,69 @@
+use yew::prelude::*;
+use web_sys::HtmlSelectElement;
+use cosmiqnotz_core::{NoteId, Task};
+
+#[derive(Properties, PartialEq)]
+pub struct TaskListProps {
+    pub tasks: Vec<Task>,
+    pub filter: String,
+    pub on_filter_change: Callback<String>,
+    pub on_toggle: Callback<Task>,
+    pub on_open_note: Callback<NoteId>,
+}
+
+#[function_component(TaskList)]
+pub fn task_list(props: &TaskListProps) -> Html {
+    let on_filter_change = {
+        let on_filter_change = props.on_filter_change.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            on_filter_change.emit(select.value());
+        })
+    };
+
+    html! {
+        <div class="task-list">
+            <div class="task-list-header">
+                <h2>{ "Tasks" }</h2>
+                <select class="task-filter" onchange={on_filter_change}>
+                    <option value="open" selected={props.filter == "open"}>{ "Open" }</option>
+                    <option value="done" selected={props.filter == "done"}>{ "Done" }</option>
+                    <option value="all" selected={props.filter == "all"}>{ "All" }</option>
+                </select>
+            </div>
+            <div class="task-list-items">
+                if props.tasks.is_empty() {
+                    <div class="empty-list">
+                        <p>{ "No tasks. Add `- [ ]` items to a note to track them here." }</p>
+                    </div>
+                } else {
+                    { for props.tasks.iter().map(|task| {
+                        let on_toggle = {
+                            let task = task.clone();
+                            let on_toggle = props.on_toggle.clone();
+                            Callback::from(move |_| {
+                                on_toggle.emit(task.clone());
+                            })
+                        };
+
+                        let on_open = {
+                            let note_id = task.note_id.clone();
+                            let on_open_note = props.on_open_note.clone();
+                            Callback::from(move |_| {
+                                on_open_note.emit(note_id.clone());
+                            })
+                        };
+
+                        html! {
+                            <div class={classes!("task-item", task.done.then(|| "done"))}>
+                                <input type="checkbox" checked={task.done} onchange={on_toggle} />
+                                <span class="task-text">{ &task.text }</span>
+                                <button class="task-note-link" onclick={on_open}>{ &task.note_title }</button>
+                            </div>
+                        }
+                    }) }
+                }
+            </div>
+        </div>
+    }
+}
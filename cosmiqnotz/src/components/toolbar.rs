@@ -1,10 +1,26 @@
 use yew::prelude::*;
+use web_sys::HtmlSelectElement;
+use cosmiqnotz_core::{UserWorkspace, WorkspaceId};
 
 #[derive(Properties, PartialEq)]
 pub struct ToolbarProps {
     pub on_sync: Callback<()>,
+    pub on_toggle_tasks: Callback<()>,
+    pub showing_tasks: bool,
+    pub on_toggle_keys: Callback<()>,
+    pub showing_keys: bool,
+    pub is_unlocked: bool,
+    pub on_unlock: Callback<()>,
+    pub on_lock: Callback<()>,
+    pub workspaces: Vec<UserWorkspace>,
+    pub current_workspace: WorkspaceId,
+    pub on_switch_workspace: Callback<WorkspaceId>,
+    pub on_create_workspace: Callback<()>,
     pub is_syncing: bool,
     pub is_online: bool,
+    // Who is signed in
+    pub user: String,
+    pub on_sign_out: Callback<()>,
 }
 
 #[function_component(Toolbar)]
@@ -16,15 +32,95 @@ pub fn toolbar(props: &ToolbarProps) -> Html {
         })
     };
 
+    let on_tasks_click = {
+        let on_toggle_tasks = props.on_toggle_tasks.clone();
+        Callback::from(move |_| {
+            on_toggle_tasks.emit(());
+        })
+    };
+
+    let on_keys_click = {
+        let on_toggle_keys = props.on_toggle_keys.clone();
+        Callback::from(move |_| {
+            on_toggle_keys.emit(());
+        })
+    };
+
+    let on_workspace_change = {
+        let on_switch_workspace = props.on_switch_workspace.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            on_switch_workspace.emit(select.value().into());
+        })
+    };
+
+    let on_new_workspace_click = {
+        let on_create_workspace = props.on_create_workspace.clone();
+        Callback::from(move |_| {
+            on_create_workspace.emit(());
+        })
+    };
+
+    let on_lock_click = {
+        let on_unlock = props.on_unlock.clone();
+        let on_lock = props.on_lock.clone();
+        let is_unlocked = props.is_unlocked;
+        Callback::from(move |_| {
+            if is_unlocked {
+                on_lock.emit(());
+            } else {
+                on_unlock.emit(());
+            }
+        })
+    };
+
+    let on_sign_out_click = {
+        let on_sign_out = props.on_sign_out.clone();
+        Callback::from(move |_| {
+            on_sign_out.emit(());
+        })
+    };
+
     html! {
         <div class="toolbar">
             <div class="logo">
                 <h1>{ "CosmiqNotz" }</h1>
             </div>
+            <div class="workspace-switcher">
+                <select class="workspace-select" onchange={on_workspace_change}>
+                    { for props.workspaces.iter().map(|workspace| {
+                        let id = workspace.workspace.id.clone().unwrap_or_default();
+                        html! {
+                            <option value={id.to_string()} selected={id == props.current_workspace}>
+                                { &workspace.workspace.name }
+                            </option>
+                        }
+                    }) }
+                </select>
+                <button class="workspace-button" title="New workspace" onclick={on_new_workspace_click}>{ "+" }</button>
+            </div>
             <div class="actions">
                 <div class={classes!("status-indicator", if props.is_online { "online" } else { "offline" })}>
                     { if props.is_online { "Online" } else { "Offline" } }
                 </div>
+                <button
+                    onclick={on_lock_click}
+                    class={classes!("lock-button", props.is_unlocked.then(|| "active"))}
+                >
+                    { if props.is_unlocked { "Lock" } else { "Unlock" } }
+                </button>
+                <button
+                    onclick={on_tasks_click}
+                    class={classes!("tasks-button", props.showing_tasks.then(|| "active"))}
+                >
+                    { if props.showing_tasks { "Notes" } else { "Tasks" } }
+                </button>
+                <button
+                    onclick={on_keys_click}
+                    class={classes!("keys-button", props.showing_keys.then(|| "active"))}
+                >
+                    { if props.showing_keys { "Notes" } else { "API Keys" } }
+                </button>
                 <button 
                     onclick={on_sync_click}
                     disabled={props.is_syncing || !props.is_online}
@@ -32,6 +128,8 @@ pub fn toolbar(props: &ToolbarProps) -> Html {
                 >
                     { if props.is_syncing { "Syncing..." } else { "Sync" } }
                 </button>
+                <span class="signed-in-user">{ &props.user }</span>
+                <button onclick={on_sign_out_click} class="sign-out-button">{ "Sign out" }</button>
             </div>
         </div>
     }
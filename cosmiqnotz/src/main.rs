@@ -1,7 +1,6 @@
 use yew::prelude::*;
 
 mod components;
-mod models;
 mod services;
 mod app;
 
@@ -1,3 +0,0 @@
-pub mod note;
-
-pub use note::Note;
\ No newline at end of file
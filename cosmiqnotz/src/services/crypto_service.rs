@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+
+use cosmiqnotz_core::{Note, NoteEncryption};
+
+const ALGORITHM: &str = "AES-256-GCM";
+const KDF: &str = "PBKDF2-HMAC-SHA256";
+const ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, String> {
+    BASE64.decode(value).map_err(|e| format!("Invalid encrypted data: {}", e))
+}
+
+// Holds the passphrase for this session only; it is never stored or sent anywhere.
+// Derived keys are cached per salt because key derivation is deliberately slow.
+pub struct KeyRing {
+    passphrase: String,
+    keys: HashMap<(String, u32), [u8; 32]>,
+    salt: Option<String>,
+}
+
+impl KeyRing {
+    pub fn new(passphrase: String) -> Self {
+        Self {
+            passphrase,
+            keys: HashMap::new(),
+            salt: None,
+        }
+    }
+
+    fn cipher(&mut self, salt: &str, iterations: u32) -> Result<Aes256Gcm, String> {
+        let cache_key = (salt.to_string(), iterations);
+        if !self.keys.contains_key(&cache_key) {
+            let mut key = [0u8; 32];
+            pbkdf2_hmac::<Sha256>(self.passphrase.as_bytes(), &decode(salt)?, iterations, &mut key);
+            self.keys.insert(cache_key.clone(), key);
+        }
+
+        Aes256Gcm::new_from_slice(&self.keys[&cache_key])
+            .map_err(|_| "Invalid encryption key".to_string())
+    }
+
+    // Reuse one salt for everything sealed in this session so only one key is derived
+    fn session_salt(&mut self) -> Result<String, String> {
+        if self.salt.is_none() {
+            self.salt = Some(BASE64.encode(random_bytes::<SALT_LEN>()?));
+        }
+        Ok(self.salt.clone().unwrap_or_default())
+    }
+
+    // Parameters marking a plaintext note as one to encrypt when it is saved
+    pub fn parameters(&mut self) -> Result<NoteEncryption, String> {
+        Ok(NoteEncryption {
+            algorithm: ALGORITHM.to_string(),
+            kdf: KDF.to_string(),
+            iterations: ITERATIONS,
+            salt: self.session_salt()?,
+            title_nonce: String::new(),
+            content_nonce: String::new(),
+        })
+    }
+
+    fn seal(&mut self, salt: &str, plaintext: &str) -> Result<(String, String), String> {
+        let nonce = random_bytes::<NONCE_LEN>()?;
+        let ciphertext = self.cipher(salt, ITERATIONS)?
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| "Failed to encrypt note".to_string())?;
+        Ok((BASE64.encode(ciphertext), BASE64.encode(nonce)))
+    }
+
+    fn open(&mut self, encryption: &NoteEncryption, ciphertext: &str, nonce: &str) -> Result<String, String> {
+        let nonce = decode(nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err("Invalid encrypted data: bad nonce".to_string());
+        }
+
+        let plaintext = self.cipher(&encryption.salt, encryption.iterations)?
+            .decrypt(Nonce::from_slice(&nonce), decode(ciphertext)?.as_ref())
+            .map_err(|_| "Wrong passphrase or corrupted note".to_string())?;
+        String::from_utf8(plaintext).map_err(|_| "Decrypted note is not valid text".to_string())
+    }
+
+    // Encrypt the title and content of a plaintext note marked for encryption
+    pub fn encrypt(&mut self, note: &Note) -> Result<Note, String> {
+        let salt = self.session_salt()?;
+        let (title, title_nonce) = self.seal(&salt, &note.title)?;
+        let (content, content_nonce) = self.seal(&salt, &note.content)?;
+
+        let mut sealed = note.clone();
+        sealed.title = title;
+        sealed.content = content;
+        sealed.encryption = Some(NoteEncryption {
+            algorithm: ALGORITHM.to_string(),
+            kdf: KDF.to_string(),
+            iterations: ITERATIONS,
+            salt,
+            title_nonce,
+            content_nonce,
+        });
+        Ok(sealed)
+    }
+
+    // Decrypt an encrypted note; the result keeps its parameters so it is re-encrypted on save
+    pub fn decrypt(&mut self, note: &Note) -> Result<Note, String> {
+        let encryption = note.encryption.clone()
+            .ok_or_else(|| "Note is not encrypted".to_string())?;
+        if encryption.algorithm != ALGORITHM || encryption.kdf != KDF {
+            return Err(format!("Unsupported encryption: {} / {}", encryption.algorithm, encryption.kdf));
+        }
+
+        let mut opened = note.clone();
+        opened.title = self.open(&encryption, &note.title, &encryption.title_nonce)?;
+        opened.content = self.open(&encryption, &note.content, &encryption.content_nonce)?;
+
+        // Adopt the salt of existing notes so new notes share the cached key
+        if self.salt.is_none() && encryption.iterations == ITERATIONS {
+            self.salt = Some(encryption.salt.clone());
+        }
+        Ok(opened)
+    }
+}
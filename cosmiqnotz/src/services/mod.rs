@@ -1 +1,2 @@
+pub mod crypto_service;
 pub mod note_service;
\ No newline at end of file
@@ -0,0 +1,876 @@
+use cosmiqnotz_core::{ApiKey, BatchOperation, BatchReport, BatchRequest, Comment, CreatedApiKey, CreatedShareLink, NewApiKey, NewComment, NewShareLink, NewWorkspace, Note, NoteFlags, NoteLock, SessionToken, ShareLink, SignIn, SignUp, Task, UserWorkspace, WorkspaceId};
+use chrono::Utc;
+use gloo_net::http::Request;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::console;
+
+// For API requests
+const API_BASE: &str = "http://localhost:8000/api";
+
+// Sent with every API request; the server logs it, so errors shown in the UI can be
+// matched to the server's log entries
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// Which workspace to act in; the server scopes every note request to the workspace,
+// falling back to the user's personal one when none is given
+const WORKSPACE_HEADER: &str = "X-Cosmiq-Workspace";
+
+// localStorage key remembering the workspace last switched to
+const WORKSPACE_KEY: &str = "current_workspace";
+
+// localStorage key holding the session token from signing in
+const SESSION_KEY: &str = "session";
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// The signed-in session, unless it has run out
+pub fn current_session() -> Option<SessionToken> {
+    local_storage()
+        .and_then(|storage| storage.get_item(SESSION_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<SessionToken>(&json).ok())
+        .filter(|session| !session.is_expired(Utc::now()))
+}
+
+// Who is signed in; empty when nobody is
+pub fn current_user() -> String {
+    current_session().map(|session| session.user).unwrap_or_default()
+}
+
+// Identifies the user to the server on every request
+fn authorization() -> String {
+    format!("Bearer {}", current_session().map(|session| session.token).unwrap_or_default())
+}
+
+// The desktop shell calls the API itself for exports, backups and reminders, so it
+// needs the session too. In a browser there is no shell and nothing to do.
+pub fn share_session_with_desktop() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let invoke = js_sys::Reflect::get(&window, &JsValue::from_str("__TAURI__"))
+        .ok()
+        .filter(|tauri| tauri.is_object())
+        .and_then(|tauri| js_sys::Reflect::get(&tauri, &JsValue::from_str("invoke")).ok())
+        .and_then(|invoke| invoke.dyn_into::<js_sys::Function>().ok());
+    let Some(invoke) = invoke else {
+        return;
+    };
+
+    let token = current_session().map(|session| JsValue::from_str(&session.token)).unwrap_or(JsValue::NULL);
+    let args = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&args, &JsValue::from_str("token"), &token);
+    if let Err(e) = invoke.call2(&JsValue::NULL, &JsValue::from_str("set_session"), &args) {
+        console::log_1(&e);
+    }
+}
+
+fn save_session(session: &SessionToken) -> Result<(), String> {
+    let storage = local_storage().ok_or_else(|| "localStorage not available".to_string())?;
+    let json = serde_json::to_string(session).map_err(|e| format!("Failed to serialize session: {}", e))?;
+    storage.set_item(SESSION_KEY, &json).map_err(|_| "Failed to save session".to_string())?;
+    share_session_with_desktop();
+    Ok(())
+}
+
+pub fn sign_out() {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(SESSION_KEY);
+        let _ = storage.remove_item(WORKSPACE_KEY);
+    }
+    share_session_with_desktop();
+}
+
+pub async fn sign_in(credentials: &SignIn) -> Result<SessionToken, String> {
+    start_session("signin", credentials).await
+}
+
+pub async fn sign_up(account: &SignUp) -> Result<SessionToken, String> {
+    start_session("signup", account).await
+}
+
+async fn start_session(endpoint: &str, body: &impl serde::Serialize) -> Result<SessionToken, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/auth/{}", API_BASE, endpoint))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .json(body)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<SessionToken>().await {
+                    Ok(session) => {
+                        save_session(&session)?;
+                        Ok(session)
+                    },
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else if response.status() == 401 {
+                Err("Wrong username or password".to_string())
+            } else {
+                Err(format!("Failed to sign in: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+// The workspace requests act in; empty means the personal workspace
+pub fn current_workspace() -> WorkspaceId {
+    local_storage()
+        .and_then(|storage| storage.get_item(WORKSPACE_KEY).ok().flatten())
+        .map(WorkspaceId::from)
+        .unwrap_or_default()
+}
+
+pub fn set_current_workspace(id: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(WORKSPACE_KEY, id);
+    }
+}
+
+fn new_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    if getrandom::getrandom(&mut bytes).is_err() {
+        return format!("{:x}", js_sys::Date::now() as u64);
+    }
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Remote API calls
+pub async fn get_notes() -> Result<Vec<Note>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/notes", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await 
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<Note>>().await {
+                    Ok(notes) => Ok(notes),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get notes: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+// Notes in one of the list views: "pinned", "favorites", "archived" or "trash"
+pub async fn get_notes_in_view(view: &str) -> Result<Vec<Note>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/notes?view={}", API_BASE, view))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<Note>>().await {
+                    Ok(notes) => Ok(notes),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get {} notes: HTTP {} (request {})", view, response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn batch_notes(operations: Vec<BatchOperation>) -> Result<BatchReport, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/notes/batch", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(&BatchRequest { operations })
+        .map_err(|e| format!("Failed to serialize batch: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<BatchReport>().await {
+                    Ok(report) => Ok(report),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to update notes: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn get_api_keys() -> Result<Vec<ApiKey>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/keys", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<ApiKey>>().await {
+                    Ok(keys) => Ok(keys),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get API keys: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn create_api_key(key: &NewApiKey) -> Result<CreatedApiKey, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/keys", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(key)
+        .map_err(|e| format!("Failed to serialize API key: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<CreatedApiKey>().await {
+                    Ok(created) => Ok(created),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to create API key: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn revoke_api_key(key: &ApiKey) -> Result<ApiKey, String> {
+    let id = match &key.id {
+        Some(id) => id.clone(),
+        None => return Err("API key ID is missing".to_string())
+    };
+
+    let request_id = new_request_id();
+    match Request::delete(&format!("{}/keys/{}", API_BASE, id))
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<ApiKey>().await {
+                    Ok(key) => Ok(key),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to revoke API key: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn get_workspaces() -> Result<Vec<UserWorkspace>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/workspaces", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<UserWorkspace>>().await {
+                    Ok(workspaces) => Ok(workspaces),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get workspaces: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn create_workspace(name: &str) -> Result<UserWorkspace, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/workspaces", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .json(&NewWorkspace { name: name.to_string() })
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<UserWorkspace>().await {
+                    Ok(workspace) => Ok(workspace),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to create workspace: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn create_note(note: &Note) -> Result<Note, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/notes", API_BASE))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(note)
+        .map_err(|e| format!("Failed to serialize note: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 || response.status() == 201 {
+                match response.json::<Note>().await {
+                    Ok(note) => Ok(note),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to create note: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn update_note(note: &Note) -> Result<Note, String> {
+    let id = match &note.id {
+        Some(id) => id.clone(),
+        None => return Err("Note ID is missing".to_string())
+    };
+
+    // Queued offline edits may belong to a workspace other than the current one
+    let workspace = note.workspace_id.clone().unwrap_or_else(current_workspace);
+    let request_id = new_request_id();
+    match Request::put(&format!("{}/notes/{}", API_BASE, id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &workspace)
+        .json(note)
+        .map_err(|e| format!("Failed to serialize note: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Note>().await {
+                    Ok(note) => Ok(note),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else if response.status() == 423 {
+                Err(format!("Someone else is editing this note; try again once they're done (request {})", request_id))
+            } else {
+                Err(format!("Failed to update note: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn set_note_flags(note_id: &str, flags: &NoteFlags) -> Result<Note, String> {
+    let request_id = new_request_id();
+    match Request::put(&format!("{}/notes/{}/flags", API_BASE, note_id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(flags)
+        .map_err(|e| format!("Failed to serialize flags: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Note>().await {
+                    Ok(note) => Ok(note),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to update note flags: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn get_tasks(status: &str) -> Result<Vec<Task>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/tasks?status={}", API_BASE, status))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<Task>>().await {
+                    Ok(tasks) => Ok(tasks),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get tasks: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn set_task_done(task: &Task, done: bool) -> Result<Task, String> {
+    let id = match &task.id {
+        Some(id) => id.clone(),
+        None => return Err("Task ID is missing".to_string())
+    };
+
+    let request_id = new_request_id();
+    match Request::put(&format!("{}/tasks/{}", API_BASE, id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(&serde_json::json!({ "done": done }))
+        .map_err(|e| format!("Failed to serialize task: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Task>().await {
+                    Ok(task) => Ok(task),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else if response.status() == 409 {
+                Err(format!("The note changed since this task was loaded; refresh and try again (request {})", request_id))
+            } else {
+                Err(format!("Failed to update task: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+// The saved version of a note rendered to sanitized HTML by the server
+pub async fn get_note_html(note_id: &str) -> Result<String, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/notes/{}/html", API_BASE, note_id))
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.text().await {
+                    Ok(html) => Ok(html),
+                    Err(e) => Err(format!("Failed to read response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to render note: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+// Share links are served by the API server itself, outside `/api`
+pub fn share_url(path: &str) -> String {
+    format!("{}{}", API_BASE.trim_end_matches("/api"), path)
+}
+
+pub async fn get_share_links(note_id: &str) -> Result<Vec<ShareLink>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/notes/{}/shares", API_BASE, note_id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<ShareLink>>().await {
+                    Ok(links) => Ok(links),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get share links: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn create_share_link(note_id: &str, link: &NewShareLink) -> Result<CreatedShareLink, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/notes/{}/shares", API_BASE, note_id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(link)
+        .map_err(|e| format!("Failed to serialize share link: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<CreatedShareLink>().await {
+                    Ok(created) => Ok(created),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else if response.status() == 422 {
+                Err(format!("Encrypted notes and notes in the trash can't be shared (request {})", request_id))
+            } else {
+                Err(format!("Failed to create share link: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn revoke_share_link(link: &ShareLink) -> Result<ShareLink, String> {
+    let id = match &link.id {
+        Some(id) => id.clone(),
+        None => return Err("Share link ID is missing".to_string())
+    };
+
+    let request_id = new_request_id();
+    match Request::delete(&format!("{}/shares/{}", API_BASE, id))
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<ShareLink>().await {
+                    Ok(link) => Ok(link),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to revoke share link: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn get_comments(note_id: &str) -> Result<Vec<Comment>, String> {
+    let request_id = new_request_id();
+    match Request::get(&format!("{}/notes/{}/comments", API_BASE, note_id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Vec<Comment>>().await {
+                    Ok(comments) => Ok(comments),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to get comments: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn add_comment(note_id: &str, comment: &NewComment) -> Result<Comment, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/notes/{}/comments", API_BASE, note_id))
+        .header("Content-Type", "application/json")
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .json(comment)
+        .map_err(|e| format!("Failed to serialize comment: {}", e))?
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 || response.status() == 201 {
+                match response.json::<Comment>().await {
+                    Ok(comment) => Ok(comment),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to add comment: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn set_comment_resolved(comment: &Comment, resolved: bool) -> Result<Comment, String> {
+    let id = match &comment.id {
+        Some(id) => id.clone(),
+        None => return Err("Comment ID is missing".to_string())
+    };
+
+    let request_id = new_request_id();
+    let request = if resolved {
+        Request::post(&format!("{}/comments/{}/resolve", API_BASE, id))
+            .header(REQUEST_ID_HEADER, &request_id)
+            .header("Authorization", &authorization())
+            .header(WORKSPACE_HEADER, &current_workspace())
+    } else {
+        Request::post(&format!("{}/comments/{}/unresolve", API_BASE, id))
+            .header("Content-Type", "application/json")
+            .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+    };
+
+    match request.send().await {
+        Ok(response) => {
+            if response.status() == 200 {
+                match response.json::<Comment>().await {
+                    Ok(comment) => Ok(comment),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to update comment: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+// Local storage for offline functionality
+pub fn save_note_locally(note: &Note) -> Result<(), String> {
+    let storage = web_sys::window()
+        .ok_or_else(|| "No window found".to_string())?
+        .local_storage()
+        .map_err(|_| "Failed to access localStorage".to_string())?
+        .ok_or_else(|| "localStorage not available".to_string())?;
+
+    let key = match &note.id {
+        Some(id) => format!("note_{}", id),
+        None => format!("note_draft_{}", js_sys::Date::now())
+    };
+
+    let json = serde_json::to_string(note)
+        .map_err(|e| format!("Failed to serialize note: {}", e))?;
+
+    storage.set_item(&key, &json)
+        .map_err(|_| "Failed to save note to localStorage".to_string())?;
+
+    // Add to sync queue if we have an ID
+    if note.id.is_some() {
+        add_to_sync_queue(&key)?;
+    }
+
+    Ok(())
+}
+
+pub fn get_local_notes() -> Result<Vec<Note>, String> {
+    let storage = web_sys::window()
+        .ok_or_else(|| "No window found".to_string())?
+        .local_storage()
+        .map_err(|_| "Failed to access localStorage".to_string())?
+        .ok_or_else(|| "localStorage not available".to_string())?;
+
+    let mut notes = Vec::new();
+
+    for i in 0..storage.length().map_err(|_| "Failed to get storage length".to_string())? {
+        let key = storage.key(i).map_err(|_| "Failed to get key from storage".to_string())?
+            .ok_or_else(|| "No key found".to_string())?;
+
+        if key.starts_with("note_") {
+            if let Some(json) = storage.get_item(&key).map_err(|_| "Failed to get item from storage".to_string())? {
+                match serde_json::from_str::<Note>(&json) {
+                    Ok(note) => notes.push(note),
+                    Err(e) => console::log_1(&format!("Failed to parse note: {}", e).into())
+                }
+            }
+        }
+    }
+
+    // Sort by updated_at
+    notes.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    
+    Ok(notes)
+}
+
+fn add_to_sync_queue(key: &str) -> Result<(), String> {
+    let storage = web_sys::window()
+        .ok_or_else(|| "No window found".to_string())?
+        .local_storage()
+        .map_err(|_| "Failed to access localStorage".to_string())?
+        .ok_or_else(|| "localStorage not available".to_string())?;
+
+    let queue = match storage.get_item("sync_queue").map_err(|_| "Failed to get sync queue".to_string())? {
+        Some(json) => {
+            let mut queue: Vec<String> = serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse sync queue: {}", e))?;
+            
+            if !queue.contains(&key.to_string()) {
+                queue.push(key.to_string());
+            }
+            
+            queue
+        },
+        None => vec![key.to_string()]
+    };
+
+    let json = serde_json::to_string(&queue)
+        .map_err(|e| format!("Failed to serialize sync queue: {}", e))?;
+
+    storage.set_item("sync_queue", &json)
+        .map_err(|_| "Failed to save sync queue to localStorage".to_string())?;
+
+    Ok(())
+}
+
+pub async fn process_sync_queue() -> Result<(), String> {
+    let storage = web_sys::window()
+        .ok_or_else(|| "No window found".to_string())?
+        .local_storage()
+        .map_err(|_| "Failed to access localStorage".to_string())?
+        .ok_or_else(|| "localStorage not available".to_string())?;
+
+    let queue = match storage.get_item("sync_queue").map_err(|_| "Failed to get sync queue".to_string())? {
+        Some(json) => {
+            serde_json::from_str::<Vec<String>>(&json)
+                .map_err(|e| format!("Failed to parse sync queue: {}", e))?
+        },
+        None => return Ok(()) // No queue, nothing to sync
+    };
+
+    if queue.is_empty() {
+        return Ok(());
+    }
+
+    let mut new_queue = Vec::new();
+
+    for key in queue {
+        let json = match storage.get_item(&key).map_err(|_| "Failed to get item from storage".to_string())? {
+            Some(json) => json,
+            None => {
+                // Item doesn't exist anymore, skip it
+                continue;
+            }
+        };
+
+        let note: Note = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse note: {}", e))?;
+
+        // Try to sync the note
+        match update_note(&note).await {
+            Ok(_) => {
+                // Successfully synced, remove from queue
+                console::log_1(&format!("Successfully synced note: {}", note.id.as_deref().unwrap_or("unknown")).into());
+            },
+            Err(e) => {
+                // Failed to sync, keep in queue
+                console::log_1(&format!("Failed to sync note: {}", e).into());
+                new_queue.push(key);
+            }
+        }
+    }
+
+    // Update the queue
+    let json = serde_json::to_string(&new_queue)
+        .map_err(|e| format!("Failed to serialize sync queue: {}", e))?;
+
+    storage.set_item("sync_queue", &json)
+        .map_err(|_| "Failed to save sync queue to localStorage".to_string())?;
+
+    Ok(())
+}
+
+// Take or renew the note's edit lock. While someone else holds it the server answers
+// `409` with their lock, which is returned here too; compare `locked_by` to tell.
+pub async fn acquire_note_lock(note_id: &str) -> Result<NoteLock, String> {
+    let request_id = new_request_id();
+    match Request::post(&format!("{}/notes/{}/lock", API_BASE, note_id))
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 200 || response.status() == 409 {
+                match response.json::<NoteLock>().await {
+                    Ok(lock) => Ok(lock),
+                    Err(e) => Err(format!("Failed to parse response: {} (request {})", e, request_id))
+                }
+            } else {
+                Err(format!("Failed to lock note: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub async fn release_note_lock(note_id: &str) -> Result<(), String> {
+    let request_id = new_request_id();
+    match Request::delete(&format!("{}/notes/{}/lock", API_BASE, note_id))
+        .header(REQUEST_ID_HEADER, &request_id)
+        .header("Authorization", &authorization())
+        .header(WORKSPACE_HEADER, &current_workspace())
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status() == 204 {
+                Ok(())
+            } else {
+                Err(format!("Failed to unlock note: HTTP {} (request {})", response.status(), request_id))
+            }
+        },
+        Err(e) => Err(format!("Network error: {} (request {})", e, request_id))
+    }
+}
+
+pub fn check_online_status() -> bool {
+    web_sys::window()
+        .and_then(|window| Some(window.navigator().online()))
+        .unwrap_or(false)
+}
\ No newline at end of file